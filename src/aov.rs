@@ -0,0 +1,131 @@
+use euler::{DVec2, dvec2, DVec3, dvec3};
+use color::Color;
+use scene::Scene;
+use geometry::NodeIntersect;
+use snowflake::ProcessUniqueId;
+
+// Arbitrary output variables, extra passes rendered alongside the beauty image for
+// compositing, denoising or finding out which node is which. They come from the ray
+// through the middle of each pixel and aren't anti-aliased, so ids and normals stay exact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AovPass {
+    // Distance from the camera along the view direction
+    Depth,
+    // World space surface normal, after normal maps
+    Normal,
+    // The SurfaceCoord of the hit
+    Uv,
+    // Color of the surface without lighting
+    Albedo,
+    // Which node was hit, 0 for the background
+    ObjectId,
+}
+
+impl AovPass {
+    pub const ALL: [AovPass; 5] = [AovPass::Depth, AovPass::Normal, AovPass::Uv, AovPass::Albedo, AovPass::ObjectId];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AovPass::Depth => "depth",
+            AovPass::Normal => "normal",
+            AovPass::Uv => "uv",
+            AovPass::Albedo => "albedo",
+            AovPass::ObjectId => "object_id",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<AovPass> {
+        AovPass::ALL.iter().find(|pass| pass.name() == name).cloned()
+    }
+}
+
+// Which passes to render, none by default
+#[derive(Clone, Copy, PartialEq)]
+pub struct AovConfig {
+    pub depth: bool,
+    pub normal: bool,
+    pub uv: bool,
+    pub albedo: bool,
+    pub object_id: bool,
+}
+
+impl AovConfig {
+    pub fn none() -> AovConfig {
+        AovConfig { depth: false, normal: false, uv: false, albedo: false, object_id: false }
+    }
+
+    pub fn all() -> AovConfig {
+        AovConfig::from_passes(&AovPass::ALL)
+    }
+
+    pub fn from_passes(passes: &[AovPass]) -> AovConfig {
+        let mut config = AovConfig::none();
+        for pass in passes.iter() {
+            config.set(*pass, true);
+        }
+        config
+    }
+
+    pub fn set(&mut self, pass: AovPass, enabled: bool) {
+        match pass {
+            AovPass::Depth => self.depth = enabled,
+            AovPass::Normal => self.normal = enabled,
+            AovPass::Uv => self.uv = enabled,
+            AovPass::Albedo => self.albedo = enabled,
+            AovPass::ObjectId => self.object_id = enabled,
+        }
+    }
+
+    pub fn contains(&self, pass: AovPass) -> bool {
+        match pass {
+            AovPass::Depth => self.depth,
+            AovPass::Normal => self.normal,
+            AovPass::Uv => self.uv,
+            AovPass::Albedo => self.albedo,
+            AovPass::ObjectId => self.object_id,
+        }
+    }
+
+    pub fn passes(&self) -> Vec<AovPass> {
+        AovPass::ALL.iter().cloned().filter(|pass| self.contains(*pass)).collect()
+    }
+
+    pub fn any(&self) -> bool {
+        AovPass::ALL.iter().any(|pass| self.contains(*pass))
+    }
+}
+
+// Everything the AOV passes need to know about what a camera ray hit
+#[derive(Debug, Clone, Copy)]
+pub struct AovSample {
+    pub depth: f64,
+    pub normal: DVec3,
+    pub uv: DVec2,
+    pub albedo: Color,
+    pub hit_id: Option<ProcessUniqueId>,
+}
+
+impl AovSample {
+    pub fn background() -> AovSample {
+        AovSample {
+            depth: f64::INFINITY,
+            normal: dvec3!(0.0, 0.0, 0.0),
+            uv: dvec2!(0.0, 0.0),
+            albedo: Color::BLACK,
+            hit_id: None,
+        }
+    }
+
+    pub fn from_intersect(scene: &Scene, node_intersect: &NodeIntersect, camera_origin: DVec3, view_direction: DVec3) -> AovSample {
+        let intersect = node_intersect.intersect;
+        let shaded = node_intersect.shader.modify_intersect(scene, intersect);
+        let (u, v) = intersect.surface_coord.get_coord();
+        AovSample {
+            depth: (intersect.hit_point - camera_origin).dot(view_direction),
+            normal: shaded.surface_normal.normalize(),
+            uv: dvec2!(u, v),
+            albedo: node_intersect.shader.get_albedo(scene, intersect),
+            hit_id: Some(node_intersect.hit_id),
+        }
+    }
+}
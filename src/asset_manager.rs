@@ -1,7 +1,7 @@
 use texture::*;
 use normal_map::*;
-use image::RgbImage;
-use std::sync::Arc;
+use error::Error;
+use image::{self, DynamicImage};
 
 use std::collections::HashMap;
 
@@ -10,6 +10,11 @@ use std::collections::HashMap;
 // TODO: Should all the path related functions be in here?
 // And all the actual constructors use their respect asset, ie RgbImage
 
+// Opens an image file, the error says which file couldn't be opened
+pub fn open_image(path: &str) -> Result<DynamicImage, Error> {
+    image::open(path).map_err(|cause| Error::image(path, cause))
+}
+
 // Factory for creating assets that benefit from being shared.
 // Anything that involves reading in a large file
 pub struct AssetManager<'a> {
@@ -26,36 +31,36 @@ impl<'a> AssetManager<'a> {
         }
     }
 
-    pub fn image_texture_from_path(&mut self, path: &'a str) -> Box<TextureMappable + Send + Sync> {
+    pub fn image_texture_from_path(&mut self, path: &'a str) -> Result<Box<TextureMappable + Send + Sync>, Error> {
         // Check if cache already has asset
         if let Some(texture) = self.texture_cache.get(path) {
-            return texture.clone()
+            return Ok(texture.clone())
         }
 
-        let texture = ImageTexture::new(image::open(path).unwrap().to_rgb());
+        let texture: Box<dyn TextureMappable + Send + Sync> = ImageTexture::from_path(path)?;
         self.texture_cache.insert(path, texture.clone());
-        texture
+        Ok(texture)
 
     }
 
-    pub fn bump_map_from_path(&mut self, path: &'a str, depth: f64) -> Box<NormalMappable + Send + Sync> {
+    pub fn bump_map_from_path(&mut self, path: &'a str, depth: f64) -> Result<Box<NormalMappable + Send + Sync>, Error> {
         if let Some(bump_map) = self.normal_map_cache.get(path) {
-            return bump_map.clone();
+            return Ok(bump_map.clone());
         }
         
-        let bump_map = BumpMap::new(image::open(path).unwrap().to_luma(), depth);
+        let bump_map: Box<dyn NormalMappable + Send + Sync> = BumpMap::from_path(path, depth)?;
         self.normal_map_cache.insert(path, bump_map.clone());
-        bump_map
+        Ok(bump_map)
     }
 
-    pub fn normal_map_from_path(&mut self, path: &'a str) -> Box<NormalMappable> {
+    pub fn normal_map_from_path(&mut self, path: &'a str) -> Result<Box<NormalMappable>, Error> {
         // Check if cache already has asset
         if let Some(normal_map) = self.normal_map_cache.get(path) {
-            return normal_map.clone();
+            return Ok(normal_map.clone());
         }
 
-        let normal_map = NormalMap::new(image::open(path).unwrap().to_rgb());
+        let normal_map: Box<dyn NormalMappable + Send + Sync> = NormalMap::from_path(path)?;
         self.normal_map_cache.insert(path, normal_map.clone());
-        normal_map
+        Ok(normal_map)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,211 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write, BufWriter};
+use std::path::Path;
+use render::ImageDimension;
+use tile::Tile;
+use filter::{FilterKind, PixelFilter, FilmTile, FilmPixel};
+use error::Error;
+
+const MAGIC: &[u8; 8] = b"RTCHKPT3";
+
+// What rendering a tile worked out, the distance to whatever each of its pixels hit
+// and the filtered samples it took
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedTile {
+    pub tile: Tile,
+    pub distances: Vec<f64>,
+    pub film: FilmTile,
+}
+
+// Tiles that have finished rendering, saved to disk as they come in so a long render can
+// carry on where it left off after being interrupted.
+//
+// The file is a header with the image size, pixel filter and a fingerprint of the scene and settings
+// the tiles were rendered with, see render_fingerprint, followed by one record per finished tile:
+// the tile's x, y, width and height and the distance for each of its pixels, then the same for
+// the region its samples were filtered onto with the fixed point sums of each pixel, see FilmPixel.
+// Everything is little endian. A record cut short by a crash is ignored.
+pub struct Checkpoint {
+    path: String,
+    file: File,
+    tiles: Vec<RenderedTile>,
+    replaced: Option<String>,
+}
+
+impl Checkpoint {
+    // Opens the checkpoint at path, reading any tiles already in it, or starts a new one.
+    // A file that isn't a checkpoint of this render, going by its fingerprint, is moved to
+    // path.old and a new checkpoint started in its place, see replaced.
+    pub fn open(path: &str, image_dimension: ImageDimension, filter: PixelFilter, fingerprint: u64) -> Result<Checkpoint, Error> {
+        let mut replaced = None;
+        let tiles = match fs::read(path) {
+            Ok(bytes) => match read_tiles(&bytes, image_dimension, filter, fingerprint) {
+                Ok(tiles) => tiles,
+                Err(_) => {
+                    let old = format!("{}.old", path);
+                    fs::rename(path, &old).map_err(|cause| Error::io(path, cause))?;
+                    replaced = Some(old);
+                    Vec::new()
+                },
+            },
+            Err(ref cause) if cause.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(cause) => return Err(Error::io(path, cause)),
+        };
+
+        if let Some(directory) = Path::new(path).parent() {
+            fs::create_dir_all(directory).map_err(|cause| Error::io(path, cause))?;
+        }
+        // Rewrite what was read next to the old file and swap it in, which drops any half written
+        // record at the end without ever leaving the checkpoint missing tiles
+        let temporary = format!("{}.tmp", path);
+        let rewritten = File::create(&temporary).and_then(|file| {
+            let mut writer = BufWriter::new(&file);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&image_dimension.width.to_le_bytes())?;
+            writer.write_all(&image_dimension.height.to_le_bytes())?;
+            writer.write_all(&filter_index(filter.kind).to_le_bytes())?;
+            writer.write_all(&filter.radius.to_le_bytes())?;
+            writer.write_all(&fingerprint.to_le_bytes())?;
+            for rendered in tiles.iter() {
+                write_tile(&mut writer, rendered)?;
+            }
+            writer.flush()?;
+            drop(writer);
+            file.sync_all()
+        });
+        rewritten.and_then(|_| fs::rename(&temporary, path)).map_err(|cause| Error::io(path, cause))?;
+
+        let file = OpenOptions::new().append(true).open(path).map_err(|cause| Error::io(path, cause))?;
+        Ok(Checkpoint { path: String::from(path), file, tiles, replaced })
+    }
+
+    // Where the file that was at path went, if it was from another render
+    pub fn replaced(&self) -> Option<&str> {
+        self.replaced.as_deref()
+    }
+
+    // Tiles finished so far, including the ones from earlier runs
+    pub fn tiles(&self) -> &[RenderedTile] {
+        &self.tiles
+    }
+
+    pub fn find(&self, tile: Tile) -> Option<&RenderedTile> {
+        self.tiles.iter().find(|rendered| rendered.tile == tile)
+    }
+
+    // Saves a finished tile, it's on disk by the time this returns
+    pub fn record(&mut self, rendered: &RenderedTile) -> Result<(), Error> {
+        let mut writer = BufWriter::new(&self.file);
+        let written = write_tile(&mut writer, rendered).and_then(|_| writer.flush());
+        drop(writer);
+        written.and_then(|_| self.file.sync_data()).map_err(|cause| Error::io(&self.path, cause))?;
+        self.tiles.push(rendered.clone());
+        Ok(())
+    }
+
+    // Deletes the checkpoint once the render it was for is done
+    pub fn remove(self) -> Result<(), Error> {
+        let Checkpoint { path, file, .. } = self;
+        drop(file);
+        fs::remove_file(&path).map_err(|cause| Error::io(&path, cause))
+    }
+}
+
+fn filter_index(kind: FilterKind) -> u32 {
+    FilterKind::ALL.iter().position(|other| *other == kind).unwrap() as u32
+}
+
+pub fn write_region<W: Write>(writer: &mut W, region: Tile) -> io::Result<()> {
+    for num in [region.x, region.y, region.width, region.height].iter() {
+        writer.write_all(&num.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+// One record of the file, also how finished tiles are sent between processes, see render_distributed
+pub fn write_tile<W: Write>(writer: &mut W, rendered: &RenderedTile) -> io::Result<()> {
+    write_region(writer, rendered.tile)?;
+    for distance in rendered.distances.iter() {
+        writer.write_all(&distance.to_le_bytes())?;
+    }
+    write_region(writer, rendered.film.region)?;
+    for pixel in rendered.film.pixels.iter() {
+        for num in [pixel.red, pixel.green, pixel.blue, pixel.weight].iter() {
+            writer.write_all(&num.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_tiles(mut bytes: &[u8], image_dimension: ImageDimension, filter: PixelFilter, fingerprint: u64) -> io::Result<Vec<RenderedTile>> {
+    let mut magic = [0; 8];
+    bytes.read_exact(&mut magic).map_err(|_| invalid("not a render checkpoint"))?;
+    if &magic != MAGIC {
+        return Err(invalid("not a render checkpoint"));
+    }
+    let width = read_u32(&mut bytes).map_err(|_| invalid("not a render checkpoint"))?;
+    let height = read_u32(&mut bytes).map_err(|_| invalid("not a render checkpoint"))?;
+    if width != image_dimension.width || height != image_dimension.height {
+        return Err(invalid(&format!("checkpoint is for a {}x{} image, not {}x{}", width, height, image_dimension.width, image_dimension.height)));
+    }
+    let kind = read_u32(&mut bytes).map_err(|_| invalid("not a render checkpoint"))?;
+    let radius = read_f64(&mut bytes).map_err(|_| invalid("not a render checkpoint"))?;
+    if kind != filter_index(filter.kind) || radius != filter.radius {
+        return Err(invalid("checkpoint was rendered with a different pixel filter"));
+    }
+    let mut rendered_fingerprint = [0; 8];
+    bytes.read_exact(&mut rendered_fingerprint).map_err(|_| invalid("not a render checkpoint"))?;
+    if u64::from_le_bytes(rendered_fingerprint) != fingerprint {
+        return Err(invalid("checkpoint was rendered from a different scene or with different settings"));
+    }
+
+    let mut tiles = Vec::new();
+    while let Ok(rendered) = read_tile(&mut bytes, width, height) {
+        tiles.push(rendered);
+    }
+    Ok(tiles)
+}
+
+pub fn read_region<R: Read>(bytes: &mut R, width: u32, height: u32) -> io::Result<Tile> {
+    let region = Tile::new(read_u32(bytes)?, read_u32(bytes)?, read_u32(bytes)?, read_u32(bytes)?);
+    if region.x as u64 + region.width as u64 > width as u64 || region.y as u64 + region.height as u64 > height as u64 {
+        return Err(invalid("checkpoint has a tile outside of the image"));
+    }
+    Ok(region)
+}
+
+// Fails at the end of the file, or on a record that was cut short or doesn't fit the image
+pub fn read_tile<R: Read>(bytes: &mut R, width: u32, height: u32) -> io::Result<RenderedTile> {
+    let tile = read_region(bytes, width, height)?;
+    let mut distances = Vec::with_capacity(tile.area() as usize);
+    for _ in 0..tile.area() {
+        distances.push(read_f64(bytes)?);
+    }
+    let mut film = FilmTile::from_region(read_region(bytes, width, height)?);
+    for pixel in film.pixels.iter_mut() {
+        *pixel = FilmPixel { red: read_i128(bytes)?, green: read_i128(bytes)?, blue: read_i128(bytes)?, weight: read_i128(bytes)? };
+    }
+    Ok(RenderedTile { tile, distances, film })
+}
+
+pub fn read_u32<R: Read>(bytes: &mut R) -> io::Result<u32> {
+    let mut buffer = [0; 4];
+    bytes.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+pub fn read_f64<R: Read>(bytes: &mut R) -> io::Result<f64> {
+    let mut buffer = [0; 8];
+    bytes.read_exact(&mut buffer)?;
+    Ok(f64::from_le_bytes(buffer))
+}
+
+pub fn read_i128<R: Read>(bytes: &mut R) -> io::Result<i128> {
+    let mut buffer = [0; 16];
+    bytes.read_exact(&mut buffer)?;
+    Ok(i128::from_le_bytes(buffer))
+}
@@ -3,19 +3,73 @@ use image::{Rgb};
 pub mod consts;
 pub mod operator;
 
-// Color needs to be encoded and decoded
-// because most image formats don't store color linearly
-const GAMMA: f64 = 2.2;
+// The working color space is linear sRGB, ie. Rec. 709 primaries with no transfer curve.
+// All the lighting math happens there, colors only get encoded when they're written to
+// an 8 bit image and decoded when they're read from one.
 
-pub fn gamma_encode(linear: f64) -> f64 {
-    linear.powf(1.0 / GAMMA)
+// The piecewise sRGB transfer functions from IEC 61966-2-1
+pub fn srgb_encode(linear: f64) -> f64 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    }
+    else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn srgb_decode(encoded: f64) -> f64 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    }
+    else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
 }
 
-pub fn gamma_decode(encoded: f64) -> f64 {
-    encoded.powf(GAMMA)
+// How the values of an 8 bit image relate to linear color.
+// Photos and painted textures are sRGB, data like normal and bump maps is usually stored linearly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorEncoding {
+    Srgb,
+    Linear,
+}
+
+impl ColorEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorEncoding::Srgb => "srgb",
+            ColorEncoding::Linear => "linear",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorEncoding> {
+        match name {
+            "srgb" => Some(ColorEncoding::Srgb),
+            "linear" => Some(ColorEncoding::Linear),
+            _ => None,
+        }
+    }
+
+    // Turns a stored 8 bit value into a linear value between 0.0 and 1.0
+    pub fn decode(&self, value: u8) -> f64 {
+        let value = value as f64 / 255.0;
+        match self {
+            ColorEncoding::Srgb => srgb_decode(value),
+            ColorEncoding::Linear => value,
+        }
+    }
+
+    // Turns a linear value between 0.0 and 1.0 into the nearest 8 bit value
+    pub fn encode(&self, value: f64) -> u8 {
+        let value = match self {
+            ColorEncoding::Srgb => srgb_encode(value),
+            ColorEncoding::Linear => value,
+        };
+        (value * 255.0).round() as u8
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub red: f64,
     pub green: f64,
@@ -55,6 +109,11 @@ impl Color {
         }
     }
 
+    // Relative luminance of a linear color, using the Rec. 709 primaries
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
     // Keep ratios the same, largest pigment becomes 1.0
     pub fn normalize(&self) -> Color {
         let max = self.red.max(self.green).max(self.blue); 
@@ -65,21 +124,29 @@ impl Color {
         }
     }
 
-    // Convert to type used by the Image Crate to make pngs
+    // Convert to type used by the Image Crate to make pngs, sRGB encoded
     pub fn to_rgb(&self) -> Rgb<u8> {
+        self.to_encoded_rgb(ColorEncoding::Srgb)
+    }
+
+    pub fn to_encoded_rgb(&self, encoding: ColorEncoding) -> Rgb<u8> {
         Rgb {
-            data: [(gamma_encode(self.red) * 255.0) as u8,
-                   (gamma_encode(self.green) *255.0) as u8,
-                   (gamma_encode(self.blue) * 255.0) as u8]
+            data: [encoding.encode(self.red),
+                   encoding.encode(self.green),
+                   encoding.encode(self.blue)]
         }
     }
 
-    // Convert from type used by Image Crate
+    // Convert from type used by Image Crate, assuming it's sRGB encoded
     pub fn from_rgb(rgb: &Rgb<u8>) -> Color {
+        Color::from_encoded_rgb(rgb, ColorEncoding::Srgb)
+    }
+
+    pub fn from_encoded_rgb(rgb: &Rgb<u8>, encoding: ColorEncoding) -> Color {
         Color {
-            red: gamma_decode((rgb.data[0] as f64) / 255.0),
-            green: gamma_decode((rgb.data[1] as f64) / 255.0),
-            blue: gamma_decode((rgb.data[2] as f64) / 255.0),
+            red: encoding.decode(rgb.data[0]),
+            green: encoding.decode(rgb.data[1]),
+            blue: encoding.decode(rgb.data[2]),
         }
     }
 }
@@ -1,5 +1,7 @@
 use euler::{DMat4};
 use geometry::{Intersectable, Intersect, Ray, Transformable, TransformComponent};
+use scene_file::{Statement, SceneFileError};
+use stats::{PrimitiveKind, count_intersection_test};
 
 pub mod base_shape;
 pub mod subtract_shape;
@@ -124,6 +126,20 @@ fn calculate_intersects(comp: &(Compositable + Send + Sync), ray: Ray, check_get
 
 pub trait Compositable: Intersectable + Transformable + CompositableClone {
     fn get_shapes(&self) -> Vec<&(Compositable + Send + Sync)>;
+
+    // Describes the shape as an operand of another composite shape, eg. `shape sphere 1`
+    fn to_shape_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(self.to_statement()?.renamed("shape"))
+    }
+}
+
+// Composite shapes are written as `primitive <kind> { transform {...} shape ... }`
+fn composite_statement(kind: &str, comp: &dyn Compositable) -> Result<Statement, SceneFileError> {
+    let mut statement = Statement::of_kind("primitive", kind).with_transform(comp.get_transform());
+    for shape in comp.get_shapes() {
+        statement = statement.with_child(shape.to_shape_statement()?);
+    }
+    Ok(statement)
 }
 
 pub trait CompositableClone {
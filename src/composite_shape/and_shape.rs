@@ -41,6 +41,14 @@ impl Intersectable for AndShape {
         });
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        composite_statement("and", self)
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::And
+    }
 }
 
 impl Compositable for AndShape {
@@ -109,6 +117,14 @@ impl Intersectable for MultiAndShape {
         });
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        composite_statement("multi_and", self)
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::MultiAnd
+    }
 }
 
 impl Transformable for MultiAndShape {
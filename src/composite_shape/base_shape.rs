@@ -19,6 +19,10 @@ impl Compositable for BaseShape {
     fn get_shapes(&self) -> Vec<&(Compositable + Send + Sync)> {
         vec!(self)
     }
+
+    fn to_shape_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(self.primitive.to_statement()?.renamed("shape").with_transform(self.get_transform()))
+    }
 }
 
 impl Transformable for BaseShape {
@@ -40,6 +44,7 @@ impl Intersectable for BaseShape {
 
     fn get_closest_intersect(&self, ray: Ray) -> Option<Intersect> {
         let ray = ray.transform(self.transform.get_inverse_transform());
+        count_intersection_test(self.primitive.kind());
         if let Some(intersect) = self.primitive.get_closest_intersect(ray) {
             return Some(intersect.transform(self.transform.get_transform()));
         }
@@ -48,6 +53,7 @@ impl Intersectable for BaseShape {
 
     fn get_all_intersects(&self, ray: Ray) -> Vec<Intersect> {
         let ray = ray.transform(self.transform.get_inverse_transform());
+        count_intersection_test(self.primitive.kind());
         let intersects = self.primitive.get_all_intersects(ray);
         intersects.into_iter().map(|inter| inter.transform(self.transform.get_transform())).collect()
     }
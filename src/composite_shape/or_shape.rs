@@ -45,6 +45,14 @@ impl Intersectable for OrShape {
         });
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        composite_statement("or", self)
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Or
+    }
 }
 
 impl Compositable for OrShape {
@@ -69,4 +69,12 @@ impl Intersectable for SubtractShape {
         });
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        composite_statement("subtract", self)
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Subtract
+    }
 }
\ No newline at end of file
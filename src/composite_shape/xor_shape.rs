@@ -52,6 +52,14 @@ impl Intersectable for XorShape {
         });
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        composite_statement("xor", self)
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Xor
+    }
 }
 
 impl Compositable for XorShape {
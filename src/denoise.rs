@@ -0,0 +1,198 @@
+use std::io;
+use euler::{DVec3, dvec3};
+use color::{Color, ColorEncoding};
+use framebuffer::FrameBuffer;
+use hdr::HdrImage;
+use aov::AovConfig;
+use error::Error;
+
+const ITERATIONS: u32 = 5;
+const COLOR_SIGMA: f64 = 0.5;
+const NORMAL_SIGMA: f64 = 0.3;
+const DEPTH_SIGMA: f64 = 0.05;
+const ALBEDO_SIGMA: f64 = 0.1;
+
+// Weights of the 5x5 B3 spline kernel along one axis
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// How hard the denoiser smooths. Each sigma is how different two pixels can be before they
+// stop being averaged together, smaller keeps more detail but leaves more noise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DenoiseConfig {
+    // Passes of the filter, each one reaches twice as far as the last
+    pub iterations: u32,
+    // Halved after every pass, so later passes only smooth what's left of the noise
+    pub color_sigma: f64,
+    // Difference between normals, which are unit length
+    pub normal_sigma: f64,
+    // Relative difference between depths, 0.05 is 5% further away
+    pub depth_sigma: f64,
+    pub albedo_sigma: f64,
+}
+
+impl Default for DenoiseConfig {
+    fn default() -> DenoiseConfig {
+        DenoiseConfig {
+            iterations: ITERATIONS,
+            color_sigma: COLOR_SIGMA,
+            normal_sigma: NORMAL_SIGMA,
+            depth_sigma: DEPTH_SIGMA,
+            albedo_sigma: ALBEDO_SIGMA,
+        }
+    }
+}
+
+// Per pixel information about what's in the image, used to find the edges the denoiser shouldn't
+// smooth over. Any of them can be left out, but the more there are the better edges are kept.
+#[derive(Debug, Clone, Default)]
+pub struct DenoiseGuides {
+    // Infinite where nothing was hit
+    pub depths: Option<Vec<f64>>,
+    pub normals: Option<Vec<DVec3>>,
+    pub albedos: Option<Vec<Color>>,
+}
+
+impl DenoiseGuides {
+    // Distances to the first hit are always there, normals and albedo need their AOV passes
+    pub fn from_framebuffer(framebuffer: &FrameBuffer) -> DenoiseGuides {
+        DenoiseGuides {
+            depths: Some(framebuffer.distances.clone()),
+            normals: framebuffer.normals.clone(),
+            albedos: framebuffer.albedos.clone(),
+        }
+    }
+}
+
+// Smooths out noise with an edge-avoiding à-trous wavelet filter (Dammertz et al. 2010).
+// Every pass blurs with a 5x5 kernel whose taps are spread further apart each time, and each tap
+// counts for less the more its color and guides differ from the pixel being filtered.
+pub fn denoise(colors: &[Color], width: u32, height: u32, guides: &DenoiseGuides, config: &DenoiseConfig) -> Vec<Color> {
+    let area = (width * height) as usize;
+    assert_eq!(colors.len(), area, "need one color per pixel");
+    for len in [guides.depths.as_ref().map(Vec::len), guides.normals.as_ref().map(Vec::len), guides.albedos.as_ref().map(Vec::len)].iter().flatten() {
+        assert_eq!(*len, area, "need one guide value per pixel");
+    }
+
+    let mut current = colors.to_vec();
+    let mut color_sigma = config.color_sigma;
+    for iteration in 0..config.iterations {
+        let step = 1i64 << iteration.min(30);
+        let mut next = Vec::with_capacity(area);
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let p = (y * width as i64 + x) as usize;
+                let mut total = Color::BLACK;
+                let mut total_weight = 0.0;
+                for (j, ky) in KERNEL.iter().enumerate() {
+                    let qy = y + (j as i64 - 2) * step;
+                    if qy < 0 || qy >= height as i64 {
+                        continue;
+                    }
+                    for (i, kx) in KERNEL.iter().enumerate() {
+                        let qx = x + (i as i64 - 2) * step;
+                        if qx < 0 || qx >= width as i64 {
+                            continue;
+                        }
+                        let q = (qy * width as i64 + qx) as usize;
+                        let weight = kx * ky * edge_weight(&current, guides, config, color_sigma, p, q);
+                        total += current[q] * weight;
+                        total_weight += weight;
+                    }
+                }
+                // The pixel itself always has a weight, so this is never 0
+                next.push(total / total_weight);
+            }
+        }
+        current = next;
+        color_sigma *= 0.5;
+    }
+    current
+}
+
+// How much pixel q should count towards pixel p, between 0 and 1
+fn edge_weight(colors: &[Color], guides: &DenoiseGuides, config: &DenoiseConfig, color_sigma: f64, p: usize, q: usize) -> f64 {
+    // Colors are compressed first so a single bright sample can't keep its neighbours from smoothing it
+    let compress = |color: Color| Color::new(color.red / (1.0 + color.red.abs()), color.green / (1.0 + color.green.abs()), color.blue / (1.0 + color.blue.abs()));
+    let mut distance = color_distance(compress(colors[p]), compress(colors[q])) / (color_sigma * color_sigma);
+
+    if let Some(ref depths) = guides.depths {
+        let (a, b) = (depths[p], depths[q]);
+        if a.is_finite() != b.is_finite() {
+            return 0.0;
+        }
+        if a.is_finite() {
+            let relative = (a - b) / a.abs().max(b.abs()).max(1e-9);
+            distance += relative * relative / (config.depth_sigma * config.depth_sigma);
+        }
+    }
+    if let Some(ref normals) = guides.normals {
+        let difference = normals[p] - normals[q];
+        distance += difference.dot(difference) / (config.normal_sigma * config.normal_sigma);
+    }
+    if let Some(ref albedos) = guides.albedos {
+        distance += color_distance(albedos[p], albedos[q]) / (config.albedo_sigma * config.albedo_sigma);
+    }
+    (-distance).exp()
+}
+
+fn color_distance(a: Color, b: Color) -> f64 {
+    let difference = a - b;
+    difference.red * difference.red + difference.green * difference.green + difference.blue * difference.blue
+}
+
+// The framebuffer with its colors denoised, guided by whichever of its passes are there
+pub fn denoise_framebuffer(framebuffer: &FrameBuffer, config: &DenoiseConfig) -> FrameBuffer {
+    let mut denoised = framebuffer.clone();
+    denoised.colors = denoise(&framebuffer.colors, framebuffer.width, framebuffer.height, &DenoiseGuides::from_framebuffer(framebuffer), config);
+    denoised
+}
+
+// The AOV passes a render needs so it can be denoised, on top of the ones in aovs
+pub fn denoise_aovs(aovs: AovConfig) -> AovConfig {
+    let mut aovs = aovs;
+    aovs.normal = true;
+    aovs.albedo = true;
+    aovs
+}
+
+// Paths to the passes saved next to a render, eg. the render.normal.png written by `--aov normal`
+#[derive(Debug, Clone, Default)]
+pub struct DenoiseGuidePaths {
+    pub depth: Option<String>,
+    pub normal: Option<String>,
+    pub albedo: Option<String>,
+}
+
+// Denoises an image that's already been saved, see HdrImage::open for the formats it can read.
+// Guides are read the way FrameBuffer::pass_to_rgb_image writes them.
+pub fn denoise_image_file(path: &str, guide_paths: &DenoiseGuidePaths, config: &DenoiseConfig) -> Result<HdrImage, Error> {
+    let image = HdrImage::open(path)?;
+    let open_guide = |guide_path: &Option<String>| -> Result<Option<Vec<Color>>, Error> {
+        match guide_path {
+            Some(guide_path) => {
+                let guide = HdrImage::open(guide_path)?;
+                if guide.width != image.width || guide.height != image.height {
+                    let message = format!("guide is {}x{} but the image is {}x{}", guide.width, guide.height, image.width, image.height);
+                    return Err(Error::io(guide_path, io::Error::new(io::ErrorKind::InvalidData, message)));
+                }
+                Ok(Some(guide.pixels))
+            },
+            None => Ok(None),
+        }
+    };
+
+    // The depth pass goes from white up close to black far away, and normals are stored as colors
+    let guides = DenoiseGuides {
+        depths: open_guide(&guide_paths.depth)?.map(|pixels| pixels.iter().map(|color| {
+            let near = ColorEncoding::Srgb.encode(color.red) as f64 / 255.0;
+            if near > 0.0 { 1.0 - near } else { f64::INFINITY }
+        }).collect()),
+        normals: open_guide(&guide_paths.normal)?.map(|pixels| pixels.iter().map(|color| {
+            let encoded = |channel: f64| ColorEncoding::Srgb.encode(channel) as f64 / 255.0 * 2.0 - 1.0;
+            dvec3!(encoded(color.red), encoded(color.green), encoded(color.blue))
+        }).collect()),
+        albedos: open_guide(&guide_paths.albedo)?,
+    };
+    let colors = denoise(&image.pixels, image.width, image.height, &guides, config);
+    Ok(HdrImage::from_pixels(image.width, image.height, colors))
+}
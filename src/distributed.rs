@@ -0,0 +1,510 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex, Condvar};
+use std::thread;
+use std::time::{Duration, Instant};
+use euler::{dvec2, dvec3};
+use color::Color;
+use scene::Scene;
+use render::{ImageDimension, TileResult, render_tile, render_region, open_checkpoint, collect_tiles};
+use tile::{Tile, region_tiles};
+use checkpoint::{Checkpoint, write_region, write_tile, read_region, read_tile, read_u32, read_f64};
+use aov::AovSample;
+use stats::RenderStats;
+use multithread::ThreadPool;
+use progress_tracker::{ProgressTracker, ProgressMessage};
+use denoise::{denoise_aovs, denoise_framebuffer};
+use framebuffer::FrameBuffer;
+use scene_file::{SceneDescription, fingerprint};
+use error::Error;
+
+// Splits a render between processes, which can be on other machines. A coordinator listens for
+// workers and hands them tiles, each worker loads the same scene file and sends back the samples
+// it took for every tile, so the image comes out exactly as if one process had rendered it.
+//
+// Everything is little endian. A worker connects and sends MAGIC and how many threads it has.
+// The coordinator answers with MAGIC, the image, render and camera statements of its scene
+// description as a length and UTF-8 text, and a fingerprint of the whole description. The worker
+// renders with those settings once it has checked its own scene gives the same fingerprint, and
+// answers READY, or REJECTED and the reason as text. From then on the coordinator sends TILE with
+// the tile's index and x, y, width and height, up to one per worker thread at a time, and the worker
+// sends back the index, the tile as a checkpoint record, its AOV samples and costs, each as a count
+// and the values, and the stats of rendering it. DONE means the render is finished.
+const MAGIC: &[u8; 8] = b"RTDIST01";
+
+// What the coordinator sends once the worker is ready
+const TILE: u8 = 1;
+const DONE: u8 = 0;
+
+// The worker's answer to the settings it was sent
+const READY: u8 = 1;
+const REJECTED: u8 = 0;
+
+// Workers keep trying to connect for this long, so they can be started before the coordinator
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_RETRY: Duration = Duration::from_millis(100);
+
+// How long a new connection has to say it's a worker before the coordinator hangs up
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// How long a worker can go without sending back any of its tiles before the coordinator gives up
+// on it and hands them to another. Generous, a single tile of a slow render can take minutes.
+const RESULT_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+// How often the coordinator checks for new workers
+const ACCEPT_POLL: Duration = Duration::from_millis(20);
+
+// Longest text either end will read, anything bigger means the connection has gone wrong
+const MAX_TEXT_LENGTH: u32 = 1 << 24;
+
+// Renders description by handing its tiles out to workers that connect to listener, see render_worker.
+// It waits for as long as it takes workers to render every tile. Workers can join and leave
+// at any point, the tiles a worker had when it went away, or stopped answering, go to the next one to ask.
+// Progressive renders and object ids can't be split up, node ids only mean something
+// in the process that made them.
+pub fn render_distributed(description: SceneDescription, listener: TcpListener) -> Result<(FrameBuffer, RenderStats), Error> {
+    let start = Instant::now();
+    let address = listener.local_addr().map(|address| address.to_string()).unwrap_or_default();
+    let unsupported = |message: &str| Error::io(&address, io::Error::new(io::ErrorKind::Unsupported, message));
+    if description.render.progressive.is_some() {
+        return Err(unsupported("progressive renders can't be split between workers"));
+    }
+    if description.render.aovs.object_id {
+        return Err(unsupported("object ids can't be rendered by workers"));
+    }
+
+    // The same as render_framebuffer_with_stats, the workers render the passes the denoiser needs
+    let mut description = description;
+    let denoise = description.render.denoise.take().filter(|_| description.render.heatmap.is_none());
+    let aovs = description.render.aovs;
+    if denoise.is_some() {
+        description.render.aovs = denoise_aovs(aovs);
+    }
+    let checkpoint = open_checkpoint(&description.scene, description.image, description.camera, &description.render)?;
+    let (mut framebuffer, mut stats) = coordinate(&description, listener, checkpoint).map_err(|cause| Error::io(&address, cause))?;
+    if let Some(denoise) = denoise {
+        let denoise_start = Instant::now();
+        framebuffer = denoise_framebuffer(&framebuffer, &denoise);
+        framebuffer.keep_passes(aovs);
+        stats.denoise_time = denoise_start.elapsed();
+    }
+    stats.elapsed = start.elapsed();
+    Ok((framebuffer, stats))
+}
+
+// Renders tiles for the coordinator at address on num_threads threads until its render is done.
+// scene has to be loaded from the same scene file as the coordinator's, the image, camera and
+// render settings all come from the coordinator. Gives back what rendering its tiles took.
+pub fn render_worker(scene: Scene, address: &str, num_threads: usize) -> Result<RenderStats, Error> {
+    work(scene, address, num_threads).map_err(|cause| Error::io(address, cause))
+}
+
+// What every worker is sent, and what the coordinator expects back
+struct Job {
+    settings: String,
+    fingerprint: u64,
+    image_dimension: ImageDimension,
+    aovs: bool,
+    heatmap: bool,
+}
+
+fn coordinate(description: &SceneDescription, listener: TcpListener, checkpoint: Option<Checkpoint>) -> io::Result<(FrameBuffer, RenderStats)> {
+    let render_config = &description.render;
+    let image_dimension = description.image;
+    let describe = |source: Result<String, _>| source.map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, format!("{}", err)));
+    let job = Arc::new(Job {
+        settings: describe(description.settings_source())?,
+        fingerprint: fingerprint(&describe(description.to_source())?),
+        image_dimension,
+        aovs: render_config.aovs.any(),
+        heatmap: render_config.heatmap.is_some(),
+    });
+
+    let region = render_region(render_config, image_dimension);
+    let tiles = region_tiles(region, render_config.tile_size, render_config.tile_order);
+    let (sender, receiver) = mpsc::channel::<(usize, TileResult)>();
+    let progress_tracker = ProgressTracker::new(ImageDimension::new(region.width, region.height));
+
+    // Tiles in the checkpoint go straight in. With AOVs they're rendered again to work those out,
+    // which comes out the same as what was saved.
+    let mut waiting = VecDeque::new();
+    for (tile_index, tile) in tiles.iter().cloned().enumerate() {
+        match checkpoint.as_ref().and_then(|checkpoint| checkpoint.find(tile)).filter(|_| !job.aovs) {
+            Some(done) => {
+                progress_tracker.get_sender().send(ProgressMessage::Progress(tile.area())).unwrap();
+                sender.send((tile_index, TileResult { rendered: done.clone(), aovs: Vec::new(), costs: Vec::new(), stats: RenderStats::default() })).unwrap();
+            },
+            None => waiting.push_back((tile_index, tile)),
+        }
+    }
+
+    listener.set_nonblocking(true)?;
+    let dispatch = Arc::new(Dispatch::new(waiting));
+    let progress_sender = progress_tracker.get_sender();
+    let accepting = thread::spawn(move || accept_workers(listener, job, dispatch, sender, progress_sender));
+    let collected = collect_tiles(&receiver, &tiles, image_dimension, render_config, checkpoint);
+    accepting.join().unwrap();
+    Ok(collected)
+}
+
+// Takes on workers until every tile is back, each one gets its own thread
+fn accept_workers(  listener: TcpListener,
+                    job: Arc<Job>,
+                    dispatch: Arc<Dispatch>,
+                    results: mpsc::Sender<(usize, TileResult)>,
+                    progress: mpsc::Sender<ProgressMessage>) {
+
+    let mut workers = Vec::new();
+    while !dispatch.is_done() {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let (job, dispatch, results, progress) = (job.clone(), dispatch.clone(), results.clone(), progress.clone());
+                workers.push(thread::spawn(move || {
+                    if let Err(err) = serve_worker(stream, &job, &dispatch, &results, &progress) {
+                        eprintln!("worker {} dropped: {}", peer, err);
+                    }
+                }));
+            },
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+            Err(err) => {
+                eprintln!("could not accept worker: {}", err);
+                thread::sleep(ACCEPT_POLL);
+            },
+        }
+    }
+
+    // Every worker gets told the render is done before the listener closes
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
+
+// Keeps one worker busy until the render is done, giving back the tiles it had if it goes away
+fn serve_worker(stream: TcpStream,
+                job: &Job,
+                dispatch: &Dispatch,
+                results: &mpsc::Sender<(usize, TileResult)>,
+                progress: &mpsc::Sender<ProgressMessage>) -> io::Result<()> {
+
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream.try_clone()?);
+    read_magic(&mut reader)?;
+    let threads = read_u32(&mut reader)?.max(1) as usize;
+    writer.write_all(MAGIC)?;
+    write_text(&mut writer, &job.settings)?;
+    writer.write_all(&job.fingerprint.to_le_bytes())?;
+    writer.flush()?;
+    match read_u8(&mut reader)? {
+        READY => {},
+        REJECTED => return Err(invalid(&read_text(&mut reader)?)),
+        _ => return Err(invalid("worker didn't say whether it was ready")),
+    }
+    stream.set_read_timeout(Some(RESULT_TIMEOUT))?;
+
+    let mut taken: Vec<(usize, Tile)> = Vec::new();
+    let served = (|| loop {
+        // Only waits for a tile when the worker has nothing to do
+        while taken.len() < threads {
+            match dispatch.take(taken.is_empty()) {
+                Next::Tile(tile_index, tile) => {
+                    writer.write_all(&[TILE])?;
+                    writer.write_all(&(tile_index as u32).to_le_bytes())?;
+                    write_region(&mut writer, tile)?;
+                    taken.push((tile_index, tile));
+                },
+                Next::Wait => break,
+                Next::Done => {
+                    writer.write_all(&[DONE])?;
+                    return writer.flush();
+                },
+            }
+        }
+        writer.flush()?;
+
+        // A worker that's stopped answering, or a connection that's gone quiet, counts as gone
+        let (tile_index, result) = read_result(&mut reader, job).map_err(|err| match err.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => io::Error::new(io::ErrorKind::TimedOut, "worker stopped sending back tiles"),
+            _ => err,
+        })?;
+        let position = taken.iter().position(|&(index, tile)| index == tile_index && tile == result.rendered.tile)
+            .ok_or_else(|| invalid("worker sent back a tile it wasn't given"))?;
+        let (_, tile) = taken.remove(position);
+        progress.send(ProgressMessage::Progress(tile.area())).unwrap();
+        results.send((tile_index, result)).unwrap();
+        dispatch.finish();
+    })();
+
+    if served.is_err() {
+        dispatch.give_back(&taken);
+    }
+    served
+}
+
+fn work(scene: Scene, address: &str, num_threads: usize) -> io::Result<RenderStats> {
+    let start = Instant::now();
+    let stream = connect(address)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(num_threads as u32).to_le_bytes())?;
+    writer.flush()?;
+    read_magic(&mut reader)?;
+    let settings = read_text(&mut reader)?;
+    let expected = read_u64(&mut reader)?;
+
+    // The scene is only checked as a whole, so it has to be described exactly the same way
+    let settings = SceneDescription::parse(&settings).map_err(|err| invalid(&format!("could not read the coordinator's settings: {}", err)))?;
+    let description = SceneDescription { scene, ..settings };
+    let rejection = match description.to_source() {
+        Ok(ref source) if fingerprint(source) == expected => None,
+        Ok(_) => Some(String::from("the worker's scene isn't the same as the coordinator's")),
+        Err(err) => Some(format!("the worker's scene can't be compared with the coordinator's: {}", err)),
+    };
+    if let Some(reason) = rejection {
+        writer.write_all(&[REJECTED])?;
+        write_text(&mut writer, &reason)?;
+        writer.flush()?;
+        return Err(invalid(&reason));
+    }
+    writer.write_all(&[READY])?;
+    writer.flush()?;
+
+    let SceneDescription { scene, camera, image, mut render } = description;
+    render.num_threads = num_threads;
+    let scene = Arc::new(scene);
+    let render_config = Arc::new(render);
+    let writer = Arc::new(Mutex::new(writer));
+    let stats = Arc::new(Mutex::new(RenderStats::default()));
+    let thread_pool = ThreadPool::new(num_threads);
+    loop {
+        match read_u8(&mut reader)? {
+            TILE => {
+                let tile_index = read_u32(&mut reader)? as usize;
+                let tile = read_region(&mut reader, image.width, image.height)?;
+                let (scene, render_config, writer, stats) = (scene.clone(), render_config.clone(), writer.clone(), stats.clone());
+                thread_pool.execute(move || {
+                    let result = render_tile(&scene, image, camera, &render_config, tile, None);
+                    stats.lock().unwrap().add(&result.stats);
+
+                    // If the tile can't be sent the connection is gone, which the next read finds out
+                    let mut writer = writer.lock().unwrap();
+                    write_result(&mut *writer, tile_index, &result).and_then(|_| writer.flush()).ok();
+                });
+            },
+            DONE => break,
+            _ => return Err(invalid("coordinator sent something other than a tile")),
+        }
+    }
+
+    drop(thread_pool);
+    let mut stats = *stats.lock().unwrap();
+    stats.elapsed = start.elapsed();
+    Ok(stats)
+}
+
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let start = Instant::now();
+    loop {
+        match TcpStream::connect(address) {
+            Ok(stream) => return Ok(stream),
+            Err(ref err) if err.kind() == io::ErrorKind::ConnectionRefused && start.elapsed() < CONNECT_TIMEOUT => thread::sleep(CONNECT_RETRY),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Tiles waiting for a worker, shared by the threads talking to workers
+struct Dispatch {
+    state: Mutex<DispatchState>,
+    changed: Condvar,
+}
+
+struct DispatchState {
+    waiting: VecDeque<(usize, Tile)>,
+    // Tiles that haven't come back yet, whether they're waiting or with a worker
+    remaining: usize,
+}
+
+enum Next {
+    Tile(usize, Tile),
+    // Nothing is waiting, but tiles are still out with workers
+    Wait,
+    Done,
+}
+
+impl Dispatch {
+    fn new(waiting: VecDeque<(usize, Tile)>) -> Dispatch {
+        let remaining = waiting.len();
+        Dispatch { state: Mutex::new(DispatchState { waiting, remaining }), changed: Condvar::new() }
+    }
+
+    // The next tile to render. With wait it blocks instead of giving back Next::Wait,
+    // in case a worker goes away and its tiles need someone else.
+    fn take(&self, wait: bool) -> Next {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some((tile_index, tile)) = state.waiting.pop_front() {
+                return Next::Tile(tile_index, tile);
+            }
+            if state.remaining == 0 {
+                return Next::Done;
+            }
+            if !wait {
+                return Next::Wait;
+            }
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    fn finish(&self) {
+        self.state.lock().unwrap().remaining -= 1;
+        self.changed.notify_all();
+    }
+
+    // Tiles a worker had when it went away, they're the next ones handed out
+    fn give_back(&self, tiles: &[(usize, Tile)]) {
+        let mut state = self.state.lock().unwrap();
+        for tile in tiles.iter().rev() {
+            state.waiting.push_front(*tile);
+        }
+        self.changed.notify_all();
+    }
+
+    fn is_done(&self) -> bool {
+        self.state.lock().unwrap().remaining == 0
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_magic<R: Read>(reader: &mut R) -> io::Result<()> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("the other end isn't a raytracer, or is a different version"));
+    }
+    Ok(())
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buffer = [0; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buffer = [0; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+fn write_text<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    writer.write_all(&(text.len() as u32).to_le_bytes())?;
+    writer.write_all(text.as_bytes())
+}
+
+fn read_text<R: Read>(reader: &mut R) -> io::Result<String> {
+    let length = read_u32(reader)?;
+    if length > MAX_TEXT_LENGTH {
+        return Err(invalid("text is too long"));
+    }
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid("text isn't UTF-8"))
+}
+
+fn write_result<W: Write>(writer: &mut W, tile_index: usize, result: &TileResult) -> io::Result<()> {
+    writer.write_all(&(tile_index as u32).to_le_bytes())?;
+    write_tile(writer, &result.rendered)?;
+    writer.write_all(&(result.aovs.len() as u32).to_le_bytes())?;
+    for aov in result.aovs.iter() {
+        write_aov(writer, aov)?;
+    }
+    writer.write_all(&(result.costs.len() as u32).to_le_bytes())?;
+    for cost in result.costs.iter() {
+        writer.write_all(&cost.to_le_bytes())?;
+    }
+    write_stats(writer, &result.stats)
+}
+
+// Fails if the tile doesn't fit the image, or doesn't have the AOVs and costs the render needs
+fn read_result<R: Read>(reader: &mut R, job: &Job) -> io::Result<(usize, TileResult)> {
+    let tile_index = read_u32(reader)? as usize;
+    let rendered = read_tile(reader, job.image_dimension.width, job.image_dimension.height)?;
+    let area = rendered.tile.area();
+    if read_u32(reader)? != if job.aovs { area } else { 0 } {
+        return Err(invalid("worker sent the wrong number of AOV samples"));
+    }
+    let mut aovs = Vec::new();
+    if job.aovs {
+        for _ in 0..area {
+            aovs.push(read_aov(reader)?);
+        }
+    }
+    if read_u32(reader)? != if job.heatmap { area } else { 0 } {
+        return Err(invalid("worker sent the wrong number of costs"));
+    }
+    let mut costs = Vec::new();
+    if job.heatmap {
+        for _ in 0..area {
+            costs.push(read_f64(reader)?);
+        }
+    }
+    let stats = read_stats(reader)?;
+    Ok((tile_index, TileResult { rendered, aovs, costs, stats }))
+}
+
+// The hit id stays behind, it wouldn't mean anything to another process
+fn write_aov<W: Write>(writer: &mut W, aov: &AovSample) -> io::Result<()> {
+    let (normal, uv, albedo) = (aov.normal, aov.uv, aov.albedo);
+    for num in [aov.depth, normal.x, normal.y, normal.z, uv.x, uv.y, albedo.red, albedo.green, albedo.blue].iter() {
+        writer.write_all(&num.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_aov<R: Read>(reader: &mut R) -> io::Result<AovSample> {
+    Ok(AovSample {
+        depth: read_f64(reader)?,
+        normal: dvec3!(read_f64(reader)?, read_f64(reader)?, read_f64(reader)?),
+        uv: dvec2!(read_f64(reader)?, read_f64(reader)?),
+        albedo: Color::new(read_f64(reader)?, read_f64(reader)?, read_f64(reader)?),
+        hit_id: None,
+    })
+}
+
+// Counts, then phase times in nanoseconds. Elapsed is left out, it's the coordinator's to work out.
+fn write_stats<W: Write>(writer: &mut W, stats: &RenderStats) -> io::Result<()> {
+    let counts = [stats.primary_rays, stats.secondary_rays, stats.shadow_rays, stats.bvh_node_visits, stats.mesh_triangle_tests];
+    for count in counts.iter().chain(stats.intersection_test_counts.iter()) {
+        writer.write_all(&count.to_le_bytes())?;
+    }
+    for time in [stats.trace_time, stats.anti_alias_time, stats.aov_time, stats.denoise_time].iter() {
+        writer.write_all(&(time.as_nanos() as u64).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_stats<R: Read>(reader: &mut R) -> io::Result<RenderStats> {
+    let mut stats = RenderStats {
+        primary_rays: read_u64(reader)?,
+        secondary_rays: read_u64(reader)?,
+        shadow_rays: read_u64(reader)?,
+        bvh_node_visits: read_u64(reader)?,
+        mesh_triangle_tests: read_u64(reader)?,
+        ..RenderStats::default()
+    };
+    for count in stats.intersection_test_counts.iter_mut() {
+        *count = read_u64(reader)?;
+    }
+    stats.trace_time = Duration::from_nanos(read_u64(reader)?);
+    stats.anti_alias_time = Duration::from_nanos(read_u64(reader)?);
+    stats.aov_time = Duration::from_nanos(read_u64(reader)?);
+    stats.denoise_time = Duration::from_nanos(read_u64(reader)?);
+    Ok(stats)
+}
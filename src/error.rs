@@ -0,0 +1,60 @@
+use std::fmt;
+use std::error;
+use std::io;
+use image::ImageError;
+use tobj::LoadError;
+
+// Something went wrong reading or writing a file, eg. a missing texture.
+// Every variant keeps the path, so it's clear which asset was the problem.
+#[derive(Debug)]
+pub enum Error {
+    Io { path: String, cause: io::Error },
+    Image { path: String, cause: ImageError },
+    Model { path: String, cause: LoadError },
+}
+
+impl Error {
+    pub fn io(path: &str, cause: io::Error) -> Error {
+        Error::Io { path: String::from(path), cause }
+    }
+
+    // Io errors from the image crate, like a missing file, are reported as Io
+    pub fn image(path: &str, cause: ImageError) -> Error {
+        match cause {
+            ImageError::IoError(cause) => Error::io(path, cause),
+            cause => Error::Image { path: String::from(path), cause },
+        }
+    }
+
+    pub fn model(path: &str, cause: LoadError) -> Error {
+        Error::Model { path: String::from(path), cause }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            Error::Io { path, .. } => path,
+            Error::Image { path, .. } => path,
+            Error::Model { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, cause } => write!(f, "could not access '{}': {}", path, cause),
+            Error::Image { path, cause } => write!(f, "could not use image '{}': {}", path, cause),
+            Error::Model { path, cause } => write!(f, "could not load model '{}': {}", path, cause),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { cause, .. } => Some(cause),
+            Error::Image { cause, .. } => Some(cause),
+            Error::Model { cause, .. } => Some(cause),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use euler::DVec3;
+use color::Color;
+use error::Error;
+use hdr::HdrImage;
+
+// Pixel types as numbered by the OpenEXR file format
+const EXR_UINT: i32 = 0;
+const EXR_FLOAT: i32 = 2;
+
+#[derive(Clone)]
+pub enum ChannelData {
+    Float(Vec<f32>),
+    // For things that shouldn't be filtered, like object ids
+    Uint(Vec<u32>),
+}
+
+impl ChannelData {
+    fn len(&self) -> usize {
+        match self {
+            ChannelData::Float(values) => values.len(),
+            ChannelData::Uint(values) => values.len(),
+        }
+    }
+
+    fn pixel_type(&self) -> i32 {
+        match self {
+            ChannelData::Float(_) => EXR_FLOAT,
+            ChannelData::Uint(_) => EXR_UINT,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ExrChannel {
+    pub name: String,
+    pub data: ChannelData,
+}
+
+// An uncompressed scanline OpenEXR image with any number of named channels.
+// Layers follow the usual "layer.channel" naming, eg. "normal.X", so compositing
+// tools can pick them apart. The main image goes in the unprefixed R, G and B channels.
+#[derive(Clone)]
+pub struct ExrImage {
+    pub width: u32,
+    pub height: u32,
+    channels: Vec<ExrChannel>,
+}
+
+impl ExrImage {
+    pub fn new(width: u32, height: u32) -> ExrImage {
+        ExrImage { width, height, channels: Vec::new() }
+    }
+
+    pub fn from_hdr_image(image: &HdrImage) -> ExrImage {
+        let mut exr = ExrImage::new(image.width, image.height);
+        exr.add_color_layer("", &image.pixels);
+        exr
+    }
+
+    pub fn get_channels(&self) -> &Vec<ExrChannel> {
+        &self.channels
+    }
+
+    // Adding a channel with the same name as an existing one replaces it
+    pub fn add_channel(&mut self, name: &str, data: ChannelData) {
+        assert_eq!(data.len(), (self.width * self.height) as usize, "wrong number of values for channel '{}'", name);
+        assert!(!name.is_empty() && name.len() < 32, "channel names must be between 1 and 31 bytes: '{}'", name);
+        self.channels.retain(|channel| channel.name != name);
+        self.channels.push(ExrChannel { name: String::from(name), data });
+    }
+
+    pub fn add_float_channel(&mut self, name: &str, values: &[f64]) {
+        self.add_channel(name, ChannelData::Float(values.iter().map(|value| *value as f32).collect()));
+    }
+
+    pub fn add_uint_channel(&mut self, name: &str, values: &[u32]) {
+        self.add_channel(name, ChannelData::Uint(values.to_vec()));
+    }
+
+    // Adds layer.R, layer.G and layer.B, or just R, G and B if the layer name is empty
+    pub fn add_color_layer(&mut self, layer: &str, colors: &[Color]) {
+        let prefix = layer_prefix(layer);
+        self.add_float_channel(&format!("{}R", prefix), &colors.iter().map(|color| color.red).collect::<Vec<f64>>());
+        self.add_float_channel(&format!("{}G", prefix), &colors.iter().map(|color| color.green).collect::<Vec<f64>>());
+        self.add_float_channel(&format!("{}B", prefix), &colors.iter().map(|color| color.blue).collect::<Vec<f64>>());
+    }
+
+    // Adds layer.X, layer.Y and layer.Z, eg. for normals or positions
+    pub fn add_vector_layer(&mut self, layer: &str, vectors: &[DVec3]) {
+        let prefix = layer_prefix(layer);
+        self.add_float_channel(&format!("{}X", prefix), &vectors.iter().map(|v| v.x).collect::<Vec<f64>>());
+        self.add_float_channel(&format!("{}Y", prefix), &vectors.iter().map(|v| v.y).collect::<Vec<f64>>());
+        self.add_float_channel(&format!("{}Z", prefix), &vectors.iter().map(|v| v.z).collect::<Vec<f64>>());
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        if let Some(directory) = Path::new(path).parent() {
+            fs::create_dir_all(directory).map_err(|cause| Error::io(path, cause))?;
+        }
+        let mut writer = BufWriter::new(File::create(path).map_err(|cause| Error::io(path, cause))?);
+        writer.write_all(&self.encode()).and_then(|_| writer.flush()).map_err(|cause| Error::io(path, cause))
+    }
+
+    // The whole file in memory, see https://www.openexr.com/documentation/openexrfilelayout.pdf
+    pub fn encode(&self) -> Vec<u8> {
+        // Readers expect channels sorted by name, both in the header and the pixel data
+        let mut channels: Vec<&ExrChannel> = self.channels.iter().collect();
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut out: Vec<u8> = Vec::new();
+        out.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+        // Version 2, single part scanline file
+        out.extend_from_slice(&2i32.to_le_bytes());
+
+        let mut channel_list: Vec<u8> = Vec::new();
+        for channel in channels.iter() {
+            channel_list.extend_from_slice(channel.name.as_bytes());
+            channel_list.push(0);
+            channel_list.extend_from_slice(&channel.data.pixel_type().to_le_bytes());
+            // pLinear and three reserved bytes
+            channel_list.extend_from_slice(&[0, 0, 0, 0]);
+            // x and y sampling
+            channel_list.extend_from_slice(&1i32.to_le_bytes());
+            channel_list.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channel_list.push(0);
+
+        let mut window: Vec<u8> = Vec::new();
+        for value in [0, 0, self.width as i32 - 1, self.height as i32 - 1].iter() {
+            window.extend_from_slice(&value.to_le_bytes());
+        }
+
+        write_attribute(&mut out, "channels", "chlist", &channel_list);
+        // No compression
+        write_attribute(&mut out, "compression", "compression", &[0]);
+        write_attribute(&mut out, "dataWindow", "box2i", &window);
+        write_attribute(&mut out, "displayWindow", "box2i", &window);
+        // Increasing y
+        write_attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+        write_attribute(&mut out, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+        write_attribute(&mut out, "screenWindowCenter", "v2f", &[0; 8]);
+        write_attribute(&mut out, "screenWindowWidth", "float", &1f32.to_le_bytes());
+        out.push(0);
+
+        // Every line gets its own block, each block is y, size, then the line of each channel in turn
+        let bytes_per_line = 4 * self.width as usize * channels.len();
+        let block_size = 8 + bytes_per_line;
+        let table_end = out.len() + 8 * self.height as usize;
+        for y in 0..self.height as usize {
+            out.extend_from_slice(&((table_end + y * block_size) as u64).to_le_bytes());
+        }
+
+        for y in 0..self.height as usize {
+            out.extend_from_slice(&(y as i32).to_le_bytes());
+            out.extend_from_slice(&(bytes_per_line as i32).to_le_bytes());
+            let line = y * self.width as usize..(y + 1) * self.width as usize;
+            for channel in channels.iter() {
+                match channel.data {
+                    ChannelData::Float(ref values) => for value in values[line.clone()].iter() {
+                        out.extend_from_slice(&value.to_le_bytes());
+                    },
+                    ChannelData::Uint(ref values) => for value in values[line.clone()].iter() {
+                        out.extend_from_slice(&value.to_le_bytes());
+                    },
+                }
+            }
+        }
+        out
+    }
+}
+
+fn layer_prefix(layer: &str) -> String {
+    if layer.is_empty() {
+        String::new()
+    }
+    else {
+        format!("{}.", layer)
+    }
+}
+
+fn write_attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
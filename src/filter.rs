@@ -0,0 +1,257 @@
+use std::f64::consts::PI;
+use std::ops::AddAssign;
+use color::Color;
+use render::ImageDimension;
+use tile::Tile;
+
+// The shape of a PixelFilter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    // Every sample counts the same, but only for the pixel it's in
+    Box,
+    // Falls off in a straight line
+    Tent,
+    Gaussian,
+    // Mitchell-Netravali with B = C = 1/3, sharper than a gaussian with a little ringing
+    Mitchell,
+    // Windowed sinc, the sharpest but rings the most
+    Lanczos,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 5] = [FilterKind::Box, FilterKind::Tent, FilterKind::Gaussian, FilterKind::Mitchell, FilterKind::Lanczos];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterKind::Box => "box",
+            FilterKind::Tent => "tent",
+            FilterKind::Gaussian => "gaussian",
+            FilterKind::Mitchell => "mitchell",
+            FilterKind::Lanczos => "lanczos",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FilterKind> {
+        FilterKind::ALL.iter().find(|kind| kind.name() == name).cloned()
+    }
+
+    // Radius in pixels each filter is usually used with
+    pub fn default_radius(&self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 2.0,
+        }
+    }
+}
+
+// How the samples around a pixel are weighted to get its color.
+// Anything wider than a box of radius 0.5 lets samples count towards the neighbouring pixels too,
+// which smooths out jagged edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelFilter {
+    pub kind: FilterKind,
+    // In pixels, from the middle of the pixel out to where samples stop counting
+    pub radius: f64,
+}
+
+impl PixelFilter {
+    pub fn new(kind: FilterKind, radius: f64) -> PixelFilter {
+        PixelFilter { kind, radius }
+    }
+
+    pub fn from_kind(kind: FilterKind) -> PixelFilter {
+        PixelFilter::new(kind, kind.default_radius())
+    }
+
+    // How much a sample dx, dy pixels away from the middle of a pixel counts towards it
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        if self.kind == FilterKind::Box {
+            // Half open, so a sample right on the border only counts for one pixel
+            let inside = |d: f64| d >= -self.radius && d < self.radius;
+            return if inside(dx) && inside(dy) { 1.0 } else { 0.0 };
+        }
+        self.weight_1d(dx.abs()) * self.weight_1d(dy.abs())
+    }
+
+    fn weight_1d(&self, d: f64) -> f64 {
+        let radius = self.radius;
+        if d >= radius {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => 1.0 - d / radius,
+            FilterKind::Gaussian => {
+                // Shifted down so it reaches 0 at the radius instead of stopping suddenly
+                let sigma = radius / 3.0;
+                let gaussian = |d: f64| (-d * d / (2.0 * sigma * sigma)).exp();
+                gaussian(d) - gaussian(radius)
+            },
+            FilterKind::Mitchell => mitchell(2.0 * d / radius),
+            FilterKind::Lanczos => sinc(d) * sinc(d / radius),
+        }
+    }
+
+    // How many pixels past its own a sample can reach
+    pub fn reach(&self) -> u32 {
+        (self.radius - 0.5).max(0.0).ceil() as u32
+    }
+}
+
+impl Default for PixelFilter {
+    fn default() -> PixelFilter {
+        PixelFilter::from_kind(FilterKind::Box)
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
+
+// Mitchell-Netravali cubic over 0..2 with B = C = 1/3
+fn mitchell(x: f64) -> f64 {
+    let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+    if x < 1.0 {
+        ((12.0 - 9.0*b - 6.0*c) * x*x*x + (-18.0 + 12.0*b + 6.0*c) * x*x + (6.0 - 2.0*b)) / 6.0
+    }
+    else if x < 2.0 {
+        ((-b - 6.0*c) * x*x*x + (6.0*b + 30.0*c) * x*x + (-12.0*b - 48.0*c) * x + (8.0*b + 24.0*c)) / 6.0
+    }
+    else {
+        0.0
+    }
+}
+
+// Film sums are kept in fixed point with this many bits after the point, so they add up to exactly
+// the same whichever order the samples come in, eg. when tiles of another size share out the samples
+// near their edges differently. That's finer than an f64 for anything brighter than 2^-28,
+// and leaves room for sums up to 2^47.
+const FILM_FRACTION_BITS: u32 = 80;
+const FILM_SCALE: f64 = (1u128 << FILM_FRACTION_BITS) as f64;
+
+fn to_fixed(value: f64) -> i128 {
+    (value * FILM_SCALE).round() as i128
+}
+
+fn from_fixed(value: i128) -> f64 {
+    value as f64 / FILM_SCALE
+}
+
+// What the samples that count towards a pixel add up to, the sum of their colors times their weights
+// and the sum of the weights
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FilmPixel {
+    pub red: i128,
+    pub green: i128,
+    pub blue: i128,
+    pub weight: i128,
+}
+
+impl FilmPixel {
+    // A single sample of color, counting for weight
+    pub fn sample(color: Color, weight: f64) -> FilmPixel {
+        FilmPixel {
+            red: to_fixed(color.red * weight),
+            green: to_fixed(color.green * weight),
+            blue: to_fixed(color.blue * weight),
+            weight: to_fixed(weight),
+        }
+    }
+
+    pub fn weight(&self) -> f64 {
+        from_fixed(self.weight)
+    }
+
+    // Weighted average of the samples, black without any. Filters with negative lobes can push
+    // a pixel below black, so that's clamped off.
+    pub fn color(&self) -> Color {
+        let weight = self.weight();
+        if weight > 0.0 {
+            let color = Color::new(from_fixed(self.red), from_fixed(self.green), from_fixed(self.blue)) / weight;
+            Color::new(color.red.max(0.0), color.green.max(0.0), color.blue.max(0.0))
+        }
+        else {
+            Color::BLACK
+        }
+    }
+}
+
+impl AddAssign for FilmPixel {
+    fn add_assign(&mut self, other: FilmPixel) {
+        self.red = self.red.saturating_add(other.red);
+        self.green = self.green.saturating_add(other.green);
+        self.blue = self.blue.saturating_add(other.blue);
+        self.weight = self.weight.saturating_add(other.weight);
+    }
+}
+
+// Filtered samples from rendering one tile. Samples near the edge of the tile count towards
+// pixels outside of it too, so region is the tile grown by the filter's reach.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilmTile {
+    pub region: Tile,
+    // One for each pixel of the region
+    pub pixels: Vec<FilmPixel>,
+}
+
+impl FilmTile {
+    pub fn new(tile: Tile, filter: PixelFilter, image_dimension: ImageDimension) -> FilmTile {
+        FilmTile::from_region(tile.grow(filter.reach(), image_dimension))
+    }
+
+    pub fn from_region(region: Tile) -> FilmTile {
+        FilmTile { region, pixels: vec![FilmPixel::default(); region.area() as usize] }
+    }
+
+    // Adds a sample taken at x, y in the image, where pixel (0, 0) covers 0..1 and its middle is at 0.5
+    pub fn add_sample(&mut self, filter: PixelFilter, x: f64, y: f64, color: Color) {
+        let radius = filter.radius;
+        let region = self.region;
+        let first_x = (x - 0.5 - radius).ceil().max(region.x as f64) as u32;
+        let last_x = (x - 0.5 + radius).floor().min((region.x + region.width) as f64 - 1.0);
+        let first_y = (y - 0.5 - radius).ceil().max(region.y as f64) as u32;
+        let last_y = (y - 0.5 + radius).floor().min((region.y + region.height) as f64 - 1.0);
+        if last_x < first_x as f64 || last_y < first_y as f64 {
+            return;
+        }
+        for pixel_y in first_y..=last_y as u32 {
+            for pixel_x in first_x..=last_x as u32 {
+                let weight = filter.weight(x - (pixel_x as f64 + 0.5), y - (pixel_y as f64 + 0.5));
+                if weight != 0.0 {
+                    let i = ((pixel_y - region.y) * region.width + pixel_x - region.x) as usize;
+                    self.pixels[i] += FilmPixel::sample(color, weight);
+                }
+            }
+        }
+    }
+}
+
+// Every tile's filtered samples added up over the whole image, in any order
+pub struct Film {
+    width: u32,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(image_dimension: ImageDimension) -> Film {
+        Film { width: image_dimension.width, pixels: vec![FilmPixel::default(); image_dimension.area() as usize] }
+    }
+
+    pub fn add_tile(&mut self, film_tile: &FilmTile) {
+        let region = film_tile.region;
+        for (i, (x, y)) in region.pixels().into_iter().enumerate() {
+            self.pixels[(y * self.width + x) as usize] += film_tile.pixels[i];
+        }
+    }
+
+    // Final color of every pixel
+    pub fn resolve(&self) -> Vec<Color> {
+        self.pixels.iter().map(FilmPixel::color).collect()
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use image::{RgbImage, ImageBuffer};
+use euler::{DVec2, DVec3};
+use snowflake::ProcessUniqueId;
+use color::Color;
+use hdr::HdrImage;
+use exr::ExrImage;
+use tone_map::ToneMapping;
+use aov::{AovConfig, AovPass, AovSample};
+use tile::Tile;
+
+// Everything a render produces before it gets turned into an image.
+// Colors are linear and unclamped, pixels are in rows starting from the top left.
+#[derive(Clone)]
+pub struct FrameBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub colors: Vec<Color>,
+    // How far the camera ray went before hitting something, infinite if it hit the background
+    pub distances: Vec<f64>,
+
+    // AOV passes, only there if they were asked for in the RenderConfig
+    pub depths: Option<Vec<f64>>,
+    pub normals: Option<Vec<DVec3>>,
+    pub uvs: Option<Vec<DVec2>>,
+    pub albedos: Option<Vec<Color>>,
+    pub object_ids: Option<Vec<u32>>,
+    // The node behind each object id, object id n belongs to objects[n-1]
+    pub objects: Vec<ProcessUniqueId>,
+    // What each pixel cost to render, only there for heatmap renders, see RenderConfig::heatmap
+    pub costs: Option<Vec<f64>>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u32, height: u32) -> FrameBuffer {
+        let area = (width * height) as usize;
+        FrameBuffer {
+            width,
+            height,
+            colors: vec![Color::BLACK; area],
+            distances: vec![f64::INFINITY; area],
+            depths: None,
+            normals: None,
+            uvs: None,
+            albedos: None,
+            object_ids: None,
+            objects: Vec::new(),
+            costs: None,
+        }
+    }
+
+    // Fills in the passes turned on in aovs from one sample per pixel
+    pub fn set_aovs(&mut self, aovs: AovConfig, samples: &[AovSample]) {
+        assert_eq!(samples.len(), self.colors.len(), "need one AOV sample per pixel");
+        if aovs.depth {
+            self.depths = Some(samples.iter().map(|sample| sample.depth).collect());
+        }
+        if aovs.normal {
+            self.normals = Some(samples.iter().map(|sample| sample.normal).collect());
+        }
+        if aovs.uv {
+            self.uvs = Some(samples.iter().map(|sample| sample.uv).collect());
+        }
+        if aovs.albedo {
+            self.albedos = Some(samples.iter().map(|sample| sample.albedo).collect());
+        }
+        if aovs.object_id {
+            // Node ids are handed out in the order nodes are made, so sorting them
+            // keeps object ids the same between renders of the same scene
+            let mut objects: Vec<ProcessUniqueId> = samples.iter().filter_map(|sample| sample.hit_id).collect();
+            objects.sort();
+            objects.dedup();
+            let lookup: HashMap<ProcessUniqueId, u32> = objects.iter().enumerate().map(|(i, id)| (*id, i as u32 + 1)).collect();
+            self.object_ids = Some(samples.iter().map(|sample| sample.hit_id.map_or(0, |id| lookup[&id])).collect());
+            self.objects = objects;
+        }
+    }
+
+    // Drops the passes that aren't turned on in aovs, eg. ones only rendered to guide the denoiser
+    pub fn keep_passes(&mut self, aovs: AovConfig) {
+        if !aovs.depth {
+            self.depths = None;
+        }
+        if !aovs.normal {
+            self.normals = None;
+        }
+        if !aovs.uv {
+            self.uvs = None;
+        }
+        if !aovs.albedo {
+            self.albedos = None;
+        }
+        if !aovs.object_id {
+            self.object_ids = None;
+            self.objects.clear();
+        }
+    }
+
+    // Just the pixels inside region, with all of their passes
+    pub fn crop(&self, region: Tile) -> FrameBuffer {
+        assert!(region.x + region.width <= self.width && region.y + region.height <= self.height, "crop goes past the edge of the framebuffer");
+        let indices: Vec<usize> = region.pixels().into_iter().map(|(x, y)| self.index(x, y)).collect();
+        fn pick<T: Copy>(values: &[T], indices: &[usize]) -> Vec<T> {
+            indices.iter().map(|&i| values[i]).collect()
+        }
+        FrameBuffer {
+            width: region.width,
+            height: region.height,
+            colors: pick(&self.colors, &indices),
+            distances: pick(&self.distances, &indices),
+            depths: self.depths.as_ref().map(|depths| pick(depths, &indices)),
+            normals: self.normals.as_ref().map(|normals| pick(normals, &indices)),
+            uvs: self.uvs.as_ref().map(|uvs| pick(uvs, &indices)),
+            albedos: self.albedos.as_ref().map(|albedos| pick(albedos, &indices)),
+            object_ids: self.object_ids.as_ref().map(|object_ids| pick(object_ids, &indices)),
+            objects: self.objects.clone(),
+            costs: self.costs.as_ref().map(|costs| pick(costs, &indices)),
+        }
+    }
+
+    pub fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    pub fn get_color(&self, x: u32, y: u32) -> Color {
+        self.colors[self.index(x, y)]
+    }
+
+    pub fn get_distance(&self, x: u32, y: u32) -> f64 {
+        self.distances[self.index(x, y)]
+    }
+
+    // The node seen through a pixel, needs the object id pass
+    pub fn get_object(&self, x: u32, y: u32) -> Option<ProcessUniqueId> {
+        match self.object_ids {
+            Some(ref ids) if ids[self.index(x, y)] > 0 => Some(self.objects[ids[self.index(x, y)] as usize - 1]),
+            _ => None,
+        }
+    }
+
+    pub fn has_pass(&self, pass: AovPass) -> bool {
+        match pass {
+            AovPass::Depth => self.depths.is_some(),
+            AovPass::Normal => self.normals.is_some(),
+            AovPass::Uv => self.uvs.is_some(),
+            AovPass::Albedo => self.albedos.is_some(),
+            AovPass::ObjectId => self.object_ids.is_some(),
+        }
+    }
+
+    pub fn to_hdr_image(&self) -> HdrImage {
+        HdrImage::from_pixels(self.width, self.height, self.colors.clone())
+    }
+
+    pub fn to_rgb_image(&self) -> RgbImage {
+        self.to_hdr_image().to_rgb_image()
+    }
+
+    pub fn tone_map(&self, tone_mapping: ToneMapping) -> RgbImage {
+        self.to_hdr_image().tone_map(tone_mapping)
+    }
+
+    // Beauty in R, G and B, plus a layer for every AOV pass, named after the pass
+    pub fn to_exr_image(&self) -> ExrImage {
+        let mut exr = ExrImage::from_hdr_image(&self.to_hdr_image());
+        if let Some(ref depths) = self.depths {
+            exr.add_float_channel("depth.Z", depths);
+        }
+        if let Some(ref normals) = self.normals {
+            exr.add_vector_layer("normal", normals);
+        }
+        if let Some(ref uvs) = self.uvs {
+            exr.add_float_channel("uv.U", &uvs.iter().map(|uv| uv.x).collect::<Vec<f64>>());
+            exr.add_float_channel("uv.V", &uvs.iter().map(|uv| uv.y).collect::<Vec<f64>>());
+        }
+        if let Some(ref albedos) = self.albedos {
+            exr.add_color_layer("albedo", albedos);
+        }
+        if let Some(ref object_ids) = self.object_ids {
+            exr.add_uint_channel("object_id", object_ids);
+        }
+        exr
+    }
+
+    // A pass made viewable as an 8 bit image, or None if it wasn't rendered.
+    // Depth goes from white up close to black far away, normals and uvs are shown as colors,
+    // and every object id gets its own color.
+    pub fn pass_to_rgb_image(&self, pass: AovPass) -> Option<RgbImage> {
+        let colors: Vec<Color> = match pass {
+            AovPass::Depth => {
+                let depths = self.depths.as_ref()?;
+                let far = depths.iter().cloned().filter(|depth| depth.is_finite()).fold(0.0, f64::max);
+                depths.iter().map(|depth| if depth.is_finite() && far > 0.0 {
+                    Color::from_f64(1.0 - depth / far)
+                } else {
+                    Color::BLACK
+                }).collect()
+            },
+            AovPass::Normal => self.normals.as_ref()?.iter().map(|n| Color::new(n.x, n.y, n.z) * 0.5 + Color::WHITE * 0.5).collect(),
+            AovPass::Uv => self.uvs.as_ref()?.iter().map(|uv| Color::new(uv.x, uv.y, 0.0)).collect(),
+            AovPass::Albedo => self.albedos.as_ref()?.clone(),
+            AovPass::ObjectId => self.object_ids.as_ref()?.iter().map(|id| id_color(*id)).collect(),
+        };
+        Some(ImageBuffer::from_fn(self.width, self.height, |x, y| colors[self.index(x, y)].clamp().to_rgb()))
+    }
+
+    // Average difference per pixel between two renders of the same size, see Color::diff
+    pub fn mean_difference(&self, other: &FrameBuffer) -> f64 {
+        assert!(self.width == other.width && self.height == other.height, "can't compare framebuffers of different sizes");
+        let total: f64 = self.colors.iter().zip(other.colors.iter()).map(|(a, b)| a.diff(*b)).sum();
+        total / self.colors.len() as f64
+    }
+}
+
+// Spreads ids around the color wheel so neighbouring ids look different
+fn id_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::BLACK;
+    }
+    let hue = (id as f64 * 0.618_033_988_75).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    match hue as u32 {
+        0 => Color::new(1.0, x, 0.0),
+        1 => Color::new(x, 1.0, 0.0),
+        2 => Color::new(0.0, 1.0, x),
+        3 => Color::new(0.0, x, 1.0),
+        4 => Color::new(x, 0.0, 1.0),
+        _ => Color::new(1.0, 0.0, x),
+    }
+}
@@ -2,13 +2,15 @@ use euler::{dmat4, DMat4, DVec3, dvec3, dvec4};
 use shader::Shadable;
 use color::*;
 use snowflake::ProcessUniqueId;
+use scene_file::{Statement, SceneFileError};
+use stats::PrimitiveKind;
 
 pub mod matrix;
 pub mod ray;
 pub mod surface_coord;
 pub mod intersect;
 
-pub use self::ray::Ray;
+pub use self::ray::{Ray, RayKind};
 pub use self::surface_coord::SurfaceCoord;
 pub use self::intersect::{NodeIntersect, Intersect};
 
@@ -27,6 +29,16 @@ pub trait Intersectable: IntersectableClone {
         }
         ret_intersects
     }
+
+    // Describes the shape as a `primitive` statement for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("primitive has no scene file description"))
+    }
+
+    // What the render stats count its intersection tests as
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Other
+    }
 }
 
 pub trait IntersectableClone {
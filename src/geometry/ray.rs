@@ -1,4 +1,31 @@
 use super::*;
+use sampler::sample_2d;
+use std::f64::consts::PI;
+
+// How a ray came about, so the ray tree of a pixel can tell them apart
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RayKind {
+    // Made with Ray::new, like the rays from the camera
+    Camera,
+    Reflected,
+    Transmitted,
+    // Bounced off in a random direction, see Ray::scatter_off
+    Scattered,
+    // Checking whether anything is in the way, see Scene::trace_shadow
+    Shadow,
+}
+
+impl RayKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RayKind::Camera => "camera",
+            RayKind::Reflected => "reflected",
+            RayKind::Transmitted => "transmitted",
+            RayKind::Scattered => "scattered",
+            RayKind::Shadow => "shadow",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
@@ -6,17 +33,18 @@ pub struct Ray {
     pub direction: DVec3,
     depth: u32,
     contribution: Color,
+    kind: RayKind,
 }
 
 impl Ray {
     pub const MIN_DISTANCE: f64 = 0.001;
     pub const MIN_CONTRIBUTION: f64 = 0.003;
     pub fn new(origin: DVec3, direction: DVec3, depth: u32) -> Ray {
-        Ray { origin, direction, depth, contribution: Color::from_f64(1.0) }
+        Ray { origin, direction, depth, contribution: Color::from_f64(1.0), kind: RayKind::Camera }
     }
 
     pub fn from_destination(origin: DVec3, destination: DVec3, depth: u32) -> Ray {
-        Ray { origin, direction: (destination - origin).normalize(), depth, contribution: Color::from_f64(1.0) }
+        Ray { origin, direction: (destination - origin).normalize(), depth, contribution: Color::from_f64(1.0), kind: RayKind::Camera }
     }
 
     pub fn contributes(&self, percentage: Color) -> Ray {
@@ -35,6 +63,10 @@ impl Ray {
         self.depth
     }
 
+    pub fn kind(&self) -> RayKind {
+        self.kind
+    }
+
     pub fn reflect_off(&self, hit_point: DVec3, surface_normal: DVec3) -> Ray {
         assert!(surface_normal.length() - 1.0 < 0.000001);
         // math
@@ -49,6 +81,7 @@ impl Ray {
             direction: reflection_direction,
             depth: self.depth - 1,
             contribution: self.contribution,
+            kind: RayKind::Reflected,
         }
     }
 
@@ -79,10 +112,40 @@ impl Ray {
                 direction: refraction_direction,
                 depth: self.depth - 1,
                 contribution: self.contribution,
+                kind: RayKind::Transmitted,
             }
         }
     }
 
+    // Bounces off in a random direction on the side of the surface the ray came from.
+    // Directions are cosine weighted, so rays near the normal are picked more often,
+    // the same way a matte surface scatters light.
+    pub fn scatter_off(&self, hit_point: DVec3, mut surface_normal: DVec3) -> Ray {
+        assert!(self.depth > 0);
+        if self.direction.dot(surface_normal) > 0.0 {
+            surface_normal *= -1.0;
+        }
+
+        // Any vector that isn't parallel to the normal will do for building a basis
+        let helper = if surface_normal.x.abs() > 0.9 { dvec3!(0.0, 1.0, 0.0) } else { dvec3!(1.0, 0.0, 0.0) };
+        let tangent = surface_normal.cross(helper).normalize();
+        let bitangent = surface_normal.cross(tangent);
+
+        let (u, v) = sample_2d();
+        let radius = u.sqrt();
+        let angle = 2.0 * PI * v;
+        let height = (1.0 - radius*radius).max(0.0).sqrt();
+        let scatter_direction = (radius * angle.cos() * tangent + radius * angle.sin() * bitangent + height * surface_normal).normalize();
+
+        Ray {
+            origin: hit_point,
+            direction: scatter_direction,
+            depth: self.depth - 1,
+            contribution: self.contribution,
+            kind: RayKind::Scattered,
+        }
+    }
+
     pub fn point_at_distance(&self, distance: f64) -> DVec3 {
         self.origin + (distance * self.direction)
     }
@@ -93,6 +156,7 @@ impl Ray {
             direction: (matrix * dvec4!(self.direction, 0.0)).xyz().normalize(),
             depth: self.depth,
             contribution: self.contribution,
+            kind: self.kind,
         }
     }
 }
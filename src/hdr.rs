@@ -0,0 +1,169 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+use image::{self, Rgb, RgbImage, ImageBuffer};
+use image::hdr::{HDRDecoder, HDREncoder};
+use color::{Color, ColorEncoding};
+use error::Error;
+use exr::ExrImage;
+use tone_map::ToneMapping;
+
+// An image of linear colors that haven't been clamped or sRGB encoded,
+// so everything brighter than 1.0 is kept around for tone mapping later
+#[derive(Clone)]
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+}
+
+impl HdrImage {
+    pub fn new(width: u32, height: u32) -> HdrImage {
+        HdrImage::from_pixels(width, height, vec![Color::BLACK; (width * height) as usize])
+    }
+
+    // Pixels are in rows, starting from the top left
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> HdrImage {
+        assert_eq!(pixels.len(), (width * height) as usize, "wrong number of pixels for a {}x{} image", width, height);
+        HdrImage { width, height, pixels }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+
+    // Clamped and sRGB encoded, same as what render_with_config gives by default
+    pub fn to_rgb_image(&self) -> RgbImage {
+        self.tone_map(ToneMapping::default())
+    }
+
+    pub fn tone_map(&self, tone_mapping: ToneMapping) -> RgbImage {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| tone_mapping.apply(self.get_pixel(x, y)).to_rgb())
+    }
+
+    // Reads a .hdr or .pfm file, or an 8 bit image like a PNG, which is taken to be sRGB encoded
+    pub fn open(path: &str) -> Result<HdrImage, Error> {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("hdr") => {
+                let file = File::open(path).map_err(|cause| Error::io(path, cause))?;
+                let decoder = HDRDecoder::new(BufReader::new(file)).map_err(|cause| Error::image(path, cause))?;
+                let metadata = decoder.metadata();
+                let data = decoder.read_image_hdr().map_err(|cause| Error::image(path, cause))?;
+                let pixels = data.iter().map(|rgb| Color::new(rgb.data[0] as f64, rgb.data[1] as f64, rgb.data[2] as f64)).collect();
+                Ok(HdrImage::from_pixels(metadata.width, metadata.height, pixels))
+            },
+            Some(ext) if ext.eq_ignore_ascii_case("pfm") => {
+                let bytes = fs::read(path).map_err(|cause| Error::io(path, cause))?;
+                read_pfm(&bytes).map_err(|cause| Error::io(path, cause))
+            },
+            _ => {
+                let image = image::open(path).map_err(|cause| Error::image(path, cause))?.to_rgb();
+                let pixels = image.pixels().map(|rgb| {
+                    let decode = |channel: u8| ColorEncoding::Srgb.decode(channel);
+                    Color::new(decode(rgb.data[0]), decode(rgb.data[1]), decode(rgb.data[2]))
+                }).collect();
+                Ok(HdrImage::from_pixels(image.width(), image.height(), pixels))
+            },
+        }
+    }
+
+    // Saves as Radiance RGBE (.hdr), Portable FloatMap (.pfm) or OpenEXR (.exr) depending on the extension
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        if let Some(directory) = Path::new(path).parent() {
+            fs::create_dir_all(directory).map_err(|cause| Error::io(path, cause))?;
+        }
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("hdr") => self.write_hdr(path),
+            Some(ext) if ext.eq_ignore_ascii_case("pfm") => self.write_pfm(path),
+            Some(ext) if ext.eq_ignore_ascii_case("exr") => return ExrImage::from_hdr_image(self).save(path),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "not a .hdr, .pfm or .exr file")),
+        }.map_err(|cause| Error::io(path, cause))
+    }
+
+    // RGBE can't store negative values, so those become black
+    pub fn write_hdr(&self, path: &str) -> io::Result<()> {
+        let data: Vec<Rgb<f32>> = self.pixels.iter().map(|color| Rgb {
+            data: [color.red.max(0.0) as f32, color.green.max(0.0) as f32, color.blue.max(0.0) as f32]
+        }).collect();
+        let writer = BufWriter::new(File::create(path)?);
+        HDREncoder::new(writer).encode(&data, self.width as usize, self.height as usize)
+    }
+
+    pub fn write_pfm(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        // A negative scale means the floats are little endian
+        write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+
+        // PFM rows go from the bottom of the image to the top
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let color = self.get_pixel(x, y);
+                for channel in [color.red, color.green, color.blue].iter() {
+                    writer.write_all(&(*channel as f32).to_le_bytes())?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+// Reads what write_pfm writes, plus big endian and greyscale (Pf) files
+fn read_pfm(bytes: &[u8]) -> io::Result<HdrImage> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("not a PFM file: {}", message));
+
+    // The header is three whitespace separated words, followed by a single whitespace character
+    let mut words = Vec::new();
+    let mut position = 0;
+    while words.len() < 4 {
+        while position < bytes.len() && (bytes[position] as char).is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while position < bytes.len() && !(bytes[position] as char).is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid("header is cut off"));
+        }
+        words.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+    }
+    position += 1;
+
+    let channels = match words[0].as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid("should start with PF or Pf")),
+    };
+    let width: u32 = words[1].parse().map_err(|_| invalid("bad width"))?;
+    let height: u32 = words[2].parse().map_err(|_| invalid("bad height"))?;
+    let scale: f32 = words[3].parse().map_err(|_| invalid("bad scale"))?;
+    let little_endian = scale < 0.0;
+
+    let size = width as usize * height as usize * channels * 4;
+    if bytes.len() < position + size {
+        return Err(invalid("pixels are cut off"));
+    }
+    let mut floats = bytes[position..position + size].chunks(4).map(|chunk| {
+        let chunk = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        (if little_endian { f32::from_le_bytes(chunk) } else { f32::from_be_bytes(chunk) }) as f64
+    });
+
+    // Rows go from the bottom of the image to the top
+    let mut image = HdrImage::new(width, height);
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let color = if channels == 3 {
+                Color::new(floats.next().unwrap(), floats.next().unwrap(), floats.next().unwrap())
+            } else {
+                Color::from_f64(floats.next().unwrap())
+            };
+            image.put_pixel(x, y, color);
+        }
+    }
+    Ok(image)
+}
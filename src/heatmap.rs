@@ -0,0 +1,85 @@
+use std::time::Instant;
+use color::{Color, srgb_decode};
+use stats::thread_intersection_tests;
+
+// Colors the heatmap goes through from the cheapest pixel to the most expensive, as sRGB
+const STOPS: [(f64, f64, f64); 5] = [
+    (0.05, 0.03, 0.30),
+    (0.10, 0.45, 0.95),
+    (0.20, 0.85, 0.40),
+    (0.98, 0.80, 0.10),
+    (0.85, 0.10, 0.05),
+];
+
+// What a heatmap render shows as the cost of a pixel, see RenderConfig::heatmap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CostMetric {
+    // Intersection tests against primitives, mesh bounding tree nodes and mesh triangles
+    Tests,
+    // Seconds spent tracing the pixel, which also shows slow shaders and lights.
+    // It's wall clock time, so with more threads than cores it also counts waiting for a turn.
+    Time,
+}
+
+impl CostMetric {
+    pub const ALL: [CostMetric; 2] = [CostMetric::Tests, CostMetric::Time];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CostMetric::Tests => "tests",
+            CostMetric::Time => "time",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CostMetric> {
+        CostMetric::ALL.iter().find(|metric| metric.name() == name).cloned()
+    }
+}
+
+// Measures the cost of the work done on this thread between start and finish
+#[derive(Debug, Clone, Copy)]
+pub struct CostMeter {
+    metric: CostMetric,
+    start: Instant,
+    tests: u64,
+}
+
+impl CostMeter {
+    pub fn start(metric: CostMetric) -> CostMeter {
+        CostMeter { metric, start: Instant::now(), tests: thread_intersection_tests() }
+    }
+
+    pub fn finish(&self) -> f64 {
+        match self.metric {
+            CostMetric::Tests => (thread_intersection_tests() - self.tests) as f64,
+            CostMetric::Time => self.start.elapsed().as_secs_f64(),
+        }
+    }
+}
+
+// Where value falls between dark blue for 0 and red for 1
+pub fn heatmap_color(value: f64) -> Color {
+    let position = value.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (position.floor() as usize).min(STOPS.len() - 2);
+    let t = position - i as f64;
+    let (from, to) = (STOPS[i], STOPS[i + 1]);
+    let mix = |a: f64, b: f64| srgb_decode(a + (b - a) * t);
+    Color::new(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+// The cost that's shown as red. It's the 99th percentile rather than the most expensive pixel,
+// so a few pixels that were held up by something else running don't make the rest look cheap.
+pub fn heatmap_scale(costs: &[f64]) -> f64 {
+    let mut sorted = costs.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    match sorted.len() {
+        0 => 0.0,
+        len => sorted[(len - 1) * 99 / 100],
+    }
+}
+
+// False colors for per pixel costs, from dark blue for nothing to red at heatmap_scale and above
+pub fn heatmap_colors(costs: &[f64]) -> Vec<Color> {
+    let scale = heatmap_scale(costs);
+    costs.iter().map(|cost| heatmap_color(if scale > 0.0 { cost / scale } else { 0.0 })).collect()
+}
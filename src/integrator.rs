@@ -0,0 +1,211 @@
+use color::Color;
+use scene::Scene;
+use geometry::Ray;
+use shader::{AmbientOcclusionShader, ambient_visibility};
+use scene_file::{Statement, SceneFileError};
+use std::f64;
+
+// Works out the color seen along each camera ray, picked with RenderConfig::integrator.
+// Swapping it changes how the whole image is rendered without touching the scene or its shaders.
+pub trait Integrator: IntegratorClone {
+    // Color seen along the ray, and how far away the first thing it hit was
+    fn trace(&self, scene: &Scene, ray: Ray) -> (f64, Color);
+
+    // Integrators that pick random directions are noisy,
+    // so every pixel averages RenderConfig::samples of them instead of being anti-aliased
+    fn is_random(&self) -> bool {
+        false
+    }
+
+    // Describes the integrator as an `integrator` statement for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("integrator has no scene file description"))
+    }
+}
+
+pub trait IntegratorClone {
+    fn clone_box(&self) -> Box<dyn Integrator + Send + Sync>;
+}
+
+impl<T> IntegratorClone for T
+where
+    T: 'static + Integrator + Send + Sync + Clone
+{
+    fn clone_box(&self) -> Box<dyn Integrator + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Integrator + Send + Sync> {
+    fn clone(&self) -> Box<dyn Integrator + Send + Sync> {
+        self.clone_box()
+    }
+}
+
+// Integrators by the name they go by in scene files and on the command line,
+// with their default settings
+pub fn integrator_from_name(name: &str) -> Option<Box<dyn Integrator + Send + Sync>> {
+    match name {
+        "whitted" => Some(WhittedIntegrator::new()),
+        "path" => Some(PathIntegrator::new()),
+        "ambient_occlusion" => Some(AmbientOcclusionIntegrator::new(AmbientOcclusionShader::RADIUS)),
+        other => DebugView::from_name(other).map(|view| DebugIntegrator::new(view) as Box<dyn Integrator + Send + Sync>),
+    }
+}
+
+// Direct light, ambient light, and reflection/refraction through the shaders, see Shadable::get_color
+#[derive(Clone)]
+pub struct WhittedIntegrator;
+
+impl WhittedIntegrator {
+    pub fn new() -> Box<WhittedIntegrator> {
+        Box::new(WhittedIntegrator)
+    }
+}
+
+impl Integrator for WhittedIntegrator {
+    fn trace(&self, scene: &Scene, ray: Ray) -> (f64, Color) {
+        scene.cast_ray_get_distance(ray)
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("integrator", "whitted"))
+    }
+}
+
+// Indirect light from random bounces instead of ambient light, see Shadable::get_path_color
+#[derive(Clone)]
+pub struct PathIntegrator;
+
+impl PathIntegrator {
+    pub fn new() -> Box<PathIntegrator> {
+        Box::new(PathIntegrator)
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn trace(&self, scene: &Scene, ray: Ray) -> (f64, Color) {
+        scene.cast_path_get_distance(ray)
+    }
+
+    fn is_random(&self) -> bool {
+        true
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("integrator", "path"))
+    }
+}
+
+// Only ambient occlusion, white where the surface is open and dark in creases, for lookdev.
+// Each ray checks one random direction, so it needs RenderConfig::samples per pixel to smooth out.
+#[derive(Clone)]
+pub struct AmbientOcclusionIntegrator {
+    pub radius: f64,
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(radius: f64) -> Box<AmbientOcclusionIntegrator> {
+        Box::new(AmbientOcclusionIntegrator { radius })
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn trace(&self, scene: &Scene, ray: Ray) -> (f64, Color) {
+        match scene.trace(ray) {
+            Some(node_intersect) => {
+                // Normal maps still count
+                let intersect = node_intersect.shader.modify_intersect(scene, node_intersect.intersect);
+                (intersect.distance, Color::from_f64(ambient_visibility(scene, intersect, self.radius, 1)))
+            },
+            None => (f64::INFINITY, Color::BLACK),
+        }
+    }
+
+    fn is_random(&self) -> bool {
+        true
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("integrator", "ambient_occlusion").with_number(self.radius))
+    }
+}
+
+// What the debug integrator shows instead of lighting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugView {
+    // World space normal after normal maps, mapped from -1..1 to 0..1
+    Normal,
+    // SurfaceCoord as red and green
+    Uv,
+    // White up close fading to black at DebugIntegrator::far
+    Depth,
+}
+
+impl DebugView {
+    pub const ALL: [DebugView; 3] = [DebugView::Normal, DebugView::Uv, DebugView::Depth];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DebugView::Normal => "normal",
+            DebugView::Uv => "uv",
+            DebugView::Depth => "depth",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DebugView> {
+        DebugView::ALL.iter().find(|view| view.name() == name).cloned()
+    }
+}
+
+// Shows the geometry of the scene without any lighting, for tracking down bad normals or uvs.
+// Misses are black.
+#[derive(Clone)]
+pub struct DebugIntegrator {
+    pub view: DebugView,
+    pub far: f64,
+}
+
+impl DebugIntegrator {
+    pub const FAR: f64 = 1000.0;
+
+    pub fn new(view: DebugView) -> Box<DebugIntegrator> {
+        Box::new(DebugIntegrator { view, far: DebugIntegrator::FAR })
+    }
+
+    pub fn with_far(far: f64) -> Box<DebugIntegrator> {
+        Box::new(DebugIntegrator { view: DebugView::Depth, far })
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn trace(&self, scene: &Scene, ray: Ray) -> (f64, Color) {
+        let node_intersect = match scene.trace(ray) {
+            Some(node_intersect) => node_intersect,
+            None => return (f64::INFINITY, Color::BLACK),
+        };
+        let intersect = node_intersect.intersect;
+        let color = match self.view {
+            DebugView::Normal => {
+                let normal = node_intersect.shader.modify_intersect(scene, intersect).surface_normal.normalize();
+                Color::new(normal.x, normal.y, normal.z) * 0.5 + Color::WHITE * 0.5
+            },
+            DebugView::Uv => {
+                let (u, v) = intersect.surface_coord.get_coord();
+                Color::new(u, v, 0.0)
+            },
+            DebugView::Depth => Color::from_f64((1.0 - intersect.distance / self.far).max(0.0)),
+        };
+        (intersect.distance, color)
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let statement = Statement::of_kind("integrator", self.view.name());
+        if self.view == DebugView::Depth && self.far != DebugIntegrator::FAR {
+            Ok(statement.with_number(self.far))
+        }
+        else {
+            Ok(statement)
+        }
+    }
+}
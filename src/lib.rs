@@ -19,8 +19,28 @@ pub mod texture;
 pub mod normal_map;
 pub mod asset_manager;
 pub mod mesh;
+pub mod scene_file;
+pub mod hdr;
+pub mod exr;
+pub mod framebuffer;
+pub mod aov;
+pub mod tone_map;
+pub mod error;
+pub mod integrator;
+pub mod progressive;
+pub mod tile;
+pub mod checkpoint;
+pub mod filter;
+pub mod sampler;
+pub mod denoise;
+pub mod ray_tree;
+pub mod stats;
+pub mod heatmap;
+pub mod distributed;
 
 use image::{RgbImage};
+use std::fs;
+use std::path::Path;
 pub use color::*;
 pub use scene::*;
 pub use shader::*;
@@ -36,11 +56,30 @@ pub use texture::*;
 pub use normal_map::*;
 pub use asset_manager::*;
 pub use mesh::*;
+pub use hdr::*;
+pub use exr::*;
+pub use framebuffer::*;
+pub use aov::*;
+pub use tone_map::*;
+pub use error::Error;
+pub use integrator::*;
+pub use progressive::*;
+pub use tile::*;
+pub use checkpoint::{Checkpoint, RenderedTile};
+pub use filter::*;
+pub use sampler::*;
+pub use denoise::*;
+pub use ray_tree::*;
+pub use stats::*;
+pub use heatmap::*;
+pub use distributed::*;
+pub use scene_file::{SceneDescription, SceneFileError};
 
-// TODO: make this more robust, so it creates directories as well
-pub fn write_to_png(img: RgbImage, file_name: &str) {
-    match img.save(format!("{}.png", file_name)) {
-        Ok(_) => println!("save successful"),
-        Err(msg) => println!("FAILED TO SAVE: {}",msg),
+// Saves the image as file_name.png, making any directories that don't exist yet
+pub fn write_to_png(img: RgbImage, file_name: &str) -> Result<(), Error> {
+    let path = format!("{}.png", file_name);
+    if let Some(directory) = Path::new(&path).parent() {
+        fs::create_dir_all(directory).map_err(|cause| Error::io(&path, cause))?;
     }
+    img.save(&path).map_err(|cause| Error::io(&path, cause))
 }
\ No newline at end of file
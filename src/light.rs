@@ -4,8 +4,8 @@ use std::f64;
 use scene::Scene;
 use geometry::{Ray, Intersect};
 use std::sync::Arc;
-use rand::prelude::*;
-use rand::distributions::{Distribution, Uniform};
+use sampler::sample_2d;
+use scene_file::{Statement, SceneFileError};
 
 const AMBIENT_PORTION : f64 = 0.01;
 
@@ -31,7 +31,7 @@ pub trait Lightable: LightableClone {
             let shadow_ray = Ray::new(intersect.hit_point, light_direction, 1);
             let light_distance = hit_to_light.length();
 
-            if let Some(_) = scene.root.partial_trace_until_distance(shadow_ray, light_distance) {
+            if scene.trace_shadow(shadow_ray, light_distance).is_some() {
                 ret_vec.push(Illum::Unlit);
             }
             else {
@@ -41,6 +41,15 @@ pub trait Lightable: LightableClone {
         }
         ret_vec
     }
+
+    // Describes the light as a `light` statement for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("light has no scene file description"))
+    }
+}
+
+fn falloff_statement(falloff: (f64, f64, f64)) -> Statement {
+    Statement::new("falloff").with_number(falloff.0).with_number(falloff.1).with_number(falloff.2)
 }
 
 pub trait LightableClone {
@@ -109,7 +118,7 @@ impl Lightable for DirectionLight {
 
         let shadow_ray = Ray::new(intersect.hit_point, -1.0*self.direction, 1);
 
-        if let Some(_) = scene.root.trace(shadow_ray) {
+        if scene.trace_shadow(shadow_ray, f64::INFINITY).is_some() {
             vec!(Illum::Unlit)
         }
         else {
@@ -117,6 +126,14 @@ impl Lightable for DirectionLight {
             vec!(Illum::Lit{surface_dot, light_direction: -1.0*self.direction, intensity})
         }
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("light", "direction").with_children(vec!(
+            Statement::new("direction").with_vec3(self.direction),
+            Statement::new("color").with_color(self.color),
+            Statement::new("power").with_number(self.power),
+        )))
+    }
 }
 
 #[derive(Clone)]
@@ -145,6 +162,15 @@ impl Lightable for PointLight {
     fn get_intensity(&self, distance: f64) -> Color {
         self.power * self.color / (self.falloff.0 + self.falloff.1*distance + self.falloff.2*distance*distance)
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("light", "point").with_children(vec!(
+            Statement::new("position").with_vec3(self.position),
+            Statement::new("color").with_color(self.color),
+            Statement::new("power").with_number(self.power),
+            falloff_statement(self.falloff),
+        )))
+    }
 }
 
 
@@ -183,12 +209,22 @@ impl SquareLight {
         self.color * self.power
     }
 
+    // Random offset from the middle of the light, somewhere in the min..max part of it
+    fn point_between(&self, min: DVec2, max: DVec2) -> DVec3 {
+        let (u, v) = sample_2d();
+        dvec3!(min.x + (max.x - min.x) * u, 0.0, min.y + (max.y - min.y) * v)
+    }
+
     const SAMPLE_RATE: usize = 400;
     const SUB_DEPTH: usize = 5;
 
     fn subdivide_points(&self, min: DVec2, max: DVec2, depth: usize) -> Vec<DVec3> {
         let change = max - min;
-        let mut rng = rand::thread_rng();
+        // Somewhere in -r..r for each axis
+        let jitter = |r: DVec2| -> DVec2 {
+            let (u, v) = sample_2d();
+            dvec2!((2.0*u - 1.0) * r.x, (2.0*v - 1.0) * r.y)
+        };
         if false {
             let change = change*0.1;
             let r = change * 0.5;
@@ -196,9 +232,10 @@ impl SquareLight {
             let mut ret_vec: Vec<DVec3> = Vec::with_capacity(8);
             for i in 0..10 {
                 for j in 0..10 {
-                    ret_vec.push(self.position + dvec3!(low.x+(i as f64 * change.x)+rng.gen_range(-r.x, r.x), 
+                    let offset = jitter(dvec2!(r.x, r.x));
+                    ret_vec.push(self.position + dvec3!(low.x+(i as f64 * change.x)+offset.x, 
                                                         0.0, 
-                                                        low.y+(j as f64 * change.y)+rng.gen_range(-r.x, r.x)));
+                                                        low.y+(j as f64 * change.y)+offset.y));
                 }
             }
             ret_vec
@@ -210,12 +247,10 @@ impl SquareLight {
             let right_low = dvec2!(low.x + change.x*0.5, low.y);
             let r = change * 0.25;
 
-            vec!(
-                self.position + dvec3!(left_high.x+rng.gen_range(-r.x, r.x), 0.0, left_high.y+rng.gen_range(-r.y, r.y)),
-                self.position + dvec3!(high.x+rng.gen_range(-r.x, r.x), 0.0, high.y+rng.gen_range(-r.y, r.y)),
-                self.position + dvec3!(low.x+rng.gen_range(-r.x, r.x), 0.0, low.y+rng.gen_range(-r.y, r.y)),
-                self.position + dvec3!(right_low.x+rng.gen_range(-r.x, r.x), 0.0, right_low.y+rng.gen_range(-r.y, r.y)),
-            )
+            [left_high, high, low, right_low].iter().map(|corner| {
+                let point = *corner + jitter(r);
+                self.position + dvec3!(point.x, 0.0, point.y)
+            }).collect()
         }
     }
 
@@ -223,11 +258,8 @@ impl SquareLight {
         let num_illums = (4 as usize).pow(depth as u32);
         let area_porp = 4.0 * area_porp / (num_illums as f64);
         let mut ret_vec: Vec<Illum> = Vec::with_capacity(num_illums);
-        let between_x = Uniform::from(min.x..max.x);
-        let between_y = Uniform::from(min.y..max.y);
-        let mut rng = rand::thread_rng();
         for _ in 0..num_illums {
-            let point = self.position + dvec3!(between_x.sample(&mut rng), 0.0, between_y.sample(&mut rng));
+            let point = self.position + self.point_between(min, max);
             let hit_to_light = point - intersect.hit_point;
             let light_distance = hit_to_light.length();
             let light_direction = hit_to_light / light_distance;
@@ -263,7 +295,7 @@ impl SquareLight {
             let shadow_ray = Ray::new(intersect.hit_point, light_direction, 1);
             let light_distance = hit_to_light.length();
 
-            if let Some(_) = scene.root.partial_trace_until_distance(shadow_ray, light_distance) {
+            if scene.trace_shadow(shadow_ray, light_distance).is_some() {
                 sample_illums.push(Illum::Unlit);
             }
             else {
@@ -299,17 +331,15 @@ impl SquareLight {
 
     fn trace_sample_rays(&self, scene: &Scene, intersect: &Intersect, num_rays: usize) -> (usize, Vec<Illum>) {
         let mut ret_vec: Vec<Illum> = Vec::with_capacity(num_rays);
-        let between_x = Uniform::from(-self.size*0.5..self.size*0.5);
-        let between_y = Uniform::from(-self.size*0.5..self.size*0.5);
-        let mut rng = rand::thread_rng();
+        let half_size = dvec2!(self.size*0.5, self.size*0.5);
         let mut count: usize = 0;
         for i in 0..num_rays {
-            let sample_point = self.position + dvec3!(between_x.sample(&mut rng), 0.0, between_y.sample(&mut rng));
+            let sample_point = self.position + self.point_between(half_size * -1.0, half_size);
             let ray_vector = sample_point - intersect.hit_point;
             let light_distance = ray_vector.length();
             let light_direction = ray_vector / light_distance;
             let ray = Ray::new(intersect.hit_point, light_direction, 1);            
-            if let Some(_) = scene.root.partial_trace_until_distance(ray, light_distance) {
+            if scene.trace_shadow(ray, light_distance).is_some() {
                 ret_vec.push(Illum::Unlit);
             }
             else {
@@ -354,10 +384,9 @@ impl SquareLight {
 impl Lightable for SquareLight {
     fn get_sample(&self) -> Vec<DVec3> {
         let mut ret_vec: Vec<DVec3> = vec!();
-        let between = Uniform::from(-self.size*0.5..self.size*0.5);
-        let mut rng = rand::thread_rng();
+        let half_size = dvec2!(self.size*0.5, self.size*0.5);
         for i in 0..SquareLight::SAMPLE_RATE {
-            ret_vec.push(self.position + dvec3!(between.sample(&mut rng), 0, between.sample(&mut rng)));
+            ret_vec.push(self.position + self.point_between(half_size * -1.0, half_size));
         }
         ret_vec
     }
@@ -372,5 +401,15 @@ impl Lightable for SquareLight {
         self.subdivide_illumination(dvec2!(-half_size, -half_size), dvec2!(half_size, half_size), scene, &intersect, SquareLight::SUB_DEPTH)
         //self.progressive_random_illumination(scene, &intersect)
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("light", "square").with_children(vec!(
+            Statement::new("position").with_vec3(self.position),
+            Statement::new("size").with_number(self.size),
+            Statement::new("color").with_color(self.color),
+            Statement::new("power").with_number(self.power),
+            falloff_statement(self.falloff),
+        )))
+    }
 }
 
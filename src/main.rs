@@ -3,77 +3,644 @@ extern crate euler;
 
 use raytracer::*;
 use euler::*;
-use std::f64::consts::PI;
+use std::env;
+use std::io::{self, BufRead};
+use std::net::TcpListener;
+use std::process;
+use std::str::FromStr;
 use std::path::Path;
+use std::time::Duration;
 
-fn light1() -> Box<PointLight> {
-    Box::new(PointLight::new(dvec3!(-100.0, 300.0, 300.0), Color::new(1.0, 1.0, 1.0), 150000.0, (0.0, 0.0, 1.0*PI)))
+const USAGE: &str = "\
+Usage: raytracer <scene file> [options]
+       raytracer denoise <image> [denoise options]
+
+Renders a scene file to an image. Options override the settings in the scene file.
+When it's done it prints how many rays it traced and where the time went.
+
+Options:
+    -o, --output <path>           Image to write, format taken from the extension (default: <scene name>.png)
+                                  .hdr, .pfm and .exr keep the full range of linear colors
+    -s, --size <width>x<height>   Resolution of the image
+        --camera-origin <x,y,z>   Where the camera is
+        --camera-target <x,y,z>   What the camera looks at
+        --camera-up <x,y,z>       Which way is up for the camera
+        --fov <degrees>           Vertical field of view
+    -t, --threads <count>         Number of worker threads
+        --tile-size <pixels>      Width and height of the squares the image is rendered in
+        --tile-order <order>      Which tiles are rendered first: spiral (from the middle),
+                                  hilbert or scanline
+        --checkpoint <path>       Save finished tiles here while rendering, and pick them up
+                                  again if the same render is started after an interruption
+        --crop <x,y,w,h>          Only render this rectangle of the image, eg. 200,120,64,32
+        --aa <on|off>             Anti-aliasing, adds rays to each pixel until its color settles
+        --aa-noise <value>        How much noise is left in an anti-aliased pixel, eg. 0.02 for 2%
+        --aa-min-samples <count>  Rays every anti-aliased pixel gets
+        --aa-max-samples <count>  Most rays an anti-aliased pixel gets
+        --filter <name>           How samples are spread over nearby pixels: box, tent, gaussian,
+                                  mitchell or lanczos
+        --filter-radius <pixels>  How far the filter reaches, each filter has its own default
+        --sampler <name>          Where random numbers come from: independent, stratified,
+                                  halton or sobol
+        --seed <number>           Picks the random numbers, the same seed gives the same image
+    -d, --depth <count>           How many times a ray can reflect/refract through the scene
+        --integrator <name>       How rays are turned into colors: whitted, path (random bounces
+                                  for indirect light), ambient_occlusion, or the unlit views
+                                  normal, uv and depth
+        --samples <count>         Rays per pixel for random integrators like path
+        --progressive <samples>   Refine the image in passes up to this many samples per pixel,
+                                  rewriting the output after every pass
+        --time-limit <seconds>    Stop refining after this long, turns on --progressive
+        --noise-threshold <value> Stop refining once every pixel's relative noise is below this,
+                                  eg. 0.01, turns on --progressive
+        --tone-map <operator>     How highlights are brought into range: clamp, reinhard or aces
+        --exposure <stops>        Brightens or darkens the render before tone mapping
+        --aov <passes>            Extra passes to render, comma separated or 'all':
+                                  depth, normal, uv, albedo, object_id
+                                  Written as layers of an .exr, otherwise as <output>.<pass>.png
+        --denoise <on|off>        Smooth out the noise left in the finished image
+        --heatmap <cost>          Color pixels by how much work they took instead, from blue to red:
+                                  tests (intersection tests) or time (use a thread per core at most)
+        --interactive             Render pixels typed on stdin as 'x y' and print what they hit,
+                                  instead of rendering an image
+        --debug-pixel <x,y>       Print every ray traced for this pixel instead of rendering
+        --debug-format <format>   How --debug-pixel prints the rays: text or json
+        --listen <address>        Hand the tiles out to worker processes connecting here instead of
+                                  rendering them, eg. 0.0.0.0:7878. Waits until they're all back.
+        --worker <address>        Render tiles for the coordinator listening at address until it's
+                                  done, using the coordinator's settings. The scene file has to be
+                                  the same as the coordinator's, only --threads is used.
+    -h, --help                    Print this message
+
+Denoise options, for an image that's already been rendered (.png, .hdr or .pfm):
+    -o, --output <path>           Denoised image to write (default: <image name>.denoised.<ext>)
+        --normal <path>           Normal pass to keep edges sharp, eg. render.normal.png
+        --depth <path>            Depth pass, eg. render.depth.png
+        --albedo <path>           Albedo pass to keep textures sharp, eg. render.albedo.png
+        --iterations <count>      Passes of the filter, more smooths out blotchier noise
+";
+
+// Settings given on the command line, anything left as None comes from the scene file
+struct Options {
+    scene_path: String,
+    output_path: String,
+    size: Option<ImageDimension>,
+    camera_origin: Option<DVec3>,
+    camera_target: Option<DVec3>,
+    camera_up: Option<DVec3>,
+    fov_y: Option<f64>,
+    num_threads: Option<usize>,
+    tile_size: Option<u32>,
+    tile_order: Option<TileOrder>,
+    checkpoint: Option<String>,
+    crop: Option<Tile>,
+    anti_alias: Option<bool>,
+    aa_noise_threshold: Option<f64>,
+    aa_min_samples: Option<u32>,
+    aa_max_samples: Option<u32>,
+    filter: Option<FilterKind>,
+    filter_radius: Option<f64>,
+    sampler: Option<SamplerKind>,
+    seed: Option<u32>,
+    recursion_depth: Option<u32>,
+    integrator: Option<Box<dyn Integrator + Send + Sync>>,
+    samples: Option<u32>,
+    progressive_samples: Option<u32>,
+    time_limit: Option<f64>,
+    noise_threshold: Option<f64>,
+    tone_map: Option<ToneMapOperator>,
+    exposure: Option<f64>,
+    aovs: Option<AovConfig>,
+    denoise: Option<bool>,
+    heatmap: Option<CostMetric>,
+    interactive: bool,
+    debug_pixel: Option<(u32, u32)>,
+    debug_format: DebugFormat,
+    listen: Option<String>,
+    worker: Option<String>,
+}
+
+// How the ray tree of --debug-pixel is printed
+#[derive(Clone, Copy)]
+enum DebugFormat {
+    Text,
+    Json,
+}
+
+// Settings for denoising an image file, see `raytracer denoise`
+struct DenoiseOptions {
+    image_path: String,
+    output_path: String,
+    guides: DenoiseGuidePaths,
+    config: DenoiseConfig,
+}
+
+enum Command {
+    Render(Box<Options>),
+    Denoise(DenoiseOptions),
+    Help,
+}
+
+impl Options {
+    fn new(scene_path: String) -> Options {
+        let stem = Path::new(&scene_path).file_stem().and_then(|stem| stem.to_str()).unwrap_or("render").to_string();
+        Options {
+            scene_path,
+            output_path: format!("{}.png", stem),
+            size: None,
+            camera_origin: None,
+            camera_target: None,
+            camera_up: None,
+            fov_y: None,
+            num_threads: None,
+            tile_size: None,
+            tile_order: None,
+            checkpoint: None,
+            crop: None,
+            anti_alias: None,
+            aa_noise_threshold: None,
+            aa_min_samples: None,
+            aa_max_samples: None,
+            filter: None,
+            filter_radius: None,
+            sampler: None,
+            seed: None,
+            recursion_depth: None,
+            integrator: None,
+            samples: None,
+            progressive_samples: None,
+            time_limit: None,
+            noise_threshold: None,
+            tone_map: None,
+            exposure: None,
+            aovs: None,
+            denoise: None,
+            heatmap: None,
+            interactive: false,
+            debug_pixel: None,
+            debug_format: DebugFormat::Text,
+            listen: None,
+            worker: None,
+        }
+    }
+
+    fn apply(&self, description: &mut SceneDescription) {
+        if let Some(size) = self.size { description.image = size; }
+        if let Some(origin) = self.camera_origin { description.camera.origin = origin; }
+        if let Some(target) = self.camera_target { description.camera.target = target; }
+        if let Some(up) = self.camera_up { description.camera.up = up; }
+        if let Some(fov_y) = self.fov_y { description.camera.fov_y = fov_y; }
+        if let Some(num_threads) = self.num_threads { description.render.num_threads = num_threads; }
+        if let Some(tile_size) = self.tile_size { description.render.tile_size = tile_size; }
+        if let Some(tile_order) = self.tile_order { description.render.tile_order = tile_order; }
+        if let Some(ref checkpoint) = self.checkpoint { description.render.checkpoint = Some(checkpoint.clone()); }
+        if let Some(crop) = self.crop { description.render.crop = Some(crop); }
+        if let Some(anti_alias) = self.anti_alias { description.render.anti_alias = anti_alias; }
+        if let Some(aa_noise_threshold) = self.aa_noise_threshold { description.render.aa_noise_threshold = aa_noise_threshold; }
+        if let Some(aa_min_samples) = self.aa_min_samples { description.render.aa_min_samples = aa_min_samples; }
+        if let Some(aa_max_samples) = self.aa_max_samples { description.render.aa_max_samples = aa_max_samples; }
+        if let Some(kind) = self.filter { description.render.filter = PixelFilter::from_kind(kind); }
+        if let Some(radius) = self.filter_radius { description.render.filter.radius = radius; }
+        if let Some(sampler) = self.sampler { description.render.sampler = sampler; }
+        if let Some(seed) = self.seed { description.render.seed = seed; }
+        if let Some(recursion_depth) = self.recursion_depth { description.render.recursion_depth = recursion_depth; }
+        if let Some(ref integrator) = self.integrator { description.render.integrator = integrator.clone(); }
+        if let Some(samples) = self.samples { description.render.samples = samples; }
+        if let Some(operator) = self.tone_map { description.render.tone_mapping.operator = operator; }
+        if let Some(exposure) = self.exposure { description.render.tone_mapping.exposure = exposure; }
+        if let Some(aovs) = self.aovs { description.render.aovs = aovs; }
+        if let Some(denoise) = self.denoise {
+            description.render.denoise = if denoise { Some(description.render.denoise.unwrap_or_default()) } else { None };
+        }
+        if let Some(metric) = self.heatmap { description.render.heatmap = Some(metric); }
+        if self.progressive_samples.is_some() || self.time_limit.is_some() || self.noise_threshold.is_some() {
+            let mut progressive = description.render.progressive.unwrap_or_default();
+            if let Some(samples) = self.progressive_samples { progressive.samples = samples; }
+            if let Some(seconds) = self.time_limit { progressive.time_limit = Some(Duration::from_secs_f64(seconds)); }
+            if let Some(noise_threshold) = self.noise_threshold { progressive.noise_threshold = Some(noise_threshold); }
+            description.render.progressive = Some(progressive);
+        }
+    }
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("invalid value '{}' for {}", value, flag))
+}
+
+fn parse_positive<T: FromStr + PartialOrd + Default>(flag: &str, value: &str) -> Result<T, String> {
+    let num = parse_number::<T>(flag, value)?;
+    if num > T::default() {
+        Ok(num)
+    }
+    else {
+        Err(format!("{} must be greater than 0", flag))
+    }
+}
+
+fn parse_vec3(flag: &str, value: &str) -> Result<DVec3, String> {
+    let nums = value.split(',').map(|num| parse_number::<f64>(flag, num.trim())).collect::<Result<Vec<f64>, String>>()?;
+    if nums.len() != 3 {
+        return Err(format!("{} needs 3 comma separated numbers, eg. 0,100,-50", flag));
+    }
+    Ok(dvec3!(nums[0], nums[1], nums[2]))
+}
+
+fn parse_size(flag: &str, value: &str) -> Result<ImageDimension, String> {
+    let mut parts = value.splitn(2, ['x', 'X']);
+    match (parts.next(), parts.next()) {
+        (Some(width), Some(height)) => Ok(ImageDimension::new(parse_positive(flag, width)?, parse_positive(flag, height)?)),
+        _ => Err(format!("{} should look like 640x480", flag)),
+    }
 }
 
-fn light2() -> Box<PointLight> {
-    Box::new(PointLight::new(dvec3!(100.0, 300.0, -300.0), Color::new(1.0, 1.0, 1.0), 150000.0, (0.0, 0.0, 1.0*PI)))
+fn parse_crop(flag: &str, value: &str) -> Result<Tile, String> {
+    let nums = value.split(',').map(|num| parse_number::<u32>(flag, num.trim())).collect::<Result<Vec<u32>, String>>()?;
+    if nums.len() != 4 || nums[2] == 0 || nums[3] == 0 {
+        return Err(format!("{} needs x,y,width,height with a width and height of at least 1, eg. 200,120,64,32", flag));
+    }
+    Ok(Tile::new(nums[0], nums[1], nums[2], nums[3]))
 }
 
-fn light3() -> Box<PointLight> {
-    Box::new(PointLight::new(dvec3!(-300.0, 300.0, 300.0), Color::new(1.0, 1.0, 1.0), 450000.0, (0.0, 0.0, 1.0*PI)))
+fn parse_pixel(flag: &str, value: &str) -> Result<(u32, u32), String> {
+    let nums = value.split(',').map(|num| parse_number::<u32>(flag, num.trim())).collect::<Result<Vec<u32>, String>>()?;
+    if nums.len() != 2 {
+        return Err(format!("{} needs x,y, eg. 320,240", flag));
+    }
+    Ok((nums[0], nums[1]))
 }
 
-fn light4() -> Box<PointLight> {
-    Box::new(PointLight::new(dvec3!(0.0, -300.0, 0.0), Color::new(1.0, 1.0, 1.0), 150000.0, (0.0, 0.0, 1.0*PI)))
+fn parse_debug_format(flag: &str, value: &str) -> Result<DebugFormat, String> {
+    match value {
+        "text" => Ok(DebugFormat::Text),
+        "json" => Ok(DebugFormat::Json),
+        _ => Err(format!("unknown format '{}' for {}, expected text or json", value, flag)),
+    }
 }
 
-fn front_light() -> Box<PointLight> {
-    Box::new(PointLight::new(dvec3!(0.0, 0.0, 1000.0), Color::new(1.0, 1.0, 1.0), 1.0, (1.0, 0.0, 0.0)))
+fn parse_switch(flag: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" | "yes" => Ok(true),
+        "off" | "false" | "no" => Ok(false),
+        _ => Err(format!("{} should be 'on' or 'off'", flag)),
+    }
 }
 
-fn square_light(pos: DVec3, size: f64) -> Box<SquareLight> {
-    SquareLight::new(pos, size, Color::WHITE, 1500000.0, (0.0, 0.0, 4.0*PI))
+fn parse_integrator(flag: &str, value: &str) -> Result<Box<dyn Integrator + Send + Sync>, String> {
+    integrator_from_name(value).ok_or_else(|| format!("unknown integrator '{}' for {}", value, flag))
 }
 
-fn default_material(color: Color) -> Box<PhongShader> {
-    PhongShader::new(color*0.5, color*0.5, color*0.01, 4.0)
+fn parse_tile_order(flag: &str, value: &str) -> Result<TileOrder, String> {
+    TileOrder::from_name(value).ok_or_else(|| format!("unknown tile order '{}' for {}, expected spiral, hilbert or scanline", value, flag))
 }
 
-fn mesh_basic() {
-    let scene = build_scene(
-        vec!(light3()),
-        no_ambient(),
-        None,
-        scene_node(
-            DMat4::identity(),
-            vec!(
-                create_room_from_material(700.0, RoomMaterialScheme {
-                    ceiling: default_material(Color::RED),
-                    floor: default_material(Color::BLUE),
-                    front: default_material(Color::MAGENTA),
-                    back: default_material(Color::CYAN),
-                    left: default_material(Color::YELLOW),
-                    right: default_material(Color::GREEN),
-                }),
-                geometry_node(
-                    translation(0.0, 0.0, 0.0)*scaling(200.0, 200.0, 200.0)*rotation(Axis::Y, -30.0),
-                    texture_phong_material("assets/images/textures/test3.png", 1.0, 0.0, 0.0, 1.0),
-                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")),
-                    vec!(),
-                ),
-                /*
-                geometry_node(
-                    translation(0.0, 0.0, 0.0)*scaling(100.0, 100.0, 100.0),
-                    default_material(Color::RED),
-                    Cube::new(4.0),
-                    vec!(),
-                ),
-                */
-            ),
-        ),
-    );
+fn parse_filter(flag: &str, value: &str) -> Result<FilterKind, String> {
+    FilterKind::from_name(value).ok_or_else(|| format!("unknown filter '{}' for {}, expected box, tent, gaussian, mitchell or lanczos", value, flag))
+}
+
+fn parse_sampler(flag: &str, value: &str) -> Result<SamplerKind, String> {
+    SamplerKind::from_name(value).ok_or_else(|| format!("unknown sampler '{}' for {}, expected independent, stratified, halton or sobol", value, flag))
+}
+
+fn parse_tone_map(flag: &str, value: &str) -> Result<ToneMapOperator, String> {
+    ToneMapOperator::from_name(value).ok_or_else(|| format!("unknown tone map '{}' for {}, expected clamp, reinhard or aces", value, flag))
+}
+
+fn parse_cost_metric(flag: &str, value: &str) -> Result<CostMetric, String> {
+    CostMetric::from_name(value).ok_or_else(|| format!("unknown cost '{}' for {}, expected tests or time", value, flag))
+}
 
-    let mut config = RenderConfig::default();
-    config.anti_alias = false;
-    let image = render_with_config(scene, image(256, 256), camera([-310.0, 200.0, 300.0], [0.0, 0.0, 0.0]), config);
-    write_to_png( image, "output/mesh_basic");
+fn parse_aovs(flag: &str, value: &str) -> Result<AovConfig, String> {
+    if value == "all" {
+        return Ok(AovConfig::all());
+    }
+    let passes = value.split(',').map(|name| AovPass::from_name(name.trim())
+        .ok_or_else(|| format!("unknown pass '{}' for {}, expected depth, normal, uv, albedo or object_id", name, flag)))
+        .collect::<Result<Vec<AovPass>, String>>()?;
+    Ok(AovConfig::from_passes(&passes))
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        return Ok(Command::Help);
+    }
+    if args.first().is_some_and(|arg| arg == "denoise") {
+        return parse_denoise_args(&args[1..]);
+    }
+
+    let mut scene_path: Option<String> = None;
+    let mut flags: Vec<(&str, &str)> = Vec::new();
+    let mut interactive = false;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--interactive" {
+            interactive = true;
+        }
+        else if arg.starts_with('-') && arg.len() > 1 {
+            match args.get(i+1) {
+                Some(value) => flags.push((arg, value.as_str())),
+                None => return Err(format!("{} needs a value", arg)),
+            }
+            i += 1;
+        }
+        else if scene_path.is_none() {
+            scene_path = Some(arg.to_string());
+        }
+        else {
+            return Err(format!("unexpected argument '{}'", arg));
+        }
+        i += 1;
+    }
+
+    let mut options = match scene_path {
+        Some(path) => Options::new(path),
+        None => return Err(String::from("no scene file given")),
+    };
+    options.interactive = interactive;
+
+    for (flag, value) in flags {
+        match flag {
+            "-o" | "--output" => options.output_path = value.to_string(),
+            "-s" | "--size" => options.size = Some(parse_size(flag, value)?),
+            "--camera-origin" => options.camera_origin = Some(parse_vec3(flag, value)?),
+            "--camera-target" => options.camera_target = Some(parse_vec3(flag, value)?),
+            "--camera-up" => options.camera_up = Some(parse_vec3(flag, value)?),
+            "--fov" => {
+                let fov_y: f64 = parse_positive(flag, value)?;
+                if fov_y >= 180.0 {
+                    return Err(format!("{} must be less than 180", flag));
+                }
+                options.fov_y = Some(fov_y);
+            },
+            "-t" | "--threads" => options.num_threads = Some(parse_positive(flag, value)?),
+            "--tile-size" => options.tile_size = Some(parse_positive(flag, value)?),
+            "--tile-order" => options.tile_order = Some(parse_tile_order(flag, value)?),
+            "--checkpoint" => options.checkpoint = Some(value.to_string()),
+            "--crop" => options.crop = Some(parse_crop(flag, value)?),
+            "--aa" => options.anti_alias = Some(parse_switch(flag, value)?),
+            "--aa-noise" => options.aa_noise_threshold = Some(parse_positive(flag, value)?),
+            "--aa-min-samples" => options.aa_min_samples = Some(parse_positive(flag, value)?),
+            "--aa-max-samples" => options.aa_max_samples = Some(parse_positive(flag, value)?),
+            "--filter" => options.filter = Some(parse_filter(flag, value)?),
+            "--filter-radius" => options.filter_radius = Some(parse_positive(flag, value)?),
+            "--sampler" => options.sampler = Some(parse_sampler(flag, value)?),
+            "--seed" => options.seed = Some(parse_number(flag, value)?),
+            "-d" | "--depth" => options.recursion_depth = Some(parse_number(flag, value)?),
+            "--integrator" => options.integrator = Some(parse_integrator(flag, value)?),
+            "--samples" => options.samples = Some(parse_positive(flag, value)?),
+            "--progressive" => options.progressive_samples = Some(parse_positive(flag, value)?),
+            "--time-limit" => options.time_limit = Some(parse_positive(flag, value)?),
+            "--noise-threshold" => options.noise_threshold = Some(parse_positive(flag, value)?),
+            "--tone-map" => options.tone_map = Some(parse_tone_map(flag, value)?),
+            "--exposure" => options.exposure = Some(parse_number(flag, value)?),
+            "--aov" => options.aovs = Some(parse_aovs(flag, value)?),
+            "--denoise" => options.denoise = Some(parse_switch(flag, value)?),
+            "--heatmap" => options.heatmap = Some(parse_cost_metric(flag, value)?),
+            "--debug-pixel" => options.debug_pixel = Some(parse_pixel(flag, value)?),
+            "--debug-format" => options.debug_format = parse_debug_format(flag, value)?,
+            "--listen" => options.listen = Some(value.to_string()),
+            "--worker" => options.worker = Some(value.to_string()),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+    Ok(Command::Render(Box::new(options)))
+}
+
+fn parse_denoise_args(args: &[String]) -> Result<Command, String> {
+    let mut image_path: Option<String> = None;
+    let mut flags: Vec<(&str, &str)> = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg.starts_with('-') && arg.len() > 1 {
+            match args.get(i+1) {
+                Some(value) => flags.push((arg, value.as_str())),
+                None => return Err(format!("{} needs a value", arg)),
+            }
+            i += 1;
+        }
+        else if image_path.is_none() {
+            image_path = Some(arg.to_string());
+        }
+        else {
+            return Err(format!("unexpected argument '{}'", arg));
+        }
+        i += 1;
+    }
+
+    // eg. render.png becomes render.denoised.png
+    let image_path = image_path.ok_or_else(|| String::from("no image given to denoise"))?;
+    let path = Path::new(&image_path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("render");
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("png");
+    let mut options = DenoiseOptions {
+        output_path: path.with_file_name(format!("{}.denoised.{}", stem, extension)).to_string_lossy().into_owned(),
+        image_path: image_path.clone(),
+        guides: DenoiseGuidePaths::default(),
+        config: DenoiseConfig::default(),
+    };
+
+    for (flag, value) in flags {
+        match flag {
+            "-o" | "--output" => options.output_path = value.to_string(),
+            "--normal" => options.guides.normal = Some(value.to_string()),
+            "--depth" => options.guides.depth = Some(value.to_string()),
+            "--albedo" => options.guides.albedo = Some(value.to_string()),
+            "--iterations" => options.config.iterations = parse_positive(flag, value)?,
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+    Ok(Command::Denoise(options))
+}
+
+fn has_extension(path: &str, extensions: &[&str]) -> bool {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => extensions.iter().any(|wanted| ext.eq_ignore_ascii_case(wanted)),
+        None => false,
+    }
+}
+
+fn is_hdr_path(path: &str) -> bool {
+    has_extension(path, &["hdr", "pfm", "exr"])
+}
+
+// eg. render.png becomes render.depth.png
+fn pass_path(path: &str, pass: AovPass) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("render");
+    path.with_file_name(format!("{}.{}.png", stem, pass.name())).to_string_lossy().into_owned()
+}
+
+fn save_image(framebuffer: &FrameBuffer, path: &str, aovs: AovConfig, tone_mapping: ToneMapping) -> Result<(), String> {
+    let saved = if aovs.any() && has_extension(path, &["exr"]) {
+        framebuffer.to_exr_image().save(path).map_err(|err| err.to_string())
+    }
+    else if is_hdr_path(path) {
+        framebuffer.to_hdr_image().save(path).map_err(|err| err.to_string())
+    }
+    else if framebuffer.costs.is_some() {
+        // Heatmap colors are already what should be shown, tone mapping would shift them off the scale
+        framebuffer.tone_map(ToneMapping::default()).save(path).map_err(|err| err.to_string())
+    }
+    else {
+        framebuffer.tone_map(tone_mapping).save(path).map_err(|err| err.to_string())
+    };
+    saved.map_err(|err| format!("could not write '{}': {}", path, err))
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut description = SceneDescription::from_path(&options.scene_path)
+        .map_err(|err| format!("could not load '{}': {}", options.scene_path, err))?;
+    options.apply(&mut description);
+    description.render.check().map_err(String::from)?;
+    if let Some(crop) = description.render.crop {
+        if crop.clip(description.image) != Some(crop) {
+            return Err(format!("crop {},{},{},{} doesn't fit in the {}x{} image", crop.x, crop.y, crop.width, crop.height, description.image.width, description.image.height));
+        }
+    }
+    if let Some((x, y)) = options.debug_pixel {
+        if x >= description.image.width || y >= description.image.height {
+            return Err(format!("pixel {},{} isn't in the {}x{} image", x, y, description.image.width, description.image.height));
+        }
+        let tree = trace_pixel_tree(&description.scene, description.image, description.camera, &description.render, x, y);
+        match options.debug_format {
+            DebugFormat::Text => print!("{}", tree.to_text()),
+            DebugFormat::Json => println!("{}", tree.to_json()),
+        }
+        return Ok(());
+    }
+    if options.interactive {
+        return run_interactive(description);
+    }
+    if let Some(ref address) = options.worker {
+        println!("rendering tiles for {}", address);
+        let stats = render_worker(description.scene, address, description.render.num_threads).map_err(|err| err.to_string())?;
+        print!("{}", stats.report());
+        return Ok(());
+    }
+
+    // Find out before rendering rather than after if the checkpoint can't be used
+    if let Some(checkpoint) = open_checkpoint(&description.scene, description.image, description.camera, &description.render).map_err(|err| err.to_string())? {
+        if let Some(old) = checkpoint.replaced() {
+            println!("moved the checkpoint of another render to {}", old);
+        }
+    }
+
+    let aovs = description.render.aovs;
+    let tone_mapping = description.render.tone_mapping;
+    let heatmap = description.render.heatmap;
+    let (framebuffer, stats) = match (description.render.progressive, options.listen.as_ref()) {
+        (_, Some(address)) => {
+            let listener = TcpListener::bind(address).map_err(|err| format!("could not listen on {}: {}", address, err))?;
+            println!("waiting for workers on {}", address);
+            render_distributed(description, listener).map_err(|err| err.to_string())?
+        },
+        (Some(progressive), None) => {
+            // Keep the output up to date so it can be looked at while the render carries on
+            let mut pass_error = None;
+            let mut stats = RenderStats::default();
+            let framebuffer = render_progressive(description.scene, description.image, description.camera, description.render, progressive, |framebuffer, status| {
+                println!("pass {}: {} samples per pixel, noise {:.4}, {:.1}s", status.pass, status.samples, status.noise, status.elapsed.as_secs_f64());
+                stats = status.stats;
+                if !status.finished && pass_error.is_none() {
+                    pass_error = save_image(framebuffer, &options.output_path, aovs, tone_mapping).err();
+                }
+            });
+            if let Some(message) = pass_error {
+                return Err(message);
+            }
+            (framebuffer, stats)
+        },
+        (None, None) => render_framebuffer_with_stats(description.scene, description.image, description.camera, description.render),
+    };
+    print!("{}", stats.report());
+    if let (Some(metric), Some(costs)) = (heatmap, framebuffer.costs.as_ref()) {
+        let scale = heatmap_scale(costs);
+        match metric {
+            CostMetric::Tests => println!("heatmap: red is {} intersection tests or more", scale),
+            CostMetric::Time => println!("heatmap: red is {:.3}ms or more", scale * 1000.0),
+        }
+    }
+    save_image(&framebuffer, &options.output_path, aovs, tone_mapping)?;
+    println!("wrote {}", options.output_path);
+
+    if !has_extension(&options.output_path, &["exr"]) {
+        for pass in aovs.passes() {
+            let path = pass_path(&options.output_path, pass);
+            if let Some(image) = framebuffer.pass_to_rgb_image(pass) {
+                image.save(&path).map_err(|err| format!("could not write '{}': {}", path, err))?;
+                println!("wrote {}", path);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Renders each pixel typed in and prints everything about it, until stdin runs out
+fn run_interactive(description: SceneDescription) -> Result<(), String> {
+    let stdin = io::stdin();
+    println!("Enter a pixel as 'x y':");
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|err| format!("could not read stdin: {}", err))?;
+        let nums: Vec<Option<u32>> = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|num| !num.is_empty()).map(|num| num.parse().ok()).collect();
+        let (x, y) = match nums.as_slice() {
+            [Some(x), Some(y)] if *x < description.image.width && *y < description.image.height => (*x, *y),
+            _ => {
+                println!("Expected a pixel inside the {}x{} image, eg. 10 20", description.image.width, description.image.height);
+                continue;
+            },
+        };
+        let pixel = render_pixel(description.scene.clone(), description.image, description.camera, description.render.clone(), x, y);
+        println!("Pixel ({}, {})", x, y);
+        println!("  color: {} {} {}", pixel.color.red, pixel.color.green, pixel.color.blue);
+        println!("  distance: {}", pixel.distance);
+        if pixel.aov.hit_id.is_some() {
+            let (normal, albedo) = (pixel.aov.normal, pixel.aov.albedo);
+            println!("  normal: {} {} {}", normal.x, normal.y, normal.z);
+            println!("  uv: {} {}", pixel.aov.uv.x, pixel.aov.uv.y);
+            println!("  albedo: {} {} {}", albedo.red, albedo.green, albedo.blue);
+        }
+        let tree = trace_pixel_tree(&description.scene, description.image, description.camera, &description.render, x, y);
+        println!("  rays:");
+        for line in tree.to_text().lines() {
+            println!("    {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn run_denoise(options: &DenoiseOptions) -> Result<(), String> {
+    let image = denoise_image_file(&options.image_path, &options.guides, &options.config).map_err(|err| err.to_string())?;
+    let saved = if is_hdr_path(&options.output_path) {
+        image.save(&options.output_path).map_err(|err| err.to_string())
+    }
+    else {
+        image.to_rgb_image().save(&options.output_path).map_err(|err| err.to_string())
+    };
+    saved.map_err(|err| format!("could not write '{}': {}", options.output_path, err))?;
+    println!("wrote {}", options.output_path);
+    Ok(())
 }
 
 fn main() {
-    mesh_basic();
-}
\ No newline at end of file
+    let args: Vec<String> = env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(Command::Help) => print!("{}", USAGE),
+        Ok(Command::Render(options)) => {
+            if let Err(message) = run(&options) {
+                eprintln!("raytracer: error: {}", message);
+                process::exit(1);
+            }
+        },
+        Ok(Command::Denoise(options)) => {
+            if let Err(message) = run_denoise(&options) {
+                eprintln!("raytracer: error: {}", message);
+                process::exit(1);
+            }
+        },
+        Err(message) => {
+            eprintln!("raytracer: error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        },
+    }
+}
@@ -4,6 +4,9 @@ use std::sync::Arc;
 use geometry::{Ray, SurfaceCoord, Intersectable, Intersect};
 use geometry::matrix::*;
 use primitive::plane::Triangle;
+use scene_file::{Statement, SceneFileError};
+use error::Error;
+use stats::{PrimitiveKind, count_bvh_node_visit, count_mesh_triangle_test};
 
 #[derive(Clone)]
 struct BoundingBox {
@@ -166,6 +169,7 @@ impl BoundingNode {
 
     pub fn check_intersect(&self, mesh: &Mesh, ray: Ray, min: DVec3, max: DVec3) -> Option<Intersect> {
 
+        count_bvh_node_visit();
         let bounding_box = BoundingBox{min, max};
         //println!("BoundingNode check_intersect bounds: min: {}, max: {}", min, max);
         let hit_point = match bounding_box.check_intersect(ray) {
@@ -266,6 +270,7 @@ impl BoundingNode {
                 ));
                 */
                 for prim in prims.iter() {
+                    count_mesh_triangle_test();
                     if let Some(intersect) = mesh.check_triangle(*prim, ray) {
                         if intersect.distance < min_distance {
                             min_distance = intersect.distance;
@@ -347,6 +352,8 @@ pub struct Mesh {
     face_normals: Arc<Vec<DVec3>>,
     face_area: Arc<Vec<f64>>,
     bounds: Arc<BoundingTree>,
+    // Where the mesh was loaded from, if anywhere
+    pub path: Option<String>,
 }
 
 fn f32_to_dvec3(positions: &Vec<f32>) -> Vec<DVec3> {
@@ -380,8 +387,8 @@ fn indices_to_faces(offset: usize, indices: &Vec<u32>) -> Vec<(usize, usize, usi
 }
 
 impl Mesh {
-    pub fn from_path(path: &Path) -> Box<Mesh> {
-        let (models, materials) = tobj::load_obj(path).unwrap();
+    pub fn from_path(path: &Path) -> Result<Box<Mesh>, Error> {
+        let (models, materials) = tobj::load_obj(path).map_err(|cause| Error::model(&path.to_string_lossy(), cause))?;
         let mut faces: Vec<(usize, usize, usize)> = vec!();
         let mut positions: Vec<DVec3> = vec!();
         let mut vertex_normals: Vec<DVec3> = vec!();
@@ -410,7 +417,9 @@ impl Mesh {
         let face_normals = Arc::new(face_normals);
         let face_area = Arc::new(face_area);
         
-        Box::new(Mesh{positions, vertex_normals, tex_coords, faces, face_normals, face_area, bounds})
+        let path = path.to_str().map(String::from);
+
+        Ok(Box::new(Mesh{positions, vertex_normals, tex_coords, faces, face_normals, face_area, bounds, path}))
     }
 
     pub fn check_triangle(&self, face: usize, ray: Ray) -> Option<Intersect> {
@@ -471,4 +480,15 @@ impl Intersectable for Mesh {
         // TODO: bounds and mesh are weirdly coupled, it makes me uncomfortable
         self.bounds.check_intersect(self, ray)
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        match self.path {
+            Some(ref path) => Ok(Statement::of_kind("primitive", "mesh").with_string(path)),
+            None => Err(SceneFileError::unsupported("mesh was not loaded from a file")),
+        }
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Mesh
+    }
 }
\ No newline at end of file
@@ -1,15 +1,23 @@
-use color::Color;
+use color::ColorEncoding;
 use image::{GrayImage, RgbImage};
 use geometry::SurfaceCoord;
 use geometry::matrix::*;
 use std::sync::Arc;
 use euler::{DVec3, dvec3, dvec4};
+use scene_file::{Statement, SceneFileError};
+use asset_manager::open_image;
+use error::Error;
 
 pub trait NormalMappable: NormalMappableClone {
     fn get_normal(&self, surface_coord: SurfaceCoord) -> DVec3;
     fn calculate_normal(&self, surface_coord: SurfaceCoord, old_normal: DVec3, up: DVec3) -> DVec3 {
         (basis(old_normal, up) * dvec4!(self.get_normal(surface_coord), 0)).xyz()
     }
+
+    // Describes the map as eg. `normal_map "path"` or `bump_map "path" depth` for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("normal map has no scene file description"))
+    }
 }
 
 pub trait NormalMappableClone {
@@ -35,6 +43,10 @@ impl Clone for Box<NormalMappable + Send + Sync> {
 pub struct BumpMap {
     bump_map: Arc<GrayImage>,
     pub depth: f64,
+    // Where the image was loaded from, if anywhere
+    pub path: Option<String>,
+    // Heights are data, so they're read linearly unless the image was painted in sRGB
+    pub encoding: ColorEncoding,
 }
 
 impl BumpMap {
@@ -43,20 +55,24 @@ impl BumpMap {
         Box::new(BumpMap {
             bump_map: Arc::new(flip_vertical(&image)),
             depth,
+            path: None,
+            encoding: ColorEncoding::Linear,
         })
     }
 
-    pub fn from_path(path: &str, depth: f64) -> Box<BumpMap> {
+    pub fn from_path(path: &str, depth: f64) -> Result<Box<BumpMap>, Error> {
         use image::imageops::flip_vertical;
-        Box::new(BumpMap {
-            bump_map: Arc::new(flip_vertical(&image::open(path).unwrap().to_luma())),
+        Ok(Box::new(BumpMap {
+            bump_map: Arc::new(flip_vertical(&open_image(path)?.to_luma())),
             depth,
-        })
+            path: Some(String::from(path)),
+            encoding: ColorEncoding::Linear,
+        }))
     }
 
     fn bump_height(&self, u: u32, v: u32) -> f64 {
         // TODO: bounds checking
-        (self.bump_map.get_pixel(u, v).data[0] as f64/ 255.0) * self.depth
+        self.encoding.decode(self.bump_map.get_pixel(u, v).data[0]) * self.depth
     }
 }
 
@@ -94,11 +110,22 @@ impl NormalMappable for BumpMap {
         assert!(normal.length() - 1.0 < 0.00001);
         normal
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        match self.path {
+            Some(ref path) => Ok(Statement::new("bump_map").with_string(path).with_number(self.depth).with_encoding(self.encoding, ColorEncoding::Linear)),
+            None => Err(SceneFileError::unsupported("bump map was not loaded from a file")),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct NormalMap {
-    normal_map: Arc<RgbImage>
+    normal_map: Arc<RgbImage>,
+    // Where the image was loaded from, if anywhere
+    pub path: Option<String>,
+    // Normals are data, so they're read linearly unless the image was saved as sRGB
+    pub encoding: ColorEncoding,
     // TODO: add tiling
 }
 
@@ -107,14 +134,18 @@ impl NormalMap {
         use image::imageops::flip_vertical;
         Box::new(NormalMap {
             normal_map: Arc::new(flip_vertical(&image)),
+            path: None,
+            encoding: ColorEncoding::Linear,
         })
     }
 
-    pub fn from_path(path: &str) -> Box<NormalMap> {
+    pub fn from_path(path: &str) -> Result<Box<NormalMap>, Error> {
         use image::imageops::flip_vertical;
-        Box::new(NormalMap {
-            normal_map: Arc::new(flip_vertical(&image::open(path).unwrap().to_rgb())),
-        })
+        Ok(Box::new(NormalMap {
+            normal_map: Arc::new(flip_vertical(&open_image(path)?.to_rgb())),
+            path: Some(String::from(path)),
+            encoding: ColorEncoding::Linear,
+        }))
     }
 }
 
@@ -123,8 +154,16 @@ impl NormalMappable for NormalMap {
         let (u, v) = surface_coord.get_uv_index(self.normal_map.width(), self.normal_map.height());
 
         let normal = self.normal_map.get_pixel(u, v);
-        let normal = dvec3!(128 - normal.data[0] as i32, 128 - normal.data[1] as i32, normal.data[2]).normalize();
+        let channel = |i: usize| self.encoding.decode(normal.data[i]) * 255.0;
+        let normal = dvec3!(128.0 - channel(0), 128.0 - channel(1), channel(2)).normalize();
 
         normal
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        match self.path {
+            Some(ref path) => Ok(Statement::new("normal_map").with_string(path).with_encoding(self.encoding, ColorEncoding::Linear)),
+            None => Err(SceneFileError::unsupported("normal map was not loaded from a file")),
+        }
+    }
 }
\ No newline at end of file
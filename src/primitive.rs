@@ -1,6 +1,8 @@
 use euler::{dvec3, DVec3, DMat4};
 use geometry::{SurfaceCoord, Intersect, Intersectable, Ray, matrix::*};
 use std::f64::consts::PI;
+use scene_file::{Statement, SceneFileError};
+use stats::PrimitiveKind;
 
 pub mod cube;
 pub mod sphere;
@@ -43,6 +45,14 @@ impl Intersectable for OneWay {
         let intersects = self.primitive.get_all_intersects(ray);
         intersects.into_iter().filter(|intersect| ray.direction.dot(intersect.surface_normal) < 0.0).collect()
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "one_way").with_child(self.primitive.to_statement()?))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::OneWay
+    }
 }
 
 #[derive(Clone)]
@@ -78,4 +88,12 @@ impl Intersectable for Inverted {
         }
         intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "inverted").with_child(self.primitive.to_statement()?))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Inverted
+    }
 }
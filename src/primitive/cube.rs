@@ -85,6 +85,14 @@ impl Intersectable for Cube {
         }
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "cube").with_number(self.length))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Cube
+    }
 }
 
 #[derive(Clone)]
@@ -179,4 +187,12 @@ impl Intersectable for Tetrahedron {
         }
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "tetrahedron").with_number(self.size))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Tetrahedron
+    }
 }
\ No newline at end of file
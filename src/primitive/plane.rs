@@ -40,6 +40,16 @@ impl Plane {
         let u = a - v;
         SurfaceCoord::new((u.length()/self.surface_scale.x).fract(), (v.length()/self.surface_scale.y).fract())
     }
+
+    // Properties of the plane as scene file statements, also used by Polyhedron
+    pub fn property_statements(&self) -> Vec<Statement> {
+        vec!(
+            Statement::new("origin").with_vec3(self.origin),
+            Statement::new("normal").with_vec3(self.normal),
+            Statement::new("tangent").with_vec3(self.tangent),
+            Statement::new("surface_scale").with_vec2(self.surface_scale),
+        )
+    }
 }
 
 impl Intersectable for Plane {
@@ -58,6 +68,14 @@ impl Intersectable for Plane {
             None
         }
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "plane").with_children(self.property_statements()))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Plane
+    }
 }
 
 #[derive(Clone)]
@@ -95,6 +113,18 @@ impl Intersectable for Rectangle {
             Some(Intersect::new(ray, hit_distance, hit_point, surface_normal, dvec3!(0.0, 1.0, 0.0), surface_coord))
         }
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "rectangle").with_number(self.width).with_number(self.height))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Rectangle
+    }
+}
+
+fn vertex_statements(vertices: &[DVec3]) -> Vec<Statement> {
+    vertices.iter().map(|vertex| Statement::new("vertex").with_vec3(*vertex)).collect()
 }
 
 #[derive(Clone)]
@@ -162,6 +192,14 @@ impl Intersectable for Triangle {
 
         None
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "triangle").with_children(vertex_statements(&self.vertices)))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Triangle
+    }
 }
 
 #[derive(Clone)]
@@ -232,4 +270,12 @@ impl Intersectable for Polygon {
 
         None
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "polygon").with_children(vertex_statements(&self.vertices)))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Polygon
+    }
 }
\ No newline at end of file
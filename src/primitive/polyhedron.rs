@@ -134,4 +134,13 @@ impl Intersectable for Polyhedron {
         }
         ret_intersect
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let planes = self.planes.iter().map(|plane| Statement::new("plane").with_children(plane.property_statements())).collect();
+        Ok(Statement::of_kind("primitive", "polyhedron").with_children(planes))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Polyhedron
+    }
 }
\ No newline at end of file
@@ -90,5 +90,13 @@ impl Intersectable for Sphere {
         }
         ret_intersects
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("primitive", "sphere").with_number(self.radius))
+    }
+
+    fn kind(&self) -> PrimitiveKind {
+        PrimitiveKind::Sphere
+    }
 }
 
@@ -24,11 +24,6 @@ impl ProgressInfo {
         self.progress += progress;
     }
 
-    pub fn reset(&mut self, total: u32) {
-        self.progress = 0;
-        self.total = total;
-    }
-
     pub fn get_percentage(&self) -> String {
         format!("{:.*}%", 6, 100.0 * self.progress as f64 / self.total as f64)
     }
@@ -36,8 +31,6 @@ impl ProgressInfo {
 
 pub enum ProgressMessage {
     Progress(u32),
-    StartAA(u32),
-    AAProgress(u32),
     Terminate,    
 }
 
@@ -60,14 +53,6 @@ impl ProgressTracker {
                         info.add_progress(pixels);
                         printr(&info.get_percentage());
                     },
-                    ProgressMessage::StartAA(total) => {
-                        println!("START ANTIALIASING: {} / {} = {}%", total, info.total, (total as f64)/(info.total as f64) * 100.0);
-                        info.reset(total);
-                    },
-                    ProgressMessage::AAProgress(pixels) => {
-                        info.add_progress(pixels);
-                        printr(&info.get_percentage());
-                    },
                     ProgressMessage::Terminate => {
                         break;
                     },
@@ -0,0 +1,271 @@
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use std::f64;
+use color::Color;
+use scene::Scene;
+use render::{RenderConfig, CameraConfig, CameraRays, ImageDimension, render_region, show_costs};
+use framebuffer::FrameBuffer;
+use aov::AovSample;
+use multithread::ThreadPool;
+use tile::region_tiles;
+use filter::{FilmTile, Film};
+use sampler::{set_thread_sampler, start_pixel_sample, start_center_sample, sample_2d};
+use denoise::{denoise_aovs, denoise_framebuffer};
+use stats::{RenderStats, RenderPhase, time_phase, take_thread_stats};
+use heatmap::CostMeter;
+
+// Keeps the noise of nearly black pixels from blowing up when divided by their brightness
+const NOISE_FLOOR: f64 = 0.01;
+
+// Refines the whole image a few samples per pixel at a time, see render_progressive.
+// It stops at whichever limit it reaches first, the sample count always applies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressiveConfig {
+    // Samples per pixel to stop at
+    pub samples: u32,
+    // Samples added to every pixel between one intermediate image and the next
+    pub samples_per_pass: u32,
+    pub time_limit: Option<Duration>,
+    // Stop once every pixel's PixelSamples::noise is below this
+    pub noise_threshold: Option<f64>,
+}
+
+impl ProgressiveConfig {
+    pub const SAMPLES: u32 = 64;
+    pub const SAMPLES_PER_PASS: u32 = 1;
+}
+
+impl Default for ProgressiveConfig {
+    fn default() -> ProgressiveConfig {
+        ProgressiveConfig {
+            samples: ProgressiveConfig::SAMPLES,
+            samples_per_pass: ProgressiveConfig::SAMPLES_PER_PASS,
+            time_limit: None,
+            noise_threshold: None,
+        }
+    }
+}
+
+// How far a progressive render has got, handed to the callback after every pass
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressiveStatus {
+    pub pass: u32,
+    // Samples every pixel has so far
+    pub samples: u32,
+    pub elapsed: Duration,
+    // Noise of the noisiest pixel, infinite until every pixel has two samples
+    pub noise: f64,
+    // This is the last pass, the image handed over is the final one
+    pub finished: bool,
+    // Everything counted over all the passes so far
+    pub stats: RenderStats,
+}
+
+// Running totals for a single pixel, enough to tell its average color and how sure we are of it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelSamples {
+    pub total: Color,
+    // Each channel squared, so noise can tell apart colors that are equally bright
+    pub total_squared: Color,
+    pub count: u32,
+}
+
+impl Default for PixelSamples {
+    fn default() -> PixelSamples {
+        PixelSamples { total: Color::BLACK, total_squared: Color::BLACK, count: 0 }
+    }
+}
+
+impl PixelSamples {
+    pub fn add(&mut self, color: Color) {
+        self.total += color;
+        self.total_squared += color * color;
+        self.count += 1;
+    }
+
+    pub fn merge(&mut self, other: PixelSamples) {
+        self.total += other.total;
+        self.total_squared += other.total_squared;
+        self.count += other.count;
+    }
+
+    pub fn mean(&self) -> Color {
+        if self.count == 0 {
+            return Color::BLACK;
+        }
+        self.total / self.count as f64
+    }
+
+    // Standard error of the noisiest channel relative to the pixel's brightest channel,
+    // so 0.01 means about 1% either way whether the pixel is dark or bright
+    pub fn noise(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let count = self.count as f64;
+        let mean = self.total / count;
+        let error = |total_squared: f64, mean: f64| {
+            let variance = (total_squared / count - mean * mean).max(0.0) * count / (count - 1.0);
+            (variance / count).sqrt()
+        };
+        let largest_error = error(self.total_squared.red, mean.red)
+            .max(error(self.total_squared.green, mean.green))
+            .max(error(self.total_squared.blue, mean.blue));
+        let brightest = mean.red.abs().max(mean.green.abs()).max(mean.blue.abs());
+        largest_error / brightest.max(NOISE_FLOOR)
+    }
+}
+
+// Renders the image in passes, each one adding ProgressiveConfig::samples_per_pass jittered samples
+// to every pixel, and calls on_pass with the image so far after each of them.
+// The first sample goes through the middle of the pixel and is the one used for distances and AOVs.
+pub fn render_progressive<F>(   scene: Scene,
+                                image_dimension: ImageDimension,
+                                camera_config: CameraConfig,
+                                render_config: RenderConfig,
+                                progressive: ProgressiveConfig,
+                                mut on_pass: F) -> FrameBuffer
+    where F: FnMut(&FrameBuffer, &ProgressiveStatus)
+{
+    let start = Instant::now();
+    let width = image_dimension.width;
+    let height = image_dimension.height;
+    let camera = CameraRays::new(camera_config, image_dimension);
+    let target_samples = progressive.samples.max(1);
+    let samples_per_pass = progressive.samples_per_pass.max(1);
+
+    let scene = Arc::new(scene);
+    let thread_pool = ThreadPool::new(render_config.num_threads);
+    let region = render_region(&render_config, image_dimension);
+    let tiles = region_tiles(region, render_config.tile_size, render_config.tile_order);
+    let measured = render_config.crop.unwrap_or(region);
+    let seed = render_config.seed as u64;
+    let aovs = match render_config.denoise {
+        Some(_) if render_config.heatmap.is_none() => denoise_aovs(render_config.aovs),
+        _ => render_config.aovs,
+    };
+
+    // The pixel filter gives the image, the samples in each pixel give its noise
+    let mut pixels = vec![PixelSamples::default(); image_dimension.area() as usize];
+    let mut film = Film::new(image_dimension);
+    let mut distances = vec![f64::INFINITY; image_dimension.area() as usize];
+    let mut aov_samples = Vec::new();
+    let mut costs = vec![0.0; image_dimension.area() as usize];
+    let mut samples = 0;
+    let mut pass = 0;
+    let mut stats = RenderStats::default();
+    loop {
+        pass += 1;
+        let first_pass = samples == 0;
+        let pass_samples = samples_per_pass.min(target_samples - samples);
+
+        let (sender, receiver) = mpsc::channel::<(usize, Vec<PixelSamples>, Vec<(f64, AovSample)>, FilmTile, Vec<f64>, RenderStats)>();
+        for (tile_index, tile) in tiles.iter().cloned().enumerate() {
+            let thread_sender = sender.clone();
+            let thread_scene = Arc::clone(&scene);
+            let render_config = render_config.clone();
+            let first_sample = samples;
+
+            thread_pool.execute(move || {
+                let mut pixel_chunk = Vec::with_capacity(tile.area() as usize);
+                let mut hit_chunk = Vec::new();
+                let mut cost_chunk = Vec::new();
+                let mut film = FilmTile::new(tile, render_config.filter, image_dimension);
+                set_thread_sampler(render_config.sampler.create(seed, target_samples));
+                take_thread_stats();
+                for (x, y) in tile.pixels() {
+                    let mut pixel = PixelSamples::default();
+                    let meter = render_config.heatmap.map(CostMeter::start);
+                    for i in 0..pass_samples {
+                        if first_pass && i == 0 {
+                            start_center_sample(x, y);
+                            let ray = camera.ray(x as f64 + 0.5, y as f64 + 0.5, render_config.recursion_depth);
+                            let (distance, color) = time_phase(RenderPhase::Trace, || render_config.integrator.trace(&thread_scene, ray));
+                            pixel.add(color);
+                            film.add_sample(render_config.filter, x as f64 + 0.5, y as f64 + 0.5, color);
+                            let aov = if aovs.any() {
+                                time_phase(RenderPhase::Aov, || thread_scene.get_aov_sample(ray, camera.view_direction))
+                            }
+                            else {
+                                AovSample::background()
+                            };
+                            hit_chunk.push((distance, aov));
+                        }
+                        else {
+                            // Sample numbers carry on from the last pass, so the sampler keeps spreading them out
+                            start_pixel_sample(x, y, first_sample + i);
+                            let (u, v) = sample_2d();
+                            let (sample_x, sample_y) = (x as f64 + u, y as f64 + v);
+                            let sample_ray = camera.ray(sample_x, sample_y, render_config.recursion_depth);
+                            let color = time_phase(RenderPhase::AntiAlias, || render_config.integrator.trace(&thread_scene, sample_ray)).1;
+                            pixel.add(color);
+                            film.add_sample(render_config.filter, sample_x, sample_y, color);
+                        }
+                    }
+                    pixel_chunk.push(pixel);
+                    if let Some(meter) = meter {
+                        cost_chunk.push(meter.finish());
+                    }
+                }
+                thread_sender.send((tile_index, pixel_chunk, hit_chunk, film, cost_chunk, take_thread_stats())).unwrap();
+            });
+        }
+
+        // Collect the pass from the worker threads
+        if first_pass {
+            aov_samples = vec![AovSample::background(); image_dimension.area() as usize];
+        }
+        for _ in 0..tiles.len() {
+            let (tile_index, pixel_chunk, hit_chunk, film_tile, cost_chunk, tile_stats) = receiver.recv().unwrap();
+            stats.add(&tile_stats);
+            film.add_tile(&film_tile);
+            let indices: Vec<usize> = tiles[tile_index].pixels().into_iter().map(|(x, y)| (y*width + x) as usize).collect();
+            for (&i, pixel) in indices.iter().zip(pixel_chunk) {
+                pixels[i].merge(pixel);
+            }
+            for (&i, (distance, aov)) in indices.iter().zip(hit_chunk) {
+                distances[i] = distance;
+                aov_samples[i] = aov;
+            }
+            for (&i, cost) in indices.iter().zip(cost_chunk) {
+                costs[i] += cost;
+            }
+        }
+        samples += pass_samples;
+
+        let mut framebuffer = FrameBuffer::new(width, height);
+        framebuffer.colors = film.resolve();
+        framebuffer.distances = distances.clone();
+        if aovs.any() {
+            framebuffer.set_aovs(aovs, &aov_samples);
+        }
+        if render_config.heatmap.is_some() {
+            framebuffer.costs = Some(costs.clone());
+        }
+
+        if let Some(crop) = render_config.crop {
+            framebuffer = framebuffer.crop(crop);
+        }
+        show_costs(&mut framebuffer);
+
+        let noise = measured.pixels().into_iter().map(|(x, y)| pixels[(y*width + x) as usize].noise()).fold(0.0, f64::max);
+        let elapsed = start.elapsed();
+        let finished = samples >= target_samples ||
+                       progressive.time_limit.is_some_and(|limit| elapsed >= limit) ||
+                       progressive.noise_threshold.is_some_and(|threshold| noise <= threshold);
+
+        // Only the final image is denoised, the ones before it are just to watch it come along
+        if let Some(denoise) = render_config.denoise.filter(|_| finished && render_config.heatmap.is_none()) {
+            let denoise_start = Instant::now();
+            framebuffer = denoise_framebuffer(&framebuffer, &denoise);
+            stats.denoise_time = denoise_start.elapsed();
+        }
+        stats.elapsed = start.elapsed();
+        let status = ProgressiveStatus { pass, samples, elapsed, noise, finished, stats };
+        framebuffer.keep_passes(render_config.aovs);
+        on_pass(&framebuffer, &status);
+        if finished {
+            return framebuffer;
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::cell::RefCell;
+use std::f64;
+use std::fmt::Write;
+use euler::DVec3;
+use snowflake::ProcessUniqueId;
+use color::Color;
+use geometry::{Ray, RayKind, NodeIntersect};
+use scene::Scene;
+use render::{RenderConfig, CameraConfig, CameraRays, ImageDimension};
+use sampler::{set_thread_sampler, start_center_sample};
+
+// How a surface split the light between reflecting it and letting it through, eg. glass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fresnel {
+    pub reflected: f64,
+    pub transmitted: f64,
+}
+
+// One ray traced for a pixel, with every ray it spawned where it hit something
+#[derive(Debug, Clone)]
+pub struct RayNode {
+    pub kind: RayKind,
+    pub origin: DVec3,
+    pub direction: DVec3,
+    // Bounces the ray had left
+    pub depth: u32,
+    // Node that was hit, for shadow rays it's whatever was in the way
+    pub hit_id: Option<ProcessUniqueId>,
+    pub hit_point: Option<DVec3>,
+    pub normal: Option<DVec3>,
+    // Infinite if it didn't hit anything
+    pub distance: f64,
+    // Set by shaders that split the ray, see TranslucentShader
+    pub fresnel: Option<Fresnel>,
+    // What the ray brought back. Shadow rays are white if they got through and black if they didn't.
+    pub color: Color,
+    pub children: Vec<RayNode>,
+}
+
+impl RayNode {
+    pub fn new(kind: RayKind, ray: Ray) -> RayNode {
+        RayNode {
+            kind,
+            origin: ray.origin,
+            direction: ray.direction,
+            depth: ray.get_depth(),
+            hit_id: None,
+            hit_point: None,
+            normal: None,
+            distance: f64::INFINITY,
+            fresnel: None,
+            color: Color::BLACK,
+            children: Vec::new(),
+        }
+    }
+
+    fn set_hit(&mut self, node_intersect: &NodeIntersect) {
+        self.hit_id = Some(node_intersect.hit_id);
+        self.hit_point = Some(node_intersect.intersect.hit_point);
+        self.normal = Some(node_intersect.intersect.surface_normal);
+        self.distance = node_intersect.get_distance();
+    }
+
+    // This ray and everything under it
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(RayNode::count).sum::<usize>()
+    }
+
+    // Every ray in the tree, parents before their children
+    pub fn iter(&self) -> Vec<&RayNode> {
+        let mut nodes = vec![self];
+        for child in self.children.iter() {
+            nodes.extend(child.iter());
+        }
+        nodes
+    }
+
+    // One ray per line, children indented under their parent
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.write_text(&mut text, 0);
+        text
+    }
+
+    fn write_text(&self, text: &mut String, indent: usize) {
+        let vec3 = |v: DVec3| format!("({:.3}, {:.3}, {:.3})", v.x, v.y, v.z);
+        write!(text, "{}{} {} -> {} depth {}", "  ".repeat(indent), self.kind.name(), vec3(self.origin), vec3(self.direction), self.depth).unwrap();
+        match (self.hit_id, self.hit_point, self.normal) {
+            (Some(id), Some(hit_point), Some(normal)) => {
+                write!(text, ", hit {} at {} distance {:.3} normal {}", id, vec3(hit_point), self.distance, vec3(normal)).unwrap();
+            },
+            _ => text.push_str(", missed"),
+        }
+        if let Some(fresnel) = self.fresnel {
+            write!(text, ", fresnel reflected {:.3} transmitted {:.3}", fresnel.reflected, fresnel.transmitted).unwrap();
+        }
+        writeln!(text, ", color ({:.4}, {:.4}, {:.4})", self.color.red, self.color.green, self.color.blue).unwrap();
+        for child in self.children.iter() {
+            child.write_text(text, indent + 1);
+        }
+    }
+
+    // The tree as a JSON object, children in a "children" array. Misses have null for the hit
+    // and an infinite distance is written as null, since JSON has no infinity.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+
+    fn write_json(&self, json: &mut String) {
+        let number = |value: f64| if value.is_finite() { format!("{}", value) } else { String::from("null") };
+        let vec3 = |v: DVec3| format!("[{}, {}, {}]", number(v.x), number(v.y), number(v.z));
+        write!(json, "{{\"kind\": \"{}\", \"origin\": {}, \"direction\": {}, \"depth\": {}, ", self.kind.name(), vec3(self.origin), vec3(self.direction), self.depth).unwrap();
+        match (self.hit_id, self.hit_point, self.normal) {
+            (Some(id), Some(hit_point), Some(normal)) => {
+                write!(json, "\"hit_id\": \"{}\", \"hit_point\": {}, \"normal\": {}, ", id, vec3(hit_point), vec3(normal)).unwrap();
+            },
+            _ => json.push_str("\"hit_id\": null, \"hit_point\": null, \"normal\": null, "),
+        }
+        write!(json, "\"distance\": {}, ", number(self.distance)).unwrap();
+        match self.fresnel {
+            Some(fresnel) => write!(json, "\"fresnel\": {{\"reflected\": {}, \"transmitted\": {}}}, ", number(fresnel.reflected), number(fresnel.transmitted)).unwrap(),
+            None => json.push_str("\"fresnel\": null, "),
+        }
+        write!(json, "\"color\": [{}, {}, {}], \"children\": [", number(self.color.red), number(self.color.green), number(self.color.blue)).unwrap();
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                json.push_str(", ");
+            }
+            child.write_json(json);
+        }
+        json.push_str("]}");
+    }
+}
+
+// Rays that have started but not finished yet, innermost last
+struct Recorder {
+    stack: Vec<RayNode>,
+    root: Option<RayNode>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+fn with_recorder<F: FnOnce(&mut Recorder)>(record: F) {
+    RECORDER.with(|recorder| {
+        if let Some(ref mut recorder) = *recorder.borrow_mut() {
+            record(recorder);
+        }
+    });
+}
+
+fn attach(recorder: &mut Recorder, node: RayNode) {
+    match recorder.stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => recorder.root = Some(node),
+    }
+}
+
+// Calls trace and gives back every ray Scene traced for it on this thread, see Scene::cast_ray.
+// None if it didn't trace any through the scene.
+pub fn record_ray_tree<T, F: FnOnce() -> T>(trace: F) -> (T, Option<RayNode>) {
+    RECORDER.with(|recorder| *recorder.borrow_mut() = Some(Recorder { stack: Vec::new(), root: None }));
+    let result = trace();
+    let recorder = RECORDER.with(|recorder| recorder.borrow_mut().take());
+    (result, recorder.and_then(|recorder| recorder.root))
+}
+
+// A ray is about to be traced, everything traced until it finishes is one of its children
+pub fn start_ray(ray: Ray) {
+    with_recorder(|recorder| recorder.stack.push(RayNode::new(ray.kind(), ray)));
+}
+
+pub fn record_hit(node_intersect: &NodeIntersect) {
+    with_recorder(|recorder| {
+        if let Some(node) = recorder.stack.last_mut() {
+            node.set_hit(node_intersect);
+        }
+    });
+}
+
+// The surface the current ray hit split it between reflected and transmitted rays
+pub fn record_fresnel(fresnel: Fresnel) {
+    with_recorder(|recorder| {
+        if let Some(node) = recorder.stack.last_mut() {
+            node.fresnel = Some(fresnel);
+        }
+    });
+}
+
+pub fn finish_ray(color: Color) {
+    with_recorder(|recorder| {
+        if let Some(mut node) = recorder.stack.pop() {
+            node.color = color;
+            attach(recorder, node);
+        }
+    });
+}
+
+// Shadow rays don't spawn anything, so they're recorded in one go
+pub fn record_shadow_ray(ray: Ray, blocker: Option<&NodeIntersect>) {
+    with_recorder(|recorder| {
+        let mut node = RayNode::new(RayKind::Shadow, ray);
+        match blocker {
+            Some(blocker) => node.set_hit(blocker),
+            None => node.color = Color::WHITE,
+        }
+        attach(recorder, node);
+    });
+}
+
+// Traces the first sample of a pixel, the ray through its middle, the same way the render does,
+// and gives back every ray that went into its color. Integrators that don't trace through
+// Scene::cast_ray, like the debug views, only get the camera ray.
+pub fn trace_pixel_tree(scene: &Scene,
+                        image_dimension: ImageDimension,
+                        camera_config: CameraConfig,
+                        render_config: &RenderConfig,
+                        x: u32,
+                        y: u32) -> RayNode {
+
+    let camera = CameraRays::new(camera_config, image_dimension);
+    let ray = camera.ray(x as f64 + 0.5, y as f64 + 0.5, render_config.recursion_depth);
+    set_thread_sampler(render_config.sampler.create(render_config.seed as u64, render_config.samples_per_pixel()));
+    start_center_sample(x, y);
+    let ((distance, color), tree) = record_ray_tree(|| render_config.integrator.trace(scene, ray));
+    tree.unwrap_or_else(|| {
+        let mut node = RayNode::new(RayKind::Camera, ray);
+        if let Some(node_intersect) = scene.root.trace(ray) {
+            node.set_hit(&node_intersect);
+        }
+        node.distance = distance;
+        node.color = color;
+        node
+    })
+}
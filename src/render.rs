@@ -1,52 +1,142 @@
+use std::io;
 use std::sync::{mpsc};
-use image::{RgbImage, ImageBuffer};
+use std::time::Instant;
+use image::RgbImage;
 use euler::*;
-use color::*;
 use scene::*;
+use color::Color;
 use geometry::*;
 use light::*;
 use multithread::*;
 use progress_tracker::*;
-use rand::prelude::*;
-use std::f64::consts::PI;
-
-// How different must neighbouring pixels be to be anti-aliased
-const AA_THRESHOLD: f64 = 0.08;
-
-// How many more rays to use for anti_aliasing
-const AA_RAYS: u32 = 5;
+use hdr::HdrImage;
+use framebuffer::FrameBuffer;
+use aov::{AovConfig, AovSample};
+use tone_map::ToneMapping;
+use integrator::{Integrator, WhittedIntegrator};
+use progressive::{ProgressiveConfig, PixelSamples, render_progressive};
+use tile::{Tile, TileOrder, region_tiles};
+use checkpoint::{Checkpoint, RenderedTile};
+use filter::{PixelFilter, FilmTile, Film};
+use sampler::{SamplerKind, set_thread_sampler, start_pixel_sample, start_center_sample, sample_2d};
+use denoise::{DenoiseConfig, denoise_aovs, denoise_framebuffer};
+use stats::{RenderStats, RenderPhase, time_phase, take_thread_stats};
+use heatmap::{CostMetric, CostMeter, heatmap_colors};
+use scene_file::{SceneDescription, SceneFileError, fingerprint};
+use error::Error;
+
+// How sure anti-aliasing has to be of a pixel's color before it stops adding rays, see PixelSamples::noise
+const AA_NOISE_THRESHOLD: f64 = 0.02;
+
+// Rays every anti-aliased pixel gets. Two is the fewest that can disagree, which keeps flat areas
+// about as cheap as one ray a pixel while edges still get picked up and refined.
+const AA_MIN_SAMPLES: u32 = 2;
+
+// Most rays an anti-aliased pixel gets, however noisy it still is. Hard edges never settle,
+// so this is about what every edge pixel costs.
+const AA_MAX_SAMPLES: u32 = 8;
 
 // How many worker threads available for jobs
 const NUM_THREADS: usize = 8;
 
-// How many chunks to split the workload into
-const WORKLOAD_SPLIT: u32 = 500;
+// Width and height of the squares the image is rendered in
+const TILE_SIZE: u32 = 32;
 
 // How times a ray can reflect/refract/etc through the scene
 const RECURSION_DEPTH: u32 = 20;
 
-#[derive(Clone, Copy)]
+// How many rays to average per pixel for integrators that pick random directions
+const SAMPLES: u32 = 16;
+
+#[derive(Clone)]
 pub struct RenderConfig {
     pub num_threads: usize,
-    pub workload_split: u32,
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    // Finished tiles are saved here while rendering, and picked up again if the render is restarted.
+    // Rendering panics if it can't be, check with open_checkpoint first.
+    pub checkpoint: Option<String>,
+    // Adds rays to each pixel until it's sure of the pixel's color, so edges and soft shadows
+    // get more of them than flat areas. Random integrators use samples instead.
     pub anti_alias: bool,
-    pub aa_threshold: f64,
-    pub aa_rays: u32,
+    pub aa_noise_threshold: f64,
+    pub aa_min_samples: u32,
+    pub aa_max_samples: u32,
+    // How samples are weighted into the pixels around them
+    pub filter: PixelFilter,
+    // Renders with the same seed and settings come out exactly the same, however many threads they use
+    pub seed: u32,
+    // Where the random numbers for placing samples in pixels, lights and bounces come from
+    pub sampler: SamplerKind,
     pub recursion_depth: u32,
-    pub interactive: bool,
+    // Only render this part of the image, the framebuffer that comes back is the size of the crop
+    pub crop: Option<Tile>,
+    pub integrator: Box<dyn Integrator + Send + Sync>,
+    // With a random integrator, each pixel averages this many rays spread over it,
+    // which also takes care of anti-aliasing
+    pub samples: u32,
+    pub aovs: AovConfig,
+    pub tone_mapping: ToneMapping,
+    // Refine the image in passes until a limit is hit instead of one pass plus anti-aliasing
+    pub progressive: Option<ProgressiveConfig>,
+    // Smooths out what noise is left once the render is done
+    pub denoise: Option<DenoiseConfig>,
+    // Colors each pixel by how much work it took instead of what it sees, to find what makes
+    // a scene slow. The costs themselves end up in FrameBuffer::costs.
+    pub heatmap: Option<CostMetric>,
 }
 
 impl RenderConfig {
     pub fn default() -> RenderConfig {
         RenderConfig {
             num_threads: NUM_THREADS,
-            workload_split: WORKLOAD_SPLIT,
+            tile_size: TILE_SIZE,
+            tile_order: TileOrder::default(),
+            checkpoint: None,
             anti_alias: true,
-            aa_threshold: AA_THRESHOLD,
-            aa_rays: AA_RAYS,
+            aa_noise_threshold: AA_NOISE_THRESHOLD,
+            aa_min_samples: AA_MIN_SAMPLES,
+            aa_max_samples: AA_MAX_SAMPLES,
+            filter: PixelFilter::default(),
+            seed: 0,
+            sampler: SamplerKind::default(),
             recursion_depth: RECURSION_DEPTH,
-            interactive: false,
+            crop: None,
+            integrator: WhittedIntegrator::new(),
+            samples: SAMPLES,
+            aovs: AovConfig::none(),
+            tone_mapping: ToneMapping::default(),
+            progressive: None,
+            denoise: None,
+            heatmap: None,
+        }
+    }
+
+    // Samples the sampler has to spread out over each pixel, adaptive anti-aliasing can go over it
+    pub fn samples_per_pixel(&self) -> u32 {
+        if self.integrator.is_random() {
+            self.samples
+        }
+        else if self.anti_alias {
+            self.aa_min_samples
+        }
+        else {
+            1
+        }
+    }
+
+    // Settings that can't be rendered with, whether they came from a scene file or the command line
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.num_threads == 0 || self.tile_size == 0 || self.samples == 0 || self.aa_min_samples == 0 {
+            return Err("threads, tile_size, samples and aa_min_samples must be at least 1");
         }
+        if self.aa_max_samples < self.aa_min_samples {
+            return Err("aa_max_samples can't be less than aa_min_samples");
+        }
+        if self.checkpoint.is_some() && self.progressive.is_some() {
+            return Err("progressive renders can't be checkpointed");
+        }
+        Ok(())
     }
 }
 
@@ -85,6 +175,58 @@ impl CameraConfig {
     }
 }
 
+// Where the camera's rays go through the image, worked out once from a CameraConfig
+#[derive(Clone, Copy)]
+pub struct CameraRays {
+    pub origin: DVec3,
+    pub view_direction: DVec3,
+    camera_to_world_mat: DMat4,
+    width: u32,
+    height: u32,
+    x_factor: f64,
+    fov_factor: f64,
+}
+
+impl CameraRays {
+    pub fn new(camera_config: CameraConfig, image_dimension: ImageDimension) -> CameraRays {
+        let width = image_dimension.width;
+        let height = image_dimension.height;
+
+        // Calculate some values for camera configuration
+        let aspect_ratio = width as f64 / height as f64;
+        let fov_factor = (camera_config.fov_y.to_radians()/2.0).tan();
+        let x_factor = aspect_ratio * fov_factor;
+
+        // Set up the Basis of the camera's view
+        let view_direction = (camera_config.target - camera_config.origin).normalize();
+        let side = view_direction.cross(camera_config.up).normalize();
+        let up = side.cross(view_direction).normalize();
+
+        // Let's us transform points/rays from camera's perspective
+        // into the scene's coordinates
+        let camera_to_world_mat = dmat4!(
+                side.x, side.y, side.z, 0.0,
+                up.x, up.y, up.z, 0.0,
+                view_direction.x, view_direction.y, view_direction.z, 0,
+                camera_config.origin.x, camera_config.origin.y, camera_config.origin.z, 1,
+        );
+
+        CameraRays { origin: camera_config.origin, view_direction, camera_to_world_mat, width, height, x_factor, fov_factor }
+    }
+
+    // A pixel's location in the scene, x and y are positions in the image
+    pub fn pixel_location(&self, x: f64, y: f64) -> DVec3 {
+        (self.camera_to_world_mat * dvec4!((2.0 * ((x + 0.5)/self.width as f64) - 1.0) * self.x_factor,
+                                           (1.0 - 2.0 * (y + 0.5)/self.height as f64) * self.fov_factor,
+                                           1,
+                                           1)).xyz()
+    }
+
+    pub fn ray(&self, x: f64, y: f64, depth: u32) -> Ray {
+        Ray::from_destination(self.origin, self.pixel_location(x, y), depth)
+    }
+}
+
 // Takes a scene and some config options and spits out an image
 pub fn render(scene: Scene,
               image_dimension: ImageDimension,
@@ -101,270 +243,342 @@ pub fn render_with_config(  scene: Scene,
                             camera_config: CameraConfig,
                             render_config: RenderConfig) -> RgbImage {
 
-    let width = image_dimension.width;
-    let height = image_dimension.height;
+    let tone_mapping = render_config.tone_mapping;
+    render_hdr_with_config(scene, image_dimension, camera_config, render_config).tone_map(tone_mapping)
+}
 
-    // Calculate some values for camera configuration
-    let aspect_ratio = width as f64 / height as f64;
-    let fov_factor = (camera_config.fov_y.to_radians()/2.0).tan();
-    let x_factor = aspect_ratio * fov_factor;
-
-    // Set up the Basis of the camera's view
-    let view_direction = (camera_config.target - camera_config.origin).normalize();
-    let side = view_direction.cross(camera_config.up).normalize();
-    let up = side.cross(view_direction).normalize();
-
-    // Let's us transform points/rays from camera's perspective
-    // into the scene's coordinates
-    let camera_to_world_mat = dmat4!(
-            side.x, side.y, side.z, 0.0,
-            up.x, up.y, up.z, 0.0,
-            view_direction.x, view_direction.y, view_direction.z, 0,
-            camera_config.origin.x, camera_config.origin.y, camera_config.origin.z, 1,
-    );
-
-    // Handy closure to calculate a pixel's location in the scene
-    let calculate_pixel_location = move |x: f64, y: f64| -> DVec3 {
-        (camera_to_world_mat * dvec4!((2.0 * ((x as f64 + 0.5)/width as f64) - 1.0) * x_factor, 
-                                     (1.0 - 2.0 * (y as f64 + 0.5)/height as f64) * fov_factor, 
-                                      1, 
-                                      1)).xyz()
-    };
+// Same as render_with_config, but the colors are left linear and unclamped, without tone mapping
+pub fn render_hdr_with_config(  scene: Scene,
+                                image_dimension: ImageDimension,
+                                camera_config: CameraConfig,
+                                render_config: RenderConfig) -> HdrImage {
 
-    // TODO: actually implement this
-    if render_config.interactive {
-        loop {
-            let (x, y) = get_input();
-            let pixel_location = calculate_pixel_location(x as f64 + 0.5, y as f64 + 0.5);
-            let prime_ray = Ray::from_destination(camera_config.origin, pixel_location, render_config.recursion_depth);
-            let (distance, color) = scene.cast_ray_get_distance(prime_ray);
-            println!("Distance: {}", distance);
-            println!("Color: {:?}", color);
-        }
+    render_framebuffer_with_config(scene, image_dimension, camera_config, render_config).to_hdr_image()
+}
+
+// Gives back everything the render worked out for each pixel, for post-processing
+// or comparing renders without going through an image
+pub fn render_framebuffer_with_config(  scene: Scene,
+                                        image_dimension: ImageDimension,
+                                        camera_config: CameraConfig,
+                                        render_config: RenderConfig) -> FrameBuffer {
+
+    render_framebuffer_with_stats(scene, image_dimension, camera_config, render_config).0
+}
+
+// Same as render_framebuffer_with_config, along with how many rays it took and where the time went
+pub fn render_framebuffer_with_stats(   scene: Scene,
+                                        image_dimension: ImageDimension,
+                                        camera_config: CameraConfig,
+                                        render_config: RenderConfig) -> (FrameBuffer, RenderStats) {
+
+    let start = Instant::now();
+
+    if let Some(progressive) = render_config.progressive {
+        let mut stats = RenderStats::default();
+        let framebuffer = render_progressive(scene, image_dimension, camera_config, render_config, progressive, |_, status| stats = status.stats);
+        return (framebuffer, stats);
+    }
+
+    // The denoiser needs normals and albedo, which are left out again if they weren't asked for.
+    // Heatmaps replace the colors anyway, so there's nothing to denoise.
+    if let Some(denoise) = render_config.denoise.filter(|_| render_config.heatmap.is_none()) {
+        let aovs = render_config.aovs;
+        let mut render_config = render_config;
+        render_config.denoise = None;
+        render_config.aovs = denoise_aovs(aovs);
+        let (noisy, mut stats) = render_framebuffer_with_stats(scene, image_dimension, camera_config, render_config);
+        let denoise_start = Instant::now();
+        let mut framebuffer = denoise_framebuffer(&noisy, &denoise);
+        framebuffer.keep_passes(aovs);
+        stats.denoise_time = denoise_start.elapsed();
+        stats.elapsed = start.elapsed();
+        return (framebuffer, stats);
     }
 
+    let checkpoint = open_checkpoint(&scene, image_dimension, camera_config, &render_config).unwrap_or_else(|err| panic!("{}", err));
+
     // Initialization of Thread Resources
     let thread_pool = ThreadPool::new(render_config.num_threads);
-    let (sender, receiver) = mpsc::channel::<(u32, Vec<(f64, Color)>)>();
-    let progress_tracker = ProgressTracker::new(image_dimension);
+    let (sender, receiver) = mpsc::channel::<(usize, TileResult)>();
 
-    // Divide work into horizontal chunks of the image
-    let lines_per_chunk = divide_round_up(height, render_config.workload_split);
-    for chunk in 0..render_config.workload_split as u32 {
+    // Divide work into square tiles of the image
+    let region = render_region(&render_config, image_dimension);
+    let tiles = region_tiles(region, render_config.tile_size, render_config.tile_order);
+    let progress_tracker = ProgressTracker::new(ImageDimension::new(region.width, region.height));
+    for (tile_index, tile) in tiles.iter().cloned().enumerate() {
 
         // cloned so thread owns it's own copy of these
         let thread_sender = sender.clone();
         let thread_progress_sender = progress_tracker.get_sender();
         let thread_scene = scene.clone();
+        let render_config = render_config.clone();
+        let done = checkpoint.as_ref().and_then(|checkpoint| checkpoint.find(tile)).cloned();
 
         // Each thread will run in its own little closure
         thread_pool.execute(move || {
-            let mut image_chunk: Vec<(f64, Color)> = Vec::with_capacity((width * lines_per_chunk) as usize);
-            for y in chunk*lines_per_chunk..height.min((chunk+1)*lines_per_chunk) {
-                for x in 0..width {
-
-                    // The actual work of ray tracing
-                    let pixel_location = calculate_pixel_location(x as f64 + 0.5, y as f64 + 0.5);
-                    let prime_ray = Ray::from_destination(camera_config.origin, pixel_location, render_config.recursion_depth);
-                    let (distance, color) = thread_scene.cast_ray_get_distance(prime_ray);
-                    image_chunk.push((distance, color));
-
-                    // Send progress report
-                    thread_progress_sender.send(ProgressMessage::Progress(1)).unwrap();
-                }
+            let result = render_tile(&thread_scene, image_dimension, camera_config, &render_config, tile, done);
 
-                // Only send progress after every line to not overload progress track
-                //thread_progress_sender.send(ProgressMessage::Progress(width)).unwrap();
-            }
-            thread_sender.send((chunk, image_chunk)).unwrap();
+            // Send progress report
+            thread_progress_sender.send(ProgressMessage::Progress(tile.area())).unwrap();
+            thread_sender.send((tile_index, result)).unwrap();
         });
     }
 
-    // Collect completed work from worker threads
-    let mut collected_chunks: Vec<Vec<(f64, Color)>> = vec![Vec::new(); render_config.workload_split as usize];
-    for _ in 0..render_config.workload_split {
-        let (i, line_colors) = receiver.recv().unwrap();
-        collected_chunks[i as usize] = line_colors;
-    }
-
-    // put into a single vec
-    let mut color_vec: Vec<(f64, Color)> = Vec::with_capacity((width * height) as usize);
-    for chunk in collected_chunks.iter_mut() {
-        color_vec.append(chunk);
-    }
+    let (framebuffer, mut stats) = collect_tiles(&receiver, &tiles, image_dimension, &render_config, checkpoint);
+    stats.elapsed = start.elapsed();
+    (framebuffer, stats)
+}
 
-    // Useful closure for indexing our Vector<Color>
-    let color_index = |x: u32, y: u32| -> usize {
-        (y*width + x) as usize
-    };
+// What rendering one tile worked out, sent back from whichever thread or worker rendered it
+#[derive(Debug, Clone)]
+pub struct TileResult {
+    pub rendered: RenderedTile,
+    // One for each pixel of the tile, if the render has AOVs
+    pub aovs: Vec<AovSample>,
+    // One for each pixel of the tile, for heatmap renders
+    pub costs: Vec<f64>,
+    pub stats: RenderStats,
+}
 
-    // Do Anti-Aliasing
-    if render_config.anti_alias {
-        let eight_directions: [(i64, i64); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1,-1), (1,0), (1,1)];
-        let mut aa_corrections: Vec<(u32, u32)> = Vec::new();
-        for y in 1..height-1 {
-            for x in 1..width-1 {
-
-                let color_i = color_index(x, y) as i64;
-                let color = color_vec[color_i as usize];
-
-                // Check all eight neighbours of a pixel to see if it needs anti-aliasing
-                for direc in eight_directions.iter() {
-                    if color.1.diff(color_vec[(color_i + direc.0*width as i64 + direc.1) as usize].1) > render_config.aa_threshold  &&
-                       color.0 < std::f64::INFINITY {
-                        aa_corrections.push((x, y));
-                        break;
-                    }
-                }
-            }
+// Renders the pixels of one tile. done is the tile from a checkpoint, which only needs its AOVs worked out.
+pub fn render_tile( scene: &Scene,
+                    image_dimension: ImageDimension,
+                    camera_config: CameraConfig,
+                    render_config: &RenderConfig,
+                    tile: Tile,
+                    done: Option<RenderedTile>) -> TileResult {
+
+    let camera = CameraRays::new(camera_config, image_dimension);
+    let filter = render_config.filter;
+    let mut distances: Vec<f64> = Vec::with_capacity(tile.area() as usize);
+    let mut film = FilmTile::new(tile, filter, image_dimension);
+    let mut aov_chunk: Vec<AovSample> = Vec::new();
+    let mut cost_chunk: Vec<f64> = Vec::new();
+    set_thread_sampler(render_config.sampler.create(render_config.seed as u64, render_config.samples_per_pixel()));
+    take_thread_stats();
+    for (x, y) in tile.pixels() {
+
+        // The actual work of ray tracing
+        let prime_ray = camera.ray(x as f64 + 0.5, y as f64 + 0.5, render_config.recursion_depth);
+        let integrator = &render_config.integrator;
+        let meter = render_config.heatmap.map(CostMeter::start);
+        if done.is_some() {
+            // Already in the checkpoint, only the AOVs are left to work out
         }
-
-        // Initialize thread resources for anti-aliasing this time
-        let (sender, receiver) = mpsc::channel::<(u32, u32, Color)>();
-        let progress_sender = progress_tracker.get_sender();
-        progress_sender.send(ProgressMessage::StartAA(aa_corrections.len() as u32)).unwrap();
-
-        let theta = 2.0*PI/render_config.aa_rays as f64;
-
-        let num_corrections = aa_corrections.len();
-        let corrections_per_thread = divide_round_up(aa_corrections.len() as u32, render_config.workload_split);
-        for _ in 0..render_config.workload_split {
-
-            // Clone these for the threads to own a copy
-            // TODO: there has to be a better way for threads to access this info
-            let thread_sender = sender.clone();
-            let thread_progress_sender = progress_tracker.get_sender();
-            let thread_scene = scene.clone();
-
-            // Each thread gets its own list of anti-aliasing corrections to complete
-            // TODO: use this information more effectively in AA process
-            let mut corrections: Vec<(u32, u32, f64, Color)> = Vec::new();
-            for _ in 0..corrections_per_thread {
-                if let Some(correction) = aa_corrections.pop() {
-                    let x = correction.0;
-                    let y = correction.1;
-                    let color_i = color_index(x, y);
-                    let (distance, color) = color_vec[color_i];
-                    corrections.push((x, y, distance, color));
-                }
-                else {
-                    break;
-                }
-            }
-
-            thread_pool.execute(move || {
-
-                let mut rng = rand::thread_rng();
-
-                for correction in corrections.into_iter() {
-                    let x = correction.0;
-                    let y = correction.1;
-                    let mut correction_colors: Vec<Color> = Vec::with_capacity(9);
-                    correction_colors.push(correction.3);
-
-                    let mut aa_directions: Vec<DVec2> = Vec::with_capacity(render_config.aa_rays as usize);
-                    let rand_rotation = rng.gen_range(0.0, 2.0*PI);
-                    for i in 0..render_config.aa_rays {
-                        let x = (rand_rotation + theta * i as f64).cos();
-                        let y = (rand_rotation + theta * i as f64).sin();
-                        aa_directions.push(dvec2!(x, y));
-                    }
-
-                    for aa_direction in aa_directions.iter() {
-                        // let corr_x = correction_dir.0 as f64;
-                        // let corr_y = correction_dir.1 as f64;
-
-                        let rand_distance: f64 = rng.gen_range(0.2, 0.5);
-                        let rand_direction = *aa_direction * rand_distance;
-                        let x_pos = x as f64 + 0.5 + rand_direction.x;
-                        let y_pos = y as f64 + 0.5 + rand_direction.y;
-                        let pixel_location = calculate_pixel_location(x_pos, y_pos);
-                        let prime_ray = Ray::from_destination(camera_config.origin, pixel_location, render_config.recursion_depth);
-
-                        if correction.2 != std::f64::INFINITY {
-                            correction_colors.push(thread_scene.cast_ray(prime_ray));
-                        }
-                        else {
-                            correction_colors.push(thread_scene.get_background_color(prime_ray));
-                        }
-                    }
-                    let mut total_color = Color::BLACK;
-                    let num_colors = correction_colors.len();
-                    for color in correction_colors.into_iter() {
-                        total_color += color;
-                    }
-
-                    thread_sender.send((x, y, total_color / num_colors as f64)).unwrap();
-                    thread_progress_sender.send(ProgressMessage::AAProgress(1)).unwrap();
+        else if integrator.is_random() {
+            // The first ray goes through the middle of the pixel, the rest are spread over it
+            start_center_sample(x, y);
+            let (distance, color) = time_phase(RenderPhase::Trace, || integrator.trace(scene, prime_ray));
+            film.add_sample(filter, x as f64 + 0.5, y as f64 + 0.5, color);
+            time_phase(RenderPhase::AntiAlias, || {
+                for i in 1..render_config.samples {
+                    start_pixel_sample(x, y, i);
+                    let (u, v) = sample_2d();
+                    let (sample_x, sample_y) = (x as f64 + u, y as f64 + v);
+                    let sample_ray = camera.ray(sample_x, sample_y, render_config.recursion_depth);
+                    film.add_sample(filter, sample_x, sample_y, integrator.trace(scene, sample_ray).1);
                 }
             });
+            distances.push(distance);
         }
-
-        // Collect the completed anti-aliasing work from worker threads
-        for _ in 0..num_corrections {
-            let (x, y, color) = receiver.recv().unwrap();
-            color_vec[color_index(x, y)] = (0.0, color);
+        else if render_config.anti_alias {
+            distances.push(adaptive_sample(scene, camera, render_config, x, y, &mut film));
+        }
+        else {
+            start_center_sample(x, y);
+            let (distance, color) = time_phase(RenderPhase::Trace, || integrator.trace(scene, prime_ray));
+            film.add_sample(filter, x as f64 + 0.5, y as f64 + 0.5, color);
+            distances.push(distance);
+        }
+        if let Some(meter) = meter {
+            cost_chunk.push(meter.finish());
+        }
+        if render_config.aovs.any() {
+            aov_chunk.push(time_phase(RenderPhase::Aov, || scene.get_aov_sample(prime_ray, camera.view_direction)));
         }
     }
 
-    // Shove all those colors into an RgbImage
-    make_image(image_dimension.width, image_dimension.height, color_vec.into_iter().map(|x| x.1).collect())
+    let rendered = done.unwrap_or(RenderedTile { tile, distances, film });
+    TileResult { rendered, aovs: aov_chunk, costs: cost_chunk, stats: take_thread_stats() }
 }
 
-fn get_input() -> (usize, usize) {
-    use std::io::stdin;
-    let mut x = 0;
-    let mut y = 0;
-    let mut input = String::new();
-    println!("Enter pixel X: ");
-    'x: loop {
-        input.clear();
-        match stdin().read_line(&mut input) {
-            Ok(_) => {
-                match input.trim_end().parse::<usize>() {
-                    Ok(num) => {
-                        x = num;
-                        break 'x;
-                    },
-                    Err(error) => println!("Invalid X: {}", error),
-                }
+// Tiles finished before an interrupted render don't need doing again. Crops are left out,
+// finishing one would remove the checkpoint the rest of the image still needs, and so are
+// heatmaps, which need to know what every tile cost. Fails if the checkpoint can't be written,
+// or the scene can't be fingerprinted because part of it has no scene file description.
+pub fn open_checkpoint( scene: &Scene,
+                        image_dimension: ImageDimension,
+                        camera_config: CameraConfig,
+                        render_config: &RenderConfig) -> Result<Option<Checkpoint>, Error> {
+    let path = match render_config.checkpoint.as_ref().filter(|_| render_config.crop.is_none() && render_config.heatmap.is_none()) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let fingerprint = render_fingerprint(scene, image_dimension, camera_config, render_config)
+        .map_err(|err| Error::io(path, io::Error::new(io::ErrorKind::Unsupported, format!("scene can't be checkpointed, {}", err))))?;
+    Checkpoint::open(path, image_dimension, render_config.filter, fingerprint).map(Some)
+}
+
+// Identifies the image a render makes from its scene and settings, so tiles of one render aren't
+// mixed up with another's. The thread count, tile order and checkpoint path are left out, changing
+// those doesn't change the image, and so are the AOVs, denoising and tone mapping, which are
+// worked out again from the tiles rather than saved with them.
+pub fn render_fingerprint(  scene: &Scene,
+                            image_dimension: ImageDimension,
+                            camera_config: CameraConfig,
+                            render_config: &RenderConfig) -> Result<u64, SceneFileError> {
+    let mut render = render_config.clone();
+    render.num_threads = 1;
+    render.tile_order = TileOrder::default();
+    render.checkpoint = None;
+    render.aovs = AovConfig::none();
+    render.denoise = None;
+    render.tone_mapping = ToneMapping::default();
+    let description = SceneDescription { scene: scene.clone(), camera: camera_config, image: image_dimension, render };
+    Ok(fingerprint(&description.to_source()?))
+}
+
+// Puts the framebuffer together from the tiles coming in on receiver, in whatever order they finish,
+// saving new ones to the checkpoint as they come in. tiles are all the tiles the render was split into,
+// each one is sent with its index in tiles. Crops the framebuffer and shows heatmaps once they're all in.
+pub fn collect_tiles(   receiver: &mpsc::Receiver<(usize, TileResult)>,
+                        tiles: &[Tile],
+                        image_dimension: ImageDimension,
+                        render_config: &RenderConfig,
+                        checkpoint: Option<Checkpoint>) -> (FrameBuffer, RenderStats) {
+
+    let width = image_dimension.width;
+    let height = image_dimension.height;
+    let mut checkpoint = checkpoint;
+    let mut distances: Vec<f64> = vec![f64::INFINITY; (width * height) as usize];
+    let mut film = Film::new(image_dimension);
+    let mut aov_samples: Vec<AovSample> = if render_config.aovs.any() {
+        vec![AovSample::background(); (width * height) as usize]
+    }
+    else {
+        Vec::new()
+    };
+    let mut costs: Vec<f64> = vec![0.0; (width * height) as usize];
+    let mut stats = RenderStats::default();
+    for _ in 0..tiles.len() {
+        let (_, TileResult { rendered, aovs: tile_aovs, costs: tile_costs, stats: tile_stats }) = receiver.recv().unwrap();
+        stats.add(&tile_stats);
+        let is_new = checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.find(rendered.tile).is_none());
+        if is_new {
+            if let Err(err) = checkpoint.as_mut().unwrap().record(&rendered) {
+                eprintln!("stopped writing checkpoint: {}", err);
+                checkpoint = None;
             }
-            Err(error) => println!("Error of some kind: {}", error),
         }
-    }
-    println!("Enter pixel Y: ");
-    'y: loop {
-        input.clear();
-        match stdin().read_line(&mut input) {
-            Ok(_) => {
-                match input.trim_end().parse::<usize>() {
-                    Ok(num) => {
-                        y = num;
-                        break 'y;
-                    },
-                    Err(error) => println!("Invalid Y: {}", error),
-                }
+        for (i, (x, y)) in rendered.tile.pixels().into_iter().enumerate() {
+            distances[(y*width + x) as usize] = rendered.distances[i];
+            if let Some(aov) = tile_aovs.get(i) {
+                aov_samples[(y*width + x) as usize] = *aov;
+            }
+            if let Some(cost) = tile_costs.get(i) {
+                costs[(y*width + x) as usize] = *cost;
             }
-            Err(error) => println!("Error of some kind: {}", error),
         }
+        film.add_tile(&rendered.film);
+    }
+
+    let mut framebuffer = FrameBuffer::new(width, height);
+    framebuffer.colors = film.resolve();
+    framebuffer.distances = distances;
+    if render_config.aovs.any() {
+        framebuffer.set_aovs(render_config.aovs, &aov_samples);
+    }
+    if render_config.heatmap.is_some() {
+        framebuffer.costs = Some(costs);
     }
-    println!("Entered: ({}, {})", x, y);
-    (x, y)
-}
 
-// integer division, but it rounds up
-fn divide_round_up(a: u32, b:u32) -> u32 {
-    (a as f32 / b as f32).ceil() as u32
+    // The render is done, so there's nothing left to resume
+    if let Some(checkpoint) = checkpoint {
+        if let Err(err) = checkpoint.remove() {
+            eprintln!("could not remove checkpoint: {}", err);
+        }
+    }
+    if let Some(crop) = render_config.crop {
+        framebuffer = framebuffer.crop(crop);
+    }
+    show_costs(&mut framebuffer);
+    (framebuffer, stats)
 }
 
-// Convient function for making an RgbImage
-fn make_image(width: u32, height: u32, colors: Vec<Color>) -> RgbImage {
-    let rgb_vec: Vec<u8> = colors.into_iter().map(|x| x.clamp().to_rgb()).map(|x| vec!(x.data[0], x.data[1], x.data[2]).into_iter()).flatten().collect();
-    let image: Option<RgbImage> = ImageBuffer::from_vec(width, height, rgb_vec);
-    if let Some(image) = image {
-        image
+// Swaps the colors of a heatmap render for its costs, scaled so the 99th percentile is red, see heatmap_scale
+pub fn show_costs(framebuffer: &mut FrameBuffer) {
+    if let Some(ref costs) = framebuffer.costs {
+        framebuffer.colors = heatmap_colors(costs);
     }
-    else {
-        panic!("Could not convert rgb_vec into image");
+}
+
+// The part of the image that needs rendering. With a crop, that's the crop plus however far
+// the filter reaches past it, so the pixels come out the same as in a render of the whole image.
+pub fn render_region(render_config: &RenderConfig, image_dimension: ImageDimension) -> Tile {
+    let whole = Tile::new(0, 0, image_dimension.width, image_dimension.height);
+    match render_config.crop {
+        Some(crop) => {
+            assert!(crop.clip(image_dimension) == Some(crop), "crop {:?} doesn't fit in the {}x{} image", crop, image_dimension.width, image_dimension.height);
+            crop.grow(render_config.filter.reach(), image_dimension)
+        },
+        None => whole,
     }
-} 
\ No newline at end of file
+}
+
+// Everything the render works out for a single pixel
+#[derive(Debug, Clone, Copy)]
+pub struct PixelResult {
+    pub color: Color,
+    // Along the ray through the middle of the pixel, infinite if it didn't hit anything
+    pub distance: f64,
+    // What the ray through the middle of the pixel hit, see AovSample
+    pub aov: AovSample,
+}
+
+// Renders one pixel exactly as it would come out of a render of the whole image,
+// eg. to look into a pixel that looks wrong without waiting for everything else
+pub fn render_pixel(scene: Scene,
+                    image_dimension: ImageDimension,
+                    camera_config: CameraConfig,
+                    render_config: RenderConfig,
+                    x: u32,
+                    y: u32) -> PixelResult {
+
+    let camera = CameraRays::new(camera_config, image_dimension);
+    let aov = scene.get_aov_sample(camera.ray(x as f64 + 0.5, y as f64 + 0.5, render_config.recursion_depth), camera.view_direction);
+
+    // Denoising needs the pixels around it, so a pixel on its own is left as it is
+    let mut render_config = render_config;
+    render_config.crop = Some(Tile::new(x, y, 1, 1));
+    render_config.aovs = AovConfig::none();
+    render_config.denoise = None;
+    let framebuffer = render_framebuffer_with_config(scene, image_dimension, camera_config, render_config);
+    PixelResult { color: framebuffer.colors[0], distance: framebuffer.distances[0], aov }
+}
+
+// Traces a pixel until its average is sure enough, see PixelSamples::noise, adding the samples to film.
+// The first ray goes through the middle of the pixel and gives the distance, the rest are spread over it.
+fn adaptive_sample(scene: &Scene, camera: CameraRays, render_config: &RenderConfig, x: u32, y: u32, film: &mut FilmTile) -> f64 {
+    let integrator = &render_config.integrator;
+    start_center_sample(x, y);
+    let (distance, color) = time_phase(RenderPhase::Trace, || integrator.trace(scene, camera.ray(x as f64 + 0.5, y as f64 + 0.5, render_config.recursion_depth)));
+    film.add_sample(render_config.filter, x as f64 + 0.5, y as f64 + 0.5, color);
+    let mut pixel = PixelSamples::default();
+    pixel.add(color);
+    time_phase(RenderPhase::AntiAlias, || {
+        while pixel.count < render_config.aa_max_samples {
+            if pixel.count >= render_config.aa_min_samples && pixel.noise() <= render_config.aa_noise_threshold {
+                break;
+            }
+            start_pixel_sample(x, y, pixel.count);
+            let (u, v) = sample_2d();
+            let (sample_x, sample_y) = (x as f64 + u, y as f64 + v);
+            let color = integrator.trace(scene, camera.ray(sample_x, sample_y, render_config.recursion_depth)).1;
+            film.add_sample(render_config.filter, sample_x, sample_y, color);
+            pixel.add(color);
+        }
+    });
+    distance
+}
+ 
\ No newline at end of file
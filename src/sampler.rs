@@ -0,0 +1,440 @@
+use std::cell::RefCell;
+use std::f64;
+use rand::prelude::*;
+
+// Largest f64 below 1, samples are always in 0..1
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+// Where random numbers for rendering come from. Each sample of a pixel asks for a series of
+// numbers, the first two place it inside the pixel and the rest go to whatever the ray runs into:
+// picking points on area lights, bouncing off matte surfaces, choosing between reflection and
+// refraction and so on. Samplers that know about the other samples of the same pixel can spread
+// them out evenly instead of leaving it to chance, which makes the noise go away faster.
+pub trait Sampler {
+    // Starts the index'th sample of pixel x, y. The numbers that follow only depend on these and
+    // the sampler's seed, not on what was sampled before.
+    fn start_sample(&mut self, x: u32, y: u32, index: u32);
+
+    // The next number of the sample, in 0..1
+    fn next_1d(&mut self) -> f64;
+
+    // The next two numbers of the sample, for picking a point on a square
+    fn next_2d(&mut self) -> (f64, f64) {
+        let u = self.next_1d();
+        (u, self.next_1d())
+    }
+}
+
+// The kinds of Sampler a render can use
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SamplerKind {
+    // Every number is picked at random
+    #[default]
+    Independent,
+    // Splits each dimension into as many strata as there are samples, then jitters one sample into each
+    Stratified,
+    // Halton sequence, randomly shifted for each pixel
+    Halton,
+    // Sobol sequence, randomly scrambled for each pixel
+    Sobol,
+}
+
+impl SamplerKind {
+    pub const ALL: [SamplerKind; 4] = [SamplerKind::Independent, SamplerKind::Stratified, SamplerKind::Halton, SamplerKind::Sobol];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplerKind::Independent => "independent",
+            SamplerKind::Stratified => "stratified",
+            SamplerKind::Halton => "halton",
+            SamplerKind::Sobol => "sobol",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SamplerKind> {
+        SamplerKind::ALL.iter().find(|kind| kind.name() == name).cloned()
+    }
+
+    // samples_per_pixel is how many samples each pixel is expected to get, samplers spread
+    // that many out evenly. Pixels that end up with more still get good samples, just not as well spread.
+    pub fn create(&self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+// Small fast random number generator, so samples come out the same on every machine
+#[derive(Debug, Clone, Copy)]
+struct SampleRng {
+    state: u64,
+}
+
+impl SampleRng {
+    fn new(seed: u64) -> SampleRng {
+        SampleRng { state: seed }
+    }
+
+    // splitmix64
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix(self.state)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x2545_f491_4f6c_dd1d, |hash, value| mix(hash ^ value.wrapping_add(0x9e37_79b9_7f4a_7c15)))
+}
+
+// Bookkeeping every sampler needs: which pixel and sample it's on, how many numbers it's given out,
+// and a random number generator for that sample
+#[derive(Debug, Clone, Copy)]
+struct SampleState {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: u32,
+    rng: SampleRng,
+}
+
+impl SampleState {
+    fn new(seed: u64) -> SampleState {
+        SampleState { seed, pixel: 0, index: 0, dimension: 0, rng: SampleRng::new(seed) }
+    }
+
+    fn start(&mut self, x: u32, y: u32, index: u32) {
+        self.pixel = hash(&[self.seed, x as u64, y as u64]);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = SampleRng::new(hash(&[self.pixel, index as u64]));
+    }
+
+    // Moves on to the next dimension, returning the one that was current
+    fn advance(&mut self, count: u32) -> u32 {
+        let dimension = self.dimension;
+        self.dimension += count;
+        dimension
+    }
+
+    // Random value that's the same for every sample of this pixel in the given dimension
+    fn pixel_hash(&self, dimension: u32, extra: u64) -> u64 {
+        hash(&[self.pixel, dimension as u64, extra])
+    }
+}
+
+pub struct IndependentSampler {
+    state: SampleState,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> IndependentSampler {
+        IndependentSampler { state: SampleState::new(seed) }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.state.advance(1);
+        self.state.rng.next_f64()
+    }
+}
+
+// Jittered sampling. Each dimension of a pixel is split into samples_per_pixel strata, or a
+// square grid of them for pairs, and every sample lands in a different one. Which sample gets
+// which stratum is shuffled separately for each dimension so the dimensions don't line up.
+pub struct StratifiedSampler {
+    state: SampleState,
+    strata: u32,
+    // Strata along each side of the grid for 2d samples
+    side: u32,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> StratifiedSampler {
+        let strata = samples_per_pixel.max(1);
+        let side = (strata as f64).sqrt().floor() as u32;
+        StratifiedSampler { state: SampleState::new(seed), strata, side }
+    }
+
+    // Which of the strata the current sample is in. After every stratum has had a sample
+    // it starts over with a different shuffle.
+    fn stratum(&self, dimension: u32, strata: u32) -> u32 {
+        let round = self.state.index / strata;
+        let shuffle = self.state.pixel_hash(dimension, round as u64) as u32;
+        permute(self.state.index % strata, strata, shuffle)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dimension = self.state.advance(1);
+        let stratum = self.stratum(dimension, self.strata);
+        ((stratum as f64 + self.state.rng.next_f64()) / self.strata as f64).min(ONE_MINUS_EPSILON)
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let dimension = self.state.advance(2);
+        let side = self.side;
+        let stratum = self.stratum(dimension, side * side);
+        let u = (((stratum % side) as f64 + self.state.rng.next_f64()) / side as f64).min(ONE_MINUS_EPSILON);
+        let v = (((stratum / side) as f64 + self.state.rng.next_f64()) / side as f64).min(ONE_MINUS_EPSILON);
+        (u, v)
+    }
+}
+
+// The i'th entry of a random permutation of 0..length picked by seed, without building the permutation.
+// From Kensler, "Correlated Multi-Jittered Sampling".
+fn permute(index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    // Shuffles everything up to the next power of two, trying again until the result is in range
+    let mut i = index;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    ((i as u64 + seed as u64) % length as u64) as u32
+}
+
+// Bases for each dimension of the Halton sequence. Higher bases need a lot of samples before
+// they're any better than random, so dimensions past these are random.
+const HALTON_PRIMES: [u32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+// Each dimension is the radical inverse of the sample index in a different prime base.
+// Every pixel would get the same points, so they're shifted by a random amount per pixel
+// (a Cranley-Patterson rotation), which keeps them just as evenly spread.
+pub struct HaltonSampler {
+    state: SampleState,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> HaltonSampler {
+        HaltonSampler { state: SampleState::new(seed) }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dimension = self.state.advance(1);
+        match HALTON_PRIMES.get(dimension as usize) {
+            Some(&base) => {
+                let shift = SampleRng::new(self.state.pixel_hash(dimension, 0)).next_f64();
+                let value = radical_inverse(base, self.state.index) + shift;
+                (if value >= 1.0 { value - 1.0 } else { value }).min(ONE_MINUS_EPSILON)
+            },
+            None => self.state.rng.next_f64(),
+        }
+    }
+}
+
+// Mirrors the digits of index in base around the decimal point, eg. 6 = 110 in base 2 becomes 0.011
+fn radical_inverse(base: u32, index: u32) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut index = index;
+    let mut reversed = 0.0;
+    let mut scale = inverse_base;
+    while index > 0 {
+        reversed += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inverse_base;
+    }
+    reversed
+}
+
+// Primitive polynomials and initial direction numbers for Sobol dimensions after the first,
+// as (degree, coefficients, initial numbers), from Joe and Kuo's new-joe-kuo-6.21201
+const SOBOL_POLYNOMIALS: [(u32, u32, [u32; 6]); 15] = [
+    (1, 0, [1, 0, 0, 0, 0, 0]),
+    (2, 1, [1, 3, 0, 0, 0, 0]),
+    (3, 1, [1, 3, 1, 0, 0, 0]),
+    (3, 2, [1, 1, 1, 0, 0, 0]),
+    (4, 1, [1, 1, 3, 3, 0, 0]),
+    (4, 4, [1, 3, 5, 13, 0, 0]),
+    (5, 2, [1, 1, 5, 5, 17, 0]),
+    (5, 4, [1, 1, 5, 5, 5, 0]),
+    (5, 7, [1, 1, 7, 11, 19, 0]),
+    (5, 11, [1, 1, 5, 1, 1, 0]),
+    (5, 13, [1, 1, 1, 3, 11, 0]),
+    (5, 14, [1, 3, 5, 5, 31, 0]),
+    (6, 1, [1, 3, 3, 9, 7, 49]),
+    (6, 13, [1, 1, 1, 15, 21, 21]),
+    (6, 16, [1, 3, 1, 13, 27, 49]),
+];
+
+const SOBOL_BITS: usize = 32;
+
+// Sobol sequence, made of the bits of the sample index XORed with each dimension's direction numbers.
+// Every pixel scrambles the bits with its own random seed as well (an Owen scramble), which keeps
+// the points just as evenly spread while stopping pixels from looking the same.
+pub struct SobolSampler {
+    state: SampleState,
+    directions: Vec<[u32; SOBOL_BITS]>,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> SobolSampler {
+        let mut directions = Vec::with_capacity(SOBOL_POLYNOMIALS.len() + 1);
+
+        // The first dimension is the van der Corput sequence
+        let mut first = [0; SOBOL_BITS];
+        for (bit, direction) in first.iter_mut().enumerate() {
+            *direction = 1 << (SOBOL_BITS - 1 - bit);
+        }
+        directions.push(first);
+
+        for &(degree, coefficients, initial) in SOBOL_POLYNOMIALS.iter() {
+            let degree = degree as usize;
+            let mut numbers = [0u32; SOBOL_BITS];
+            for bit in 0..SOBOL_BITS {
+                numbers[bit] = if bit < degree {
+                    initial[bit] << (SOBOL_BITS - 1 - bit)
+                }
+                else {
+                    let mut number = numbers[bit - degree] ^ (numbers[bit - degree] >> degree);
+                    for k in 1..degree {
+                        if (coefficients >> (degree - 1 - k)) & 1 == 1 {
+                            number ^= numbers[bit - k];
+                        }
+                    }
+                    number
+                };
+            }
+            directions.push(numbers);
+        }
+        SobolSampler { state: SampleState::new(seed), directions }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dimension = self.state.advance(1);
+        match self.directions.get(dimension as usize) {
+            Some(numbers) => {
+                let mut bits = 0;
+                let mut index = self.state.index;
+                let mut bit = 0;
+                while index > 0 {
+                    if index & 1 == 1 {
+                        bits ^= numbers[bit];
+                    }
+                    index >>= 1;
+                    bit += 1;
+                }
+                let bits = owen_scramble(bits, self.state.pixel_hash(dimension, 0) as u32);
+                bits as f64 / (1u64 << 32) as f64
+            },
+            None => self.state.rng.next_f64(),
+        }
+    }
+}
+
+// Randomly flips bits of a 0..1 fraction so that each bit depends on the ones above it, which shuffles
+// the points within every power of two stratum without moving any of them to another one.
+// From Burley, "Practical Hash-based Owen Scrambling".
+fn owen_scramble(bits: u32, seed: u32) -> u32 {
+    let mut v = bits.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20_adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x0552_6c56);
+    v ^= v.wrapping_mul(0x53a2_2864);
+    v.reverse_bits()
+}
+
+thread_local! {
+    static THREAD_SAMPLER: RefCell<Option<Box<dyn Sampler>>> = RefCell::new(None);
+}
+
+// Makes sampler the one sample_1d and sample_2d use on this thread, each render worker sets its
+// own so shaders and lights don't need it passed all the way down to them
+pub fn set_thread_sampler(sampler: Box<dyn Sampler>) {
+    THREAD_SAMPLER.with(|current| *current.borrow_mut() = Some(sampler));
+}
+
+// Starts the index'th sample of pixel x, y on this thread's sampler
+pub fn start_pixel_sample(x: u32, y: u32, index: u32) {
+    THREAD_SAMPLER.with(|current| {
+        if let Some(sampler) = current.borrow_mut().as_mut() {
+            sampler.start_sample(x, y, index);
+        }
+    });
+}
+
+// Starts sample 0 of pixel x, y, the one through the middle of the pixel. It still uses up the two
+// numbers the other samples place themselves in the pixel with, otherwise its lights and bounces
+// would get the numbers the others use for where they are.
+pub fn start_center_sample(x: u32, y: u32) {
+    start_pixel_sample(x, y, 0);
+    sample_2d();
+}
+
+// Next number of the current sample, from this thread's sampler or at random if it doesn't have one
+pub fn sample_1d() -> f64 {
+    THREAD_SAMPLER.with(|current| match current.borrow_mut().as_mut() {
+        Some(sampler) => sampler.next_1d(),
+        None => rand::thread_rng().gen::<f64>(),
+    })
+}
+
+pub fn sample_2d() -> (f64, f64) {
+    THREAD_SAMPLER.with(|current| match current.borrow_mut().as_mut() {
+        Some(sampler) => sampler.next_2d(),
+        None => {
+            let mut rng = rand::thread_rng();
+            (rng.gen::<f64>(), rng.gen::<f64>())
+        },
+    })
+}
@@ -1,7 +1,7 @@
 use euler::{dvec3, DVec3, DMat4};
 use color::Color;
 use light::{Lightable, AmbientLight};
-use geometry::{matrix, NodeIntersect, Intersectable, Transformable, TransformComponent, Ray, SurfaceCoord};
+use geometry::{matrix, NodeIntersect, Intersectable, Transformable, TransformComponent, Ray, RayKind, SurfaceCoord};
 use shader::{Shadable, PhongShader};
 use texture::{TextureMappable, ImageTexture};
 use snowflake::ProcessUniqueId;
@@ -9,6 +9,11 @@ use image::{RgbImage, ImageBuffer};
 use std::f64::consts::PI;
 use std::f64;
 use std::sync::Arc;
+use scene_file::{Statement, SceneFileError};
+use aov::AovSample;
+use error::Error;
+use ray_tree::{start_ray, record_hit, finish_ray, record_shadow_ray};
+use stats::{count_ray, count_intersection_test, uncounted};
 
 // TODO: find a better place for SkyBox
 // TODO: We've implemented textures, use textures for skybox
@@ -21,13 +26,20 @@ pub struct SkyBox {
 }
 
 impl SkyBox {
-    pub fn from_path(path: &str, matrix: DMat4) -> SkyBox {
+    pub fn new(image: Box<dyn TextureMappable + Send + Sync>, matrix: DMat4) -> SkyBox {
         SkyBox {
-            image: ImageTexture::from_path(path),
+            image,
             transform: TransformComponent::new(matrix),
         }
     }
 
+    pub fn from_path(path: &str, matrix: DMat4) -> Result<SkyBox, Error> {
+        Ok(SkyBox {
+            image: ImageTexture::from_path(path)?,
+            transform: TransformComponent::new(matrix),
+        })
+    }
+
     pub fn get_color(&self, ray: Ray) -> Color {
         // TODO: i don't like how this line has 3 "transform"s 
         let ray = ray.transform(self.transform.get_inverse_transform());
@@ -41,6 +53,11 @@ impl SkyBox {
         
         self.image.get_color(SurfaceCoord::new(u, v))
     }
+
+    // Written as the texture's path, eg. `background "path" { transform {...} }`
+    pub fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(self.image.to_statement()?.renamed("background").with_transform(self.transform.get_transform()))
+    }
 }
 
 #[derive(Clone)]
@@ -64,32 +81,90 @@ impl Scene {
     }
 
     pub fn cast_ray(&self, ray: Ray) -> Color {
-        if ray.get_depth() > 0 && ray.get_contribution() > Ray::MIN_CONTRIBUTION {
-            let node_intersect = self.root.trace(ray);
-            if let Some(node_intersect) = node_intersect {
-                return node_intersect.shader.get_color(self, node_intersect.intersect);
-            }
-        }
-        self.get_background_color(ray)
+        self.cast_ray_get_distance(ray).1
     }
 
+    // Every ray cast goes in the ray tree when one is being recorded, see record_ray_tree
     pub fn cast_ray_get_distance(&self, ray: Ray) -> (f64, Color) {
-        if ray.get_depth() > 0 && ray.get_contribution() > Ray::MIN_CONTRIBUTION {
-            let node_intersect = self.root.trace(ray);
-            if let Some(node_intersect) = node_intersect {
-                return (node_intersect.get_distance(), 
-                        node_intersect.shader.get_color(self, node_intersect.intersect));
-            }
+        start_ray(ray);
+        let node_intersect = if ray.get_depth() > 0 && ray.get_contribution() > Ray::MIN_CONTRIBUTION {
+            self.trace(ray)
         }
-        (f64::INFINITY, self.get_background_color(ray))
+        else {
+            None
+        };
+        let result = match node_intersect {
+            Some(node_intersect) => {
+                record_hit(&node_intersect);
+                (node_intersect.get_distance(), 
+                 node_intersect.shader.get_color(self, node_intersect.intersect))
+            },
+            None => (f64::INFINITY, self.get_background_color(ray)),
+        };
+        finish_ray(result.1);
+        result
+    }
+
+    // Like cast_ray but for path tracing, see Shadable::get_path_color.
+    // Paths that run out of depth go dark instead of picking up the background.
+    pub fn cast_path(&self, ray: Ray) -> Color {
+        self.cast_path_get_distance(ray).1
+    }
+
+    pub fn cast_path_get_distance(&self, ray: Ray) -> (f64, Color) {
+        if ray.get_depth() == 0 {
+            return (f64::INFINITY, Color::BLACK);
+        }
+        start_ray(ray);
+        let result = match self.trace(ray) {
+            Some(node_intersect) => {
+                record_hit(&node_intersect);
+                (node_intersect.get_distance(),
+                 node_intersect.shader.get_path_color(self, node_intersect.intersect))
+            },
+            None => (f64::INFINITY, self.get_background_color(ray)),
+        };
+        finish_ray(result.1);
+        result
+    }
+
+    // The first thing the ray hits, counted in the render stats by its RayKind.
+    // Integrators that don't go through cast_ray use it instead of root.trace.
+    pub fn trace(&self, ray: Ray) -> Option<NodeIntersect<'_>> {
+        count_ray(ray.kind());
+        self.root.trace(ray)
+    }
+
+    // Whatever is in the way of ray before max_distance, which can be infinite.
+    // Used for shadows and other visibility checks, so they show up in the ray tree and render stats.
+    pub fn trace_shadow(&self, ray: Ray, max_distance: f64) -> Option<NodeIntersect<'_>> {
+        count_ray(RayKind::Shadow);
+        let blocker = if max_distance.is_finite() {
+            self.root.partial_trace_until_distance(ray, max_distance)
+        }
+        else {
+            self.root.trace(ray)
+        };
+        record_shadow_ray(ray, blocker.as_ref());
+        blocker
+    }
+
+    // What the ray hit, for the AOV passes. Not counted in the render stats, it's the same ray
+    // the camera already traced.
+    pub fn get_aov_sample(&self, ray: Ray, view_direction: DVec3) -> AovSample {
+        uncounted(|| match self.root.trace(ray) {
+            Some(node_intersect) => AovSample::from_intersect(self, &node_intersect, ray.origin, view_direction),
+            None => AovSample::background(),
+        })
     }
 
     pub fn add_light(&mut self, light: Box<Lightable + Send + Sync>) {
         self.lights.push(light);
     }
 
-    pub fn set_background_from_path(&mut self, file_path: &str) {
-        self.background = Some(SkyBox::from_path(file_path, DMat4::identity()));
+    pub fn set_background_from_path(&mut self, file_path: &str) -> Result<(), Error> {
+        self.background = Some(SkyBox::from_path(file_path, DMat4::identity())?);
+        Ok(())
     }
 
     pub fn set_background(&mut self, background: SkyBox) {
@@ -117,6 +192,11 @@ pub trait Traceable: TraceableClone {
     fn trace(&self, ray: Ray) -> Option<NodeIntersect>;
     fn partial_trace_until_distance(&self, ray: Ray, max_distance: f64) -> Option<NodeIntersect>;
     fn total_trace_until_distance(&self, ray: Ray, max_distance: f64) -> Vec<NodeIntersect>;
+
+    // Describes the node and its children as a `node` statement for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("node has no scene file description"))
+    }
 }
 
 pub trait TraceableClone {
@@ -198,6 +278,7 @@ impl Traceable for SceneNode {
         let ray = ray.transform(self.transform.get_inverse_transform());
 
         if let Some(ref primitive) = self.primitive {
+            count_intersection_test(primitive.kind());
             if let Some(intersect) = primitive.get_closest_intersect(ray) {
                 final_node_intersect = Some(NodeIntersect::new(self.id, &(*self.material), intersect));
             }
@@ -230,6 +311,7 @@ impl Traceable for SceneNode {
         let max_distance = (ray.origin - max_distance_point).length();
 
         if let Some(ref primitive) = self.primitive {
+            count_intersection_test(primitive.kind());
             if let Some(intersect) = primitive.get_closest_intersect(ray) {
                 if intersect.distance <= max_distance {
                     return Some(NodeIntersect::new(self.id, &(*self.material), intersect).transform(self.transform.get_transform()));
@@ -254,6 +336,7 @@ impl Traceable for SceneNode {
 
         let mut all_intersects: Vec<NodeIntersect> = Vec::new();
         if let Some(ref primitive) = self.primitive {
+            count_intersection_test(primitive.kind());
             let intersects: Vec<NodeIntersect> = primitive.get_all_intersects(ray)
                                                           .into_iter()
                                                           .filter( |inter| inter.distance <= max_distance )
@@ -270,4 +353,17 @@ impl Traceable for SceneNode {
         // transform all intersects in all_intersects
         all_intersects.iter().map(|sect| sect.transform(self.transform.get_transform())).collect()
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let mut statement = Statement::new("node").with_transform(self.transform.get_transform());
+        // Materials aren't inherited, so they only matter on nodes with a primitive
+        if let Some(ref primitive) = self.primitive {
+            statement = statement.with_child(primitive.to_statement()?);
+            statement = statement.with_child(self.material.to_statement()?);
+        }
+        for child in self.children.iter() {
+            statement = statement.with_child(child.to_statement()?);
+        }
+        Ok(statement)
+    }
 }
\ No newline at end of file
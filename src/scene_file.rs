@@ -0,0 +1,192 @@
+use std::fmt;
+use std::error::Error;
+use std::fs;
+use std::io;
+use scene::Scene;
+use render::{RenderConfig, CameraConfig, ImageDimension};
+
+mod parser;
+mod builder;
+mod writer;
+
+pub use self::parser::{Statement, Arg, Value, Position};
+
+// A scene with the camera, image size and render settings to render it with, read from or written
+// to a scene file so scenes can be changed without recompiling. See docs/scene_format.md for the format.
+pub struct SceneDescription {
+    pub scene: Scene,
+    pub camera: CameraConfig,
+    pub image: ImageDimension,
+    pub render: RenderConfig,
+}
+
+impl SceneDescription {
+    // Describe a scene with the default camera and render settings
+    pub fn from_scene(scene: Scene) -> SceneDescription {
+        SceneDescription {
+            scene,
+            camera: CameraConfig::default(),
+            image: ImageDimension::new(512, 512),
+            render: RenderConfig::default(),
+        }
+    }
+
+    pub fn from_path(path: &str) -> Result<SceneDescription, SceneFileError> {
+        let source = fs::read_to_string(path).map_err(SceneFileError::Io)?;
+        SceneDescription::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<SceneDescription, SceneFileError> {
+        let statements = parser::parse(source)?;
+        builder::build_description(&statements)
+    }
+
+    pub fn to_statements(&self) -> Result<Vec<Statement>, SceneFileError> {
+        let render = &self.render;
+        let camera = &self.camera;
+        let scene = &self.scene;
+        let mut render_statement = Statement::new("render").with_children(vec!(
+            Statement::new("threads").with_number(render.num_threads as f64),
+            Statement::new("tile_size").with_number(render.tile_size as f64),
+            Statement::new("tile_order").with_ident(render.tile_order.name()),
+            Statement::new("anti_alias").with_ident(if render.anti_alias {"true"} else {"false"}),
+            Statement::new("aa_noise_threshold").with_number(render.aa_noise_threshold),
+            Statement::new("aa_min_samples").with_number(render.aa_min_samples as f64),
+            Statement::new("aa_max_samples").with_number(render.aa_max_samples as f64),
+            Statement::new("filter").with_ident(render.filter.kind.name()).with_number(render.filter.radius),
+            Statement::new("sampler").with_ident(render.sampler.name()),
+            Statement::new("seed").with_number(render.seed as f64),
+            Statement::new("recursion_depth").with_number(render.recursion_depth as f64),
+            render.integrator.to_statement()?,
+            Statement::new("samples").with_number(render.samples as f64),
+            Statement::new("tone_map").with_ident(render.tone_mapping.operator.name()),
+            Statement::new("exposure").with_number(render.tone_mapping.exposure),
+        ));
+        if render.aovs.any() {
+            let aovs = render.aovs.passes().iter().fold(Statement::new("aovs"), |aovs, pass| aovs.with_ident(pass.name()));
+            render_statement = render_statement.with_child(aovs);
+        }
+        if let Some(crop) = render.crop {
+            let crop_statement = Statement::new("crop").with_number(crop.x as f64).with_number(crop.y as f64);
+            render_statement = render_statement.with_child(crop_statement.with_number(crop.width as f64).with_number(crop.height as f64));
+        }
+        if let Some(ref checkpoint) = render.checkpoint {
+            render_statement = render_statement.with_child(Statement::new("checkpoint").with_string(checkpoint));
+        }
+        if let Some(progressive) = render.progressive {
+            let mut limits = vec!(
+                Statement::new("samples").with_number(progressive.samples as f64),
+                Statement::new("samples_per_pass").with_number(progressive.samples_per_pass as f64),
+            );
+            if let Some(time_limit) = progressive.time_limit {
+                limits.push(Statement::new("time_limit").with_number(time_limit.as_secs_f64()));
+            }
+            if let Some(noise_threshold) = progressive.noise_threshold {
+                limits.push(Statement::new("noise_threshold").with_number(noise_threshold));
+            }
+            render_statement = render_statement.with_child(Statement::new("progressive").with_children(limits));
+        }
+        if let Some(denoise) = render.denoise {
+            render_statement = render_statement.with_child(Statement::new("denoise").with_children(vec!(
+                Statement::new("iterations").with_number(denoise.iterations as f64),
+                Statement::new("color_sigma").with_number(denoise.color_sigma),
+                Statement::new("normal_sigma").with_number(denoise.normal_sigma),
+                Statement::new("depth_sigma").with_number(denoise.depth_sigma),
+                Statement::new("albedo_sigma").with_number(denoise.albedo_sigma),
+            )));
+        }
+        if let Some(metric) = render.heatmap {
+            render_statement = render_statement.with_child(Statement::new("heatmap").with_ident(metric.name()));
+        }
+        let mut statements = vec!(
+            Statement::new("image").with_number(self.image.width as f64).with_number(self.image.height as f64),
+            render_statement,
+            Statement::new("camera").with_children(vec!(
+                Statement::new("origin").with_vec3(camera.origin),
+                Statement::new("target").with_vec3(camera.target),
+                Statement::new("up").with_vec3(camera.up),
+                Statement::new("fov_y").with_number(camera.fov_y),
+            )),
+            Statement::new("ambient").with_children(vec!(
+                Statement::new("color").with_color(scene.ambient_light.color),
+                Statement::new("power").with_number(scene.ambient_light.power),
+            )),
+        );
+        if let Some(ref background) = scene.background {
+            statements.push(background.to_statement()?);
+        }
+        for light in scene.lights.iter() {
+            statements.push(light.to_statement()?);
+        }
+        statements.push(scene.root.to_statement()?);
+        Ok(statements)
+    }
+
+    // Just the image, render and camera statements, eg. to send the settings of a render
+    // to another process that loads the scene itself
+    pub fn settings_source(&self) -> Result<String, SceneFileError> {
+        let settings: Vec<Statement> = self.to_statements()?.into_iter()
+            .filter(|statement| ["image", "render", "camera"].contains(&statement.name.as_str()))
+            .collect();
+        Ok(writer::write(&settings))
+    }
+
+    pub fn to_source(&self) -> Result<String, SceneFileError> {
+        Ok(writer::write(&self.to_statements()?))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SceneFileError> {
+        fs::write(path, self.to_source()?).map_err(SceneFileError::Io)
+    }
+}
+
+// Hash of a scene's source, to check two descriptions are the same without sending or keeping
+// either of them. FNV-1a, unlike the standard library's hasher it's the same in every build.
+pub fn fingerprint(source: &str) -> u64 {
+    source.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(io::Error),
+    Invalid {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // Part of a scene that can't be described in a scene file
+    Unsupported(String),
+}
+
+impl SceneFileError {
+    pub fn unsupported(message: &str) -> SceneFileError {
+        SceneFileError::Unsupported(String::from(message))
+    }
+
+    fn invalid(position: Position, message: &str) -> SceneFileError {
+        SceneFileError::Invalid {
+            line: position.line,
+            column: position.column,
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneFileError::Io(error) => write!(f, "could not access scene file: {}", error),
+            SceneFileError::Invalid{line, column, message} => write!(f, "line {}, column {}: {}", line, column, message),
+            SceneFileError::Unsupported(message) => write!(f, "can't write to scene file: {}", message),
+        }
+    }
+}
+
+impl Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneFileError::Io(error) => Some(error),
+            SceneFileError::Invalid{..} | SceneFileError::Unsupported(_) => None,
+        }
+    }
+}
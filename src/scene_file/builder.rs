@@ -0,0 +1,854 @@
+use std::path::Path;
+use std::time::Duration;
+use euler::{DVec2, dvec2, DVec3, dvec3, DMat4};
+use color::{Color, ColorEncoding};
+use scene::{Scene, SceneNode, SkyBox, Traceable};
+use render::{RenderConfig, CameraConfig, ImageDimension};
+use light::{Lightable, AmbientLight, PointLight, SquareLight, DirectionLight};
+use geometry::{Intersectable, Transformable};
+use geometry::matrix::*;
+use primitive::*;
+use composite_shape::*;
+use shader::*;
+use texture::{TextureMappable, ImageTexture};
+use normal_map::{NormalMappable, NormalMap, BumpMap};
+use mesh::Mesh;
+use error::Error;
+use aov::{AovConfig, AovPass};
+use tone_map::{ToneMapping, ToneMapOperator};
+use integrator::{Integrator, DebugIntegrator, AmbientOcclusionIntegrator, integrator_from_name};
+use progressive::ProgressiveConfig;
+use filter::{PixelFilter, FilterKind};
+use sampler::SamplerKind;
+use heatmap::CostMetric;
+use denoise::DenoiseConfig;
+use tile::{Tile, TileOrder};
+use super::{SceneDescription, SceneFileError};
+use super::parser::{Statement, Value, Position};
+
+type BuildResult<T> = Result<T, SceneFileError>;
+
+fn error<T>(position: Position, message: &str) -> BuildResult<T> {
+    Err(SceneFileError::invalid(position, message))
+}
+
+fn unknown_statement<T>(statement: &Statement, parent: &str) -> BuildResult<T> {
+    error(statement.position, &format!("unknown statement '{}' in {}", statement.name, parent))
+}
+
+fn expect_args(statement: &Statement, count: usize) -> BuildResult<()> {
+    if statement.args.len() != count {
+        return error(statement.position, &format!("'{}' expects {} argument(s), found {}", statement.name, count, statement.args.len()));
+    }
+    Ok(())
+}
+
+fn expect_no_children(statement: &Statement) -> BuildResult<()> {
+    if let Some(child) = statement.children.first() {
+        return unknown_statement(child, &format!("'{}'", statement.name));
+    }
+    Ok(())
+}
+
+fn check_children(statement: &Statement, allowed: &[&str]) -> BuildResult<()> {
+    for child in statement.children.iter() {
+        if !allowed.contains(&child.name.as_str()) {
+            return unknown_statement(child, &format!("'{}'", statement.name));
+        }
+    }
+    Ok(())
+}
+
+fn arg_position(statement: &Statement, i: usize) -> Position {
+    match statement.args.get(i) {
+        Some(arg) => arg.position,
+        None => statement.position,
+    }
+}
+
+fn number(statement: &Statement, i: usize) -> BuildResult<f64> {
+    match statement.args.get(i).map(|arg| &arg.value) {
+        Some(Value::Number(num)) => Ok(*num),
+        Some(_) => error(arg_position(statement, i), &format!("'{}' expects a number here", statement.name)),
+        None => error(statement.position, &format!("'{}' is missing argument {}", statement.name, i + 1)),
+    }
+}
+
+fn unsigned(statement: &Statement, i: usize) -> BuildResult<u32> {
+    let num = number(statement, i)?;
+    if num < 0.0 || num.fract() != 0.0 || num > u32::MAX as f64 {
+        return error(arg_position(statement, i), &format!("'{}' expects a whole number here", statement.name));
+    }
+    Ok(num as u32)
+}
+
+fn string(statement: &Statement, i: usize) -> BuildResult<String> {
+    match statement.args.get(i).map(|arg| &arg.value) {
+        Some(Value::Str(string)) => Ok(string.clone()),
+        Some(_) => error(arg_position(statement, i), &format!("'{}' expects a string here", statement.name)),
+        None => error(statement.position, &format!("'{}' is missing argument {}", statement.name, i + 1)),
+    }
+}
+
+fn ident(statement: &Statement, i: usize) -> BuildResult<String> {
+    match statement.args.get(i).map(|arg| &arg.value) {
+        Some(Value::Ident(ident)) => Ok(ident.clone()),
+        Some(_) => error(arg_position(statement, i), &format!("'{}' expects a name here", statement.name)),
+        None => error(statement.position, &format!("'{}' is missing argument {}", statement.name, i + 1)),
+    }
+}
+
+fn boolean(statement: &Statement, i: usize) -> BuildResult<bool> {
+    match ident(statement, i)?.as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => error(arg_position(statement, i), &format!("'{}' expects true or false", statement.name)),
+    }
+}
+
+fn vec2(statement: &Statement, i: usize) -> BuildResult<DVec2> {
+    Ok(dvec2!(number(statement, i)?, number(statement, i+1)?))
+}
+
+fn vec3(statement: &Statement, i: usize) -> BuildResult<DVec3> {
+    Ok(dvec3!(number(statement, i)?, number(statement, i+1)?, number(statement, i+2)?))
+}
+
+fn color(statement: &Statement, i: usize) -> BuildResult<Color> {
+    Ok(Color::new(number(statement, i)?, number(statement, i+1)?, number(statement, i+2)?))
+}
+
+fn axis(statement: &Statement, i: usize) -> BuildResult<Axis> {
+    match ident(statement, i)?.as_str() {
+        "x" => Ok(Axis::X),
+        "y" => Ok(Axis::Y),
+        "z" => Ok(Axis::Z),
+        _ => error(arg_position(statement, i), "expected an axis: x, y or z"),
+    }
+}
+
+fn tone_map_operator(statement: &Statement, i: usize) -> BuildResult<ToneMapOperator> {
+    match ToneMapOperator::from_name(&ident(statement, i)?) {
+        Some(operator) => Ok(operator),
+        None => error(arg_position(statement, i), "expected a tone map: clamp, reinhard or aces"),
+    }
+}
+
+fn tile_order(statement: &Statement, i: usize) -> BuildResult<TileOrder> {
+    match TileOrder::from_name(&ident(statement, i)?) {
+        Some(order) => Ok(order),
+        None => error(arg_position(statement, i), "expected a tile order: scanline, spiral or hilbert"),
+    }
+}
+
+fn sampler_kind(statement: &Statement, i: usize) -> BuildResult<SamplerKind> {
+    match SamplerKind::from_name(&ident(statement, i)?) {
+        Some(kind) => Ok(kind),
+        None => error(arg_position(statement, i), "expected a sampler: independent, stratified, halton or sobol"),
+    }
+}
+
+fn cost_metric(statement: &Statement, i: usize) -> BuildResult<CostMetric> {
+    match CostMetric::from_name(&ident(statement, i)?) {
+        Some(metric) => Ok(metric),
+        None => error(arg_position(statement, i), "expected a heatmap: tests or time"),
+    }
+}
+
+fn color_encoding(statement: &Statement, i: usize) -> BuildResult<ColorEncoding> {
+    match ColorEncoding::from_name(&ident(statement, i)?) {
+        Some(encoding) => Ok(encoding),
+        None => error(arg_position(statement, i), "expected an encoding: srgb or linear"),
+    }
+}
+
+// The kind of thing a statement describes, eg. the 'point' in `light point {...}`
+fn kind(statement: &Statement) -> BuildResult<String> {
+    ident(statement, 0)
+}
+
+// Finds the last child statement with the given name
+fn property<'a>(statement: &'a Statement, name: &str) -> Option<&'a Statement> {
+    statement.children.iter().rev().find(|child| child.name == name)
+}
+
+// Reads a property made up of a single statement with `count` args
+fn read_property<T, F>(statement: &Statement, name: &str, count: usize, default: T, read: F) -> BuildResult<T>
+    where F: Fn(&Statement, usize) -> BuildResult<T>
+{
+    match property(statement, name) {
+        Some(prop) => {
+            expect_args(prop, count)?;
+            expect_no_children(prop)?;
+            read(prop, 0)
+        },
+        None => Ok(default),
+    }
+}
+
+fn required<'a>(statement: &'a Statement, name: &str) -> BuildResult<&'a Statement> {
+    match property(statement, name) {
+        Some(prop) => Ok(prop),
+        None => error(statement.position, &format!("'{}' is missing '{}'", statement.name, name)),
+    }
+}
+
+fn number_property(statement: &Statement, name: &str, default: f64) -> BuildResult<f64> {
+    read_property(statement, name, 1, default, number)
+}
+
+fn unsigned_property(statement: &Statement, name: &str, default: u32) -> BuildResult<u32> {
+    read_property(statement, name, 1, default, unsigned)
+}
+
+fn bool_property(statement: &Statement, name: &str, default: bool) -> BuildResult<bool> {
+    read_property(statement, name, 1, default, boolean)
+}
+
+fn vec2_property(statement: &Statement, name: &str, default: DVec2) -> BuildResult<DVec2> {
+    read_property(statement, name, 2, default, vec2)
+}
+
+fn vec3_property(statement: &Statement, name: &str, default: DVec3) -> BuildResult<DVec3> {
+    read_property(statement, name, 3, default, vec3)
+}
+
+fn color_property(statement: &Statement, name: &str, default: Color) -> BuildResult<Color> {
+    read_property(statement, name, 3, default, color)
+}
+
+fn falloff_property(statement: &Statement, default: (f64, f64, f64)) -> BuildResult<(f64, f64, f64)> {
+    let falloff = vec3_property(statement, "falloff", dvec3!(default.0, default.1, default.2))?;
+    Ok((falloff.x, falloff.y, falloff.z))
+}
+
+fn encoding_property(statement: &Statement, default: ColorEncoding) -> BuildResult<ColorEncoding> {
+    read_property(statement, "encoding", 1, default, color_encoding)
+}
+
+fn transform_property(statement: &Statement) -> BuildResult<DMat4> {
+    match property(statement, "transform") {
+        Some(transform) => build_transform(transform),
+        None => Ok(DMat4::identity()),
+    }
+}
+
+pub fn build_description(statements: &[Statement]) -> BuildResult<SceneDescription> {
+    let mut scene = Scene::new();
+    let mut camera = CameraConfig::default();
+    let mut image = ImageDimension::new(512, 512);
+    let mut render = RenderConfig::default();
+    let mut nodes: Vec<Box<dyn Traceable + Send + Sync>> = Vec::new();
+    let mut crop_position = None;
+
+    for statement in statements.iter() {
+        match statement.name.as_str() {
+            "image" => {
+                expect_args(statement, 2)?;
+                expect_no_children(statement)?;
+                image = ImageDimension::new(unsigned(statement, 0)?, unsigned(statement, 1)?);
+                if image.area() == 0 {
+                    return error(statement.position, "image must be at least 1x1");
+                }
+            },
+            "render" => {
+                render = build_render_config(statement)?;
+                crop_position = property(statement, "crop").map(|crop| crop.position);
+            },
+            "camera" => camera = build_camera(statement)?,
+            "ambient" => scene.ambient_light = build_ambient(statement)?,
+            "background" => scene.set_background(build_background(statement)?),
+            "light" => scene.add_light(build_light(statement)?),
+            "node" => nodes.push(build_node(statement)?),
+            _ => return unknown_statement(statement, "scene"),
+        }
+    }
+
+    // The crop can only be checked against the image once both have been read
+    if let (Some(crop), Some(position)) = (render.crop, crop_position) {
+        if crop.clip(image) != Some(crop) {
+            return error(position, &format!("crop has to fit inside the {}x{} image", image.width, image.height));
+        }
+    }
+
+    // Multiple top level nodes share an untransformed root
+    if nodes.len() == 1 {
+        scene.root = nodes.remove(0);
+    }
+    else {
+        let mut root = SceneNode::new();
+        root.add_children(nodes);
+        scene.root = Box::new(root);
+    }
+
+    Ok(SceneDescription { scene, camera, image, render })
+}
+
+fn build_render_config(statement: &Statement) -> BuildResult<RenderConfig> {
+    expect_args(statement, 0)?;
+    check_children(statement, &["threads", "tile_size", "tile_order", "checkpoint", "anti_alias", "aa_noise_threshold", "aa_min_samples", "aa_max_samples", "filter", "sampler", "seed", "recursion_depth", "crop", "integrator", "samples", "aovs", "tone_map", "exposure", "progressive", "denoise", "heatmap"])?;
+    let default = RenderConfig::default();
+    let config = RenderConfig {
+        num_threads: unsigned_property(statement, "threads", default.num_threads as u32)? as usize,
+        tile_size: unsigned_property(statement, "tile_size", default.tile_size)?,
+        tile_order: read_property(statement, "tile_order", 1, default.tile_order, tile_order)?,
+        checkpoint: read_property(statement, "checkpoint", 1, None, |prop, i| string(prop, i).map(Some))?,
+        anti_alias: bool_property(statement, "anti_alias", default.anti_alias)?,
+        aa_noise_threshold: number_property(statement, "aa_noise_threshold", default.aa_noise_threshold)?,
+        aa_min_samples: unsigned_property(statement, "aa_min_samples", default.aa_min_samples)?,
+        aa_max_samples: unsigned_property(statement, "aa_max_samples", default.aa_max_samples)?,
+        filter: match property(statement, "filter") {
+            Some(filter) => build_filter(filter)?,
+            None => default.filter,
+        },
+        sampler: read_property(statement, "sampler", 1, default.sampler, sampler_kind)?,
+        seed: unsigned_property(statement, "seed", default.seed)?,
+        recursion_depth: unsigned_property(statement, "recursion_depth", default.recursion_depth)?,
+        crop: read_property(statement, "crop", 4, None, |prop, i| Ok(Some(Tile::new(unsigned(prop, i)?, unsigned(prop, i + 1)?, unsigned(prop, i + 2)?, unsigned(prop, i + 3)?))))?,
+        integrator: match property(statement, "integrator") {
+            Some(integrator) => build_integrator(integrator)?,
+            None => default.integrator,
+        },
+        samples: unsigned_property(statement, "samples", default.samples)?,
+        aovs: aovs_property(statement)?,
+        tone_mapping: ToneMapping::new(read_property(statement, "tone_map", 1, default.tone_mapping.operator, tone_map_operator)?,
+                                       number_property(statement, "exposure", default.tone_mapping.exposure)?),
+        progressive: match property(statement, "progressive") {
+            Some(progressive) => Some(build_progressive(progressive)?),
+            None => None,
+        },
+        denoise: match property(statement, "denoise") {
+            Some(denoise) => Some(build_denoise(denoise)?),
+            None => None,
+        },
+        heatmap: read_property(statement, "heatmap", 1, None, |prop, i| cost_metric(prop, i).map(Some))?,
+    };
+    if let Err(message) = config.check() {
+        return error(statement.position, message);
+    }
+    Ok(config)
+}
+
+// eg. `progressive { samples 256; time_limit 60; noise_threshold 0.01 }`
+fn build_progressive(statement: &Statement) -> BuildResult<ProgressiveConfig> {
+    expect_args(statement, 0)?;
+    check_children(statement, &["samples", "samples_per_pass", "time_limit", "noise_threshold"])?;
+    let default = ProgressiveConfig::default();
+    let time_limit = read_property(statement, "time_limit", 1, None, |prop, i| number(prop, i).map(Some))?;
+    let noise_threshold = read_property(statement, "noise_threshold", 1, None, |prop, i| number(prop, i).map(Some))?;
+    if time_limit.is_some_and(|seconds| seconds <= 0.0) || noise_threshold.is_some_and(|noise| noise <= 0.0) {
+        return error(statement.position, "time_limit and noise_threshold must be greater than 0");
+    }
+    let config = ProgressiveConfig {
+        samples: unsigned_property(statement, "samples", default.samples)?,
+        samples_per_pass: unsigned_property(statement, "samples_per_pass", default.samples_per_pass)?,
+        time_limit: time_limit.map(Duration::from_secs_f64),
+        noise_threshold,
+    };
+    if config.samples == 0 || config.samples_per_pass == 0 {
+        return error(statement.position, "samples and samples_per_pass must be at least 1");
+    }
+    Ok(config)
+}
+
+// eg. `denoise { iterations 3; color_sigma 0.2 }`, or just `denoise {}` for the defaults
+fn build_denoise(statement: &Statement) -> BuildResult<DenoiseConfig> {
+    expect_args(statement, 0)?;
+    check_children(statement, &["iterations", "color_sigma", "normal_sigma", "depth_sigma", "albedo_sigma"])?;
+    let default = DenoiseConfig::default();
+    let config = DenoiseConfig {
+        iterations: unsigned_property(statement, "iterations", default.iterations)?,
+        color_sigma: number_property(statement, "color_sigma", default.color_sigma)?,
+        normal_sigma: number_property(statement, "normal_sigma", default.normal_sigma)?,
+        depth_sigma: number_property(statement, "depth_sigma", default.depth_sigma)?,
+        albedo_sigma: number_property(statement, "albedo_sigma", default.albedo_sigma)?,
+    };
+    if config.color_sigma <= 0.0 || config.normal_sigma <= 0.0 || config.depth_sigma <= 0.0 || config.albedo_sigma <= 0.0 {
+        return error(statement.position, "denoise sigmas must be greater than 0");
+    }
+    Ok(config)
+}
+
+// eg. `filter gaussian` or `filter mitchell 1.5`, the radius is in pixels
+fn build_filter(statement: &Statement) -> BuildResult<PixelFilter> {
+    expect_no_children(statement)?;
+    if statement.args.len() != 1 {
+        expect_args(statement, 2)?;
+    }
+    let kind = match FilterKind::from_name(&ident(statement, 0)?) {
+        Some(kind) => kind,
+        None => return error(arg_position(statement, 0), "expected a filter: box, tent, gaussian, mitchell or lanczos"),
+    };
+    if statement.args.len() == 2 {
+        let radius = number(statement, 1)?;
+        if radius <= 0.0 {
+            return error(arg_position(statement, 1), "filter needs a radius greater than 0");
+        }
+        return Ok(PixelFilter::new(kind, radius));
+    }
+    Ok(PixelFilter::from_kind(kind))
+}
+
+// eg. `aovs depth normal object_id`
+fn aovs_property(statement: &Statement) -> BuildResult<AovConfig> {
+    let mut aovs = AovConfig::none();
+    if let Some(prop) = property(statement, "aovs") {
+        expect_no_children(prop)?;
+        for i in 0..prop.args.len() {
+            match AovPass::from_name(&ident(prop, i)?) {
+                Some(pass) => aovs.set(pass, true),
+                None => return error(arg_position(prop, i), "expected an AOV pass: depth, normal, uv, albedo or object_id"),
+            }
+        }
+    }
+    Ok(aovs)
+}
+
+fn build_camera(statement: &Statement) -> BuildResult<CameraConfig> {
+    expect_args(statement, 0)?;
+    check_children(statement, &["origin", "target", "up", "fov_y"])?;
+    let default = CameraConfig::default();
+    Ok(CameraConfig {
+        origin: vec3_property(statement, "origin", default.origin)?,
+        target: vec3_property(statement, "target", default.target)?,
+        up: vec3_property(statement, "up", default.up)?,
+        fov_y: number_property(statement, "fov_y", default.fov_y)?,
+    })
+}
+
+fn build_ambient(statement: &Statement) -> BuildResult<AmbientLight> {
+    expect_args(statement, 0)?;
+    check_children(statement, &["color", "power"])?;
+    Ok(AmbientLight::new(color_property(statement, "color", Color::WHITE)?,
+                         number_property(statement, "power", 0.0)?))
+}
+
+fn build_background(statement: &Statement) -> BuildResult<SkyBox> {
+    expect_args(statement, 1)?;
+    check_children(statement, &["transform", "encoding"])?;
+    let texture = load_texture(statement, 0)?;
+    Ok(SkyBox::new(texture, transform_property(statement)?))
+}
+
+// eg. `integrator path`. Depth and ambient occlusion take an optional distance,
+// `integrator depth 500` to set where the depth view fades out, or the radius of ambient occlusion.
+fn build_integrator(statement: &Statement) -> BuildResult<Box<dyn Integrator + Send + Sync>> {
+    expect_no_children(statement)?;
+    let kind = kind(statement)?;
+    if (kind == "depth" || kind == "ambient_occlusion") && statement.args.len() == 2 {
+        let distance = number(statement, 1)?;
+        if distance <= 0.0 {
+            return error(arg_position(statement, 1), &format!("{} needs a distance greater than 0", kind));
+        }
+        if kind == "depth" {
+            return Ok(DebugIntegrator::with_far(distance));
+        }
+        return Ok(AmbientOcclusionIntegrator::new(distance));
+    }
+    expect_args(statement, 1)?;
+    match integrator_from_name(&kind) {
+        Some(integrator) => Ok(integrator),
+        None => error(arg_position(statement, 0), &format!("unknown integrator '{}'", kind)),
+    }
+}
+
+fn build_light(statement: &Statement) -> BuildResult<Box<dyn Lightable + Send + Sync>> {
+    expect_args(statement, 1)?;
+    match kind(statement)?.as_str() {
+        "point" => {
+            check_children(statement, &["position", "color", "power", "falloff"])?;
+            Ok(Box::new(PointLight::new(vec3_property(statement, "position", dvec3!(0.0, 0.0, 0.0))?,
+                                        color_property(statement, "color", Color::WHITE)?,
+                                        number_property(statement, "power", 1.0)?,
+                                        falloff_property(statement, (1.0, 0.0, 0.0))?)))
+        },
+        "square" => {
+            check_children(statement, &["position", "size", "color", "power", "falloff"])?;
+            Ok(SquareLight::new(vec3_property(statement, "position", dvec3!(0.0, 0.0, 0.0))?,
+                                number_property(statement, "size", 1.0)?,
+                                color_property(statement, "color", Color::WHITE)?,
+                                number_property(statement, "power", 1.0)?,
+                                falloff_property(statement, (1.0, 0.0, 0.0))?))
+        },
+        "direction" => {
+            check_children(statement, &["direction", "color", "power"])?;
+            Ok(Box::new(DirectionLight::new(vec3_property(statement, "direction", dvec3!(0.0, -1.0, 0.0))?,
+                                            color_property(statement, "color", Color::WHITE)?,
+                                            number_property(statement, "power", 1.0)?)))
+        },
+        other => error(arg_position(statement, 0), &format!("unknown light '{}'", other)),
+    }
+}
+
+fn build_node(statement: &Statement) -> BuildResult<Box<dyn Traceable + Send + Sync>> {
+    expect_args(statement, 0)?;
+    let mut node = SceneNode::new();
+    for child in statement.children.iter() {
+        match child.name.as_str() {
+            "transform" => node.set_transform(build_transform(child)?),
+            "primitive" => node.set_primitive(build_primitive(child, false)?),
+            "material" => node.set_material(build_material(child)?),
+            "node" => node.add_child(build_node(child)?),
+            _ => return unknown_statement(child, "'node'"),
+        }
+    }
+    Ok(Box::new(node))
+}
+
+fn build_transform(statement: &Statement) -> BuildResult<DMat4> {
+    expect_args(statement, 0)?;
+    let mut transform = DMat4::identity();
+    for child in statement.children.iter() {
+        expect_no_children(child)?;
+        let matrix = match child.name.as_str() {
+            "translate" => {
+                expect_args(child, 3)?;
+                let v = vec3(child, 0)?;
+                translation(v.x, v.y, v.z)
+            },
+            "scale" => {
+                // Either uniform or per axis scaling
+                if child.args.len() == 1 {
+                    let s = number(child, 0)?;
+                    scaling(s, s, s)
+                }
+                else {
+                    expect_args(child, 3)?;
+                    let v = vec3(child, 0)?;
+                    scaling(v.x, v.y, v.z)
+                }
+            },
+            "rotate" => {
+                expect_args(child, 2)?;
+                rotation(axis(child, 0)?, number(child, 1)?)
+            },
+            "reflect" => {
+                expect_args(child, 1)?;
+                reflection(axis(child, 0)?)
+            },
+            "matrix" => {
+                expect_args(child, 16)?;
+                let mut m = [0.0; 16];
+                for (i, value) in m.iter_mut().enumerate() {
+                    *value = number(child, i)?;
+                }
+                DMat4::new(m[0], m[1], m[2], m[3],
+                           m[4], m[5], m[6], m[7],
+                           m[8], m[9], m[10], m[11],
+                           m[12], m[13], m[14], m[15])
+            },
+            _ => return unknown_statement(child, "'transform'"),
+        };
+        transform = transform * matrix;
+    }
+    Ok(transform)
+}
+
+fn build_vertices(statement: &Statement) -> BuildResult<Vec<DVec3>> {
+    check_children(statement, &["vertex"])?;
+    let mut vertices: Vec<DVec3> = Vec::with_capacity(statement.children.len());
+    for vertex in statement.children.iter() {
+        expect_args(vertex, 3)?;
+        expect_no_children(vertex)?;
+        vertices.push(vec3(vertex, 0)?);
+    }
+    Ok(vertices)
+}
+
+fn build_plane(statement: &Statement) -> BuildResult<Box<Plane>> {
+    check_children(statement, &["origin", "normal", "tangent", "surface_scale"])?;
+    let origin = vec3_property(statement, "origin", dvec3!(0.0, 0.0, 0.0))?;
+    let normal = vec3_property(statement, "normal", dvec3!(0.0, 0.0, 1.0))?;
+    let surface_scale = vec2_property(statement, "surface_scale", dvec2!(1.0, 1.0))?;
+    if normal.length() == 0.0 {
+        return error(statement.position, "plane normal can't be zero");
+    }
+    match property(statement, "tangent") {
+        Some(_) => {
+            let tangent = vec3_property(statement, "tangent", UP)?;
+            if normal.cross(tangent).length() <= 1e-9 * normal.length() * tangent.length() {
+                return error(statement.position, "plane tangent can't be parallel to its normal");
+            }
+            Ok(Plane::with_surface_scale(origin, normal, tangent, surface_scale))
+        },
+        None => {
+            let plane = Plane::new(origin, normal);
+            Ok(Plane::with_surface_scale(plane.origin, plane.normal, plane.tangent, surface_scale))
+        },
+    }
+}
+
+// Composite shapes can also have a transform of their own
+fn is_composite(kind: &str) -> bool {
+    matches!(kind, "subtract" | "or" | "xor" | "and" | "multi_and")
+}
+
+// `in_shape` is set when the primitive is an operand of a composite shape,
+// in which case it may also carry a transform
+fn build_primitive(statement: &Statement, in_shape: bool) -> BuildResult<Box<dyn Intersectable + Send + Sync>> {
+    let kind = kind(statement)?;
+    if !in_shape && !is_composite(&kind) && property(statement, "transform").is_some() {
+        return error(property(statement, "transform").unwrap().position,
+                     "only composite shapes and their operands can have a transform, put it on the node instead");
+    }
+    match kind.as_str() {
+        "sphere" | "cube" | "tetrahedron" | "octahedron" => {
+            expect_args(statement, 2)?;
+            check_children(statement, &["transform"])?;
+            let size = number(statement, 1)?;
+            let primitive: Box<dyn Intersectable + Send + Sync> = match kind.as_str() {
+                "sphere" => Sphere::from_radius(size),
+                "cube" => Cube::new(size),
+                "tetrahedron" => Tetrahedron::new(size),
+                _ => Polyhedron::octahedron(size),
+            };
+            Ok(primitive)
+        },
+        "rectangle" => {
+            expect_args(statement, 3)?;
+            check_children(statement, &["transform"])?;
+            Ok(Rectangle::new(number(statement, 1)?, number(statement, 2)?))
+        },
+        "triangle" => {
+            expect_args(statement, 1)?;
+            let vertices = build_vertices(&without_transform(statement))?;
+            if vertices.len() != 3 {
+                return error(statement.position, "triangle needs exactly 3 vertices");
+            }
+            Ok(Triangle::from_vertices(vertices[0], vertices[1], vertices[2]))
+        },
+        "polygon" => {
+            expect_args(statement, 1)?;
+            let vertices = build_vertices(&without_transform(statement))?;
+            if vertices.len() < 3 {
+                return error(statement.position, "polygon needs at least 3 vertices");
+            }
+            Ok(Polygon::from_vertices(vertices))
+        },
+        "plane" => {
+            expect_args(statement, 1)?;
+            Ok(build_plane(&without_transform(statement))?)
+        },
+        "polyhedron" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["plane", "transform"])?;
+            let mut planes: Vec<Plane> = Vec::new();
+            for plane in statement.children.iter().filter(|child| child.name == "plane") {
+                expect_args(plane, 0)?;
+                planes.push(*build_plane(plane)?);
+            }
+            if planes.is_empty() {
+                return error(statement.position, "polyhedron needs at least one plane");
+            }
+            Ok(Polyhedron::from_planes(planes))
+        },
+        "mesh" => {
+            expect_args(statement, 2)?;
+            check_children(statement, &["transform"])?;
+            let path = string(statement, 1)?;
+            Ok(asset(statement, 1, Mesh::from_path(Path::new(&path)))?)
+        },
+        "one_way" | "inverted" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["primitive", "transform"])?;
+            let inner = build_primitive(required(statement, "primitive")?, false)?;
+            if kind == "one_way" {
+                Ok(Box::new(OneWay::new(inner)))
+            }
+            else {
+                Ok(Box::new(Inverted::new(inner)))
+            }
+        },
+        "subtract" => Ok(Box::new(build_subtract(statement)?)),
+        "or" => Ok(Box::new(build_or(statement)?)),
+        "xor" => Ok(Box::new(build_xor(statement)?)),
+        "and" => Ok(Box::new(build_and(statement)?)),
+        "multi_and" => Ok(Box::new(build_multi_and(statement)?)),
+        other => error(arg_position(statement, 0), &format!("unknown primitive '{}'", other)),
+    }
+}
+
+// Copy of a statement with any transforms removed, for properties that are lists of children
+fn without_transform(statement: &Statement) -> Statement {
+    let mut statement = statement.clone();
+    statement.children.retain(|child| child.name != "transform");
+    statement
+}
+
+// An operand of a composite shape, either another composite shape or a transformed primitive
+fn build_shape(statement: &Statement) -> BuildResult<Box<dyn Compositable + Send + Sync>> {
+    match kind(statement)?.as_str() {
+        "subtract" => Ok(Box::new(build_subtract(statement)?)),
+        "or" => Ok(Box::new(build_or(statement)?)),
+        "xor" => Ok(Box::new(build_xor(statement)?)),
+        "and" => Ok(Box::new(build_and(statement)?)),
+        "multi_and" => Ok(Box::new(build_multi_and(statement)?)),
+        _ => {
+            let transform = transform_property(statement)?;
+            Ok(Box::new(BaseShape::new(transform, build_primitive(statement, true)?)))
+        },
+    }
+}
+
+fn build_operands(statement: &Statement) -> BuildResult<Vec<Box<dyn Compositable + Send + Sync>>> {
+    expect_args(statement, 1)?;
+    check_children(statement, &["shape", "transform"])?;
+    statement.children.iter()
+                      .filter(|child| child.name == "shape")
+                      .map(build_shape)
+                      .collect()
+}
+
+fn build_two_operands(statement: &Statement) -> BuildResult<(Box<dyn Compositable + Send + Sync>, Box<dyn Compositable + Send + Sync>)> {
+    let mut operands = build_operands(statement)?;
+    if operands.len() != 2 {
+        return error(statement.position, &format!("'{}' needs exactly 2 shapes, found {}", kind(statement)?, operands.len()));
+    }
+    let second = operands.pop().unwrap();
+    let first = operands.pop().unwrap();
+    Ok((first, second))
+}
+
+fn build_subtract(statement: &Statement) -> BuildResult<SubtractShape> {
+    let (positive, negative) = build_two_operands(statement)?;
+    let mut shape = SubtractShape::new(positive, negative);
+    shape.set_transform(transform_property(statement)?);
+    Ok(shape)
+}
+
+fn build_or(statement: &Statement) -> BuildResult<OrShape> {
+    let (primary, secondary) = build_two_operands(statement)?;
+    let mut shape = OrShape::new(primary, secondary);
+    shape.set_transform(transform_property(statement)?);
+    Ok(shape)
+}
+
+fn build_xor(statement: &Statement) -> BuildResult<XorShape> {
+    let (primary, secondary) = build_two_operands(statement)?;
+    let mut shape = XorShape::new(primary, secondary);
+    shape.set_transform(transform_property(statement)?);
+    Ok(shape)
+}
+
+fn build_and(statement: &Statement) -> BuildResult<AndShape> {
+    let (primary, secondary) = build_two_operands(statement)?;
+    let mut shape = AndShape::new(primary, secondary);
+    shape.set_transform(transform_property(statement)?);
+    Ok(shape)
+}
+
+fn build_multi_and(statement: &Statement) -> BuildResult<MultiAndShape> {
+    let shapes = build_operands(statement)?;
+    if shapes.is_empty() {
+        return error(statement.position, "'multi_and' needs at least one shape");
+    }
+    let mut shape = MultiAndShape::from_vec(shapes);
+    shape.set_transform(transform_property(statement)?);
+    Ok(shape)
+}
+
+// Asset errors already say which file was the problem, this adds where in the scene file it came from
+fn asset<T>(statement: &Statement, i: usize, loaded: Result<T, Error>) -> BuildResult<T> {
+    loaded.or_else(|err| error(arg_position(statement, i), &err.to_string()))
+}
+
+fn load_texture(statement: &Statement, i: usize) -> BuildResult<Box<dyn TextureMappable + Send + Sync>> {
+    let mut texture = asset(statement, i, ImageTexture::from_path(&string(statement, i)?))?;
+    texture.encoding = encoding_property(statement, ColorEncoding::Srgb)?;
+    Ok(texture)
+}
+
+fn load_normal_map(statement: &Statement, i: usize) -> BuildResult<Box<dyn NormalMappable + Send + Sync>> {
+    let mut normal_map = asset(statement, i, NormalMap::from_path(&string(statement, i)?))?;
+    normal_map.encoding = encoding_property(statement, ColorEncoding::Linear)?;
+    Ok(normal_map)
+}
+
+fn load_bump_map(statement: &Statement, i: usize, depth: f64) -> BuildResult<Box<dyn NormalMappable + Send + Sync>> {
+    let mut bump_map = asset(statement, i, BumpMap::from_path(&string(statement, i)?, depth))?;
+    bump_map.encoding = encoding_property(statement, ColorEncoding::Linear)?;
+    Ok(bump_map)
+}
+
+fn build_materials(statement: &Statement) -> BuildResult<Vec<Box<dyn Shadable + Send + Sync>>> {
+    check_children(statement, &["material"])?;
+    statement.children.iter().map(build_material).collect()
+}
+
+fn build_material(statement: &Statement) -> BuildResult<Box<dyn Shadable + Send + Sync>> {
+    match kind(statement)?.as_str() {
+        "phong" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["diffuse", "specular", "ambient", "shininess"])?;
+            Ok(PhongShader::new(color_property(statement, "diffuse", Color::WHITE*0.5)?,
+                                color_property(statement, "specular", Color::WHITE*0.5)?,
+                                color_property(statement, "ambient", Color::WHITE*0.1)?,
+                                number_property(statement, "shininess", 1.0)?))
+        },
+        "reflection" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["color"])?;
+            Ok(ReflectionShader::new(color_property(statement, "color", Color::WHITE)?))
+        },
+        "translucent" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["translucency", "refractive_index"])?;
+            Ok(TranslucentShader::new(color_property(statement, "translucency", Color::WHITE)?,
+                                      number_property(statement, "refractive_index", 1.0)?))
+        },
+        "texture" => {
+            expect_args(statement, 2)?;
+            check_children(statement, &["encoding"])?;
+            Ok(TextureShader::new(load_texture(statement, 1)?))
+        },
+        "normal_map" => {
+            expect_args(statement, 2)?;
+            check_children(statement, &["encoding"])?;
+            Ok(NormalMapShader::new(load_normal_map(statement, 1)?))
+        },
+        "bump_map" => {
+            expect_args(statement, 3)?;
+            check_children(statement, &["encoding"])?;
+            let depth = number(statement, 2)?;
+            Ok(NormalMapShader::new(load_bump_map(statement, 1, depth)?))
+        },
+        "ambient_occlusion" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["ambient", "radius", "samples"])?;
+            Ok(AmbientOcclusionShader::new(color_property(statement, "ambient", Color::WHITE*0.1)?,
+                                           number_property(statement, "radius", AmbientOcclusionShader::RADIUS)?,
+                                           unsigned_property(statement, "samples", AmbientOcclusionShader::SAMPLES)?))
+        },
+        "mix" => {
+            expect_args(statement, 1)?;
+            let shaders = build_materials(statement)?;
+            if shaders.is_empty() {
+                return error(statement.position, "'mix' needs at least one material");
+            }
+            Ok(MixShader::from_shaders(shaders))
+        },
+        "chain" => {
+            expect_args(statement, 1)?;
+            let shaders = build_materials(statement)?;
+            if shaders.is_empty() {
+                return error(statement.position, "'chain' needs at least one material");
+            }
+            Ok(ChainShader::from_shaders(shaders))
+        },
+        "composite" => {
+            expect_args(statement, 1)?;
+            check_children(statement, &["layer"])?;
+            let mut composite = CompositeShader::new();
+            for layer in statement.children.iter() {
+                expect_args(layer, 1)?;
+                check_children(layer, &["material"])?;
+                if layer.children.len() != 1 {
+                    return error(layer.position, "'layer' needs exactly one material");
+                }
+                composite.add_shader(number(layer, 0)?, build_material(&layer.children[0])?);
+            }
+            Ok(composite)
+        },
+        other => error(arg_position(statement, 0), &format!("unknown material '{}'", other)),
+    }
+}
@@ -0,0 +1,222 @@
+use super::SceneFileError;
+
+// Where something was found in the source text, both 1-based
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Str(String),
+    Ident(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub value: Value,
+    pub position: Position,
+}
+
+// A single line of a scene file: a name, some arguments and an optional block of children
+// eg. `light point { position 0 100 0 }`
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub children: Vec<Statement>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    OpenBrace,
+    CloseBrace,
+    Separator,
+    End,
+}
+
+fn describe_token(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Number(num) => format!("number {}", num),
+        Token::Str(string) => format!("string \"{}\"", string),
+        Token::OpenBrace => String::from("'{'"),
+        Token::CloseBrace => String::from("'}'"),
+        Token::Separator => String::from("end of statement"),
+        Token::End => String::from("end of file"),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_number_start(c: char) -> bool {
+    c.is_ascii_digit() || c == '-' || c == '+' || c == '.'
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, Position)>, SceneFileError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<(Token, Position)> = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let position = Position { line, column: i - line_start + 1 };
+
+        if c == '\n' {
+            tokens.push((Token::Separator, position));
+            i += 1;
+            line += 1;
+            line_start = i;
+        }
+        else if c.is_whitespace() {
+            i += 1;
+        }
+        else if c == '#' {
+            // Comments run until the end of the line
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        }
+        else if c == ';' {
+            tokens.push((Token::Separator, position));
+            i += 1;
+        }
+        else if c == '{' {
+            tokens.push((Token::OpenBrace, position));
+            i += 1;
+        }
+        else if c == '}' {
+            tokens.push((Token::CloseBrace, position));
+            i += 1;
+        }
+        else if c == '"' {
+            let mut string = String::new();
+            i += 1;
+            loop {
+                if i >= chars.len() || chars[i] == '\n' {
+                    return Err(SceneFileError::invalid(position, "unterminated string"));
+                }
+                match chars[i] {
+                    '"' => break,
+                    '\\' if i + 1 < chars.len() => {
+                        i += 1;
+                        match chars[i] {
+                            'n' => string.push('\n'),
+                            't' => string.push('\t'),
+                            '"' => string.push('"'),
+                            '\\' => string.push('\\'),
+                            other => return Err(SceneFileError::invalid(
+                                Position { line, column: i - line_start + 1 },
+                                &format!("unknown escape sequence '\\{}'", other))),
+                        }
+                    },
+                    other => string.push(other),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((Token::Str(string), position));
+        }
+        else if is_number_start(c) {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == 'e' || chars[i] == 'E' ||
+                                      ((chars[i] == '-' || chars[i] == '+') && (chars[i-1] == 'e' || chars[i-1] == 'E'))) {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse::<f64>() {
+                Ok(num) => tokens.push((Token::Number(num), position)),
+                Err(_) => return Err(SceneFileError::invalid(position, &format!("invalid number '{}'", text))),
+            }
+        }
+        else if is_ident_start(c) {
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), position));
+        }
+        else {
+            return Err(SceneFileError::invalid(position, &format!("unexpected character '{}'", c)));
+        }
+    }
+
+    tokens.push((Token::End, Position { line, column: chars.len() - line_start + 1 }));
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, Position)>,
+    current: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &(Token, Position) {
+        &self.tokens[self.current]
+    }
+
+    fn advance(&mut self) -> (Token, Position) {
+        let token = self.tokens[self.current].clone();
+        if token.0 != Token::End {
+            self.current += 1;
+        }
+        token
+    }
+
+    // Parses statements until the end of the file, or the closing brace of a block
+    fn parse_statements(&mut self, in_block: bool) -> Result<Vec<Statement>, SceneFileError> {
+        let mut statements: Vec<Statement> = Vec::new();
+        loop {
+            let (token, position) = self.advance();
+            match token {
+                Token::Separator => continue,
+                Token::Ident(name) => statements.push(self.parse_statement(name, position)?),
+                Token::End if !in_block => return Ok(statements),
+                Token::CloseBrace if in_block => return Ok(statements),
+                Token::End => return Err(SceneFileError::invalid(position, "expected '}' before end of file")),
+                other => return Err(SceneFileError::invalid(position, &format!("expected a statement, found {}", describe_token(&other)))),
+            }
+        }
+    }
+
+    fn parse_statement(&mut self, name: String, position: Position) -> Result<Statement, SceneFileError> {
+        let mut statement = Statement { name, args: Vec::new(), children: Vec::new(), position };
+        loop {
+            let (token, position) = self.peek().clone();
+            match token {
+                Token::Number(num) => statement.args.push(Arg { value: Value::Number(num), position }),
+                Token::Str(string) => statement.args.push(Arg { value: Value::Str(string), position }),
+                Token::Ident(ident) => statement.args.push(Arg { value: Value::Ident(ident), position }),
+                Token::OpenBrace => {
+                    self.advance();
+                    statement.children = self.parse_statements(true)?;
+                    return Ok(statement);
+                },
+                // Let the enclosing block deal with these
+                Token::Separator | Token::CloseBrace | Token::End => return Ok(statement),
+            }
+            self.advance();
+        }
+    }
+}
+
+// Turns scene file text into a tree of statements
+pub fn parse(source: &str) -> Result<Vec<Statement>, SceneFileError> {
+    let mut parser = Parser { tokens: tokenize(source)?, current: 0 };
+    parser.parse_statements(false)
+}
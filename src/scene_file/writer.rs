@@ -0,0 +1,134 @@
+use euler::{DVec2, DVec3, DMat4};
+use color::{Color, ColorEncoding};
+use super::parser::{Statement, Arg, Value, Position};
+
+// Convenience constructors for describing scene objects as statements
+impl Statement {
+    pub fn new(name: &str) -> Statement {
+        Statement {
+            name: String::from(name),
+            args: Vec::new(),
+            children: Vec::new(),
+            position: Position { line: 0, column: 0 },
+        }
+    }
+
+    // Statement for something with a kind, eg. `material phong`
+    pub fn of_kind(name: &str, kind: &str) -> Statement {
+        Statement::new(name).with_ident(kind)
+    }
+
+    pub fn renamed(mut self, name: &str) -> Statement {
+        self.name = String::from(name);
+        self
+    }
+
+    fn with_arg(mut self, value: Value) -> Statement {
+        self.args.push(Arg { value, position: Position { line: 0, column: 0 } });
+        self
+    }
+
+    pub fn with_number(self, num: f64) -> Statement {
+        self.with_arg(Value::Number(num))
+    }
+
+    pub fn with_string(self, string: &str) -> Statement {
+        self.with_arg(Value::Str(String::from(string)))
+    }
+
+    pub fn with_ident(self, ident: &str) -> Statement {
+        self.with_arg(Value::Ident(String::from(ident)))
+    }
+
+    pub fn with_vec2(self, v: DVec2) -> Statement {
+        self.with_number(v.x).with_number(v.y)
+    }
+
+    pub fn with_vec3(self, v: DVec3) -> Statement {
+        self.with_number(v.x).with_number(v.y).with_number(v.z)
+    }
+
+    pub fn with_color(self, color: Color) -> Statement {
+        self.with_number(color.red).with_number(color.green).with_number(color.blue)
+    }
+
+    // Adds an `encoding` child when it isn't what the image would normally use
+    pub fn with_encoding(self, encoding: ColorEncoding, default: ColorEncoding) -> Statement {
+        if encoding == default {
+            self
+        }
+        else {
+            self.with_child(Statement::new("encoding").with_ident(encoding.name()))
+        }
+    }
+
+    pub fn with_child(mut self, child: Statement) -> Statement {
+        self.children.push(child);
+        self
+    }
+
+    pub fn with_children(mut self, children: Vec<Statement>) -> Statement {
+        self.children.extend(children);
+        self
+    }
+
+    // Adds a transform block, unless the transform does nothing
+    pub fn with_transform(self, matrix: DMat4) -> Statement {
+        if matrix == DMat4::identity() {
+            return self;
+        }
+        let m = matrix;
+        let values = [m.m00, m.m01, m.m02, m.m03,
+                      m.m10, m.m11, m.m12, m.m13,
+                      m.m20, m.m21, m.m22, m.m23,
+                      m.m30, m.m31, m.m32, m.m33];
+        let matrix = values.iter().fold(Statement::new("matrix"), |statement, value| statement.with_number(*value));
+        self.with_child(Statement::new("transform").with_child(matrix))
+    }
+}
+
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_statement(statement: &Statement, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    out.push_str(&indent);
+    out.push_str(&statement.name);
+    for arg in statement.args.iter() {
+        out.push(' ');
+        match arg.value {
+            Value::Number(num) => out.push_str(&format!("{}", num)),
+            Value::Str(ref string) => write_string(string, out),
+            Value::Ident(ref ident) => out.push_str(ident),
+        }
+    }
+    if !statement.children.is_empty() {
+        out.push_str(" {\n");
+        for child in statement.children.iter() {
+            write_statement(child, depth + 1, out);
+        }
+        out.push_str(&indent);
+        out.push('}');
+    }
+    out.push('\n');
+}
+
+// Turns statements back into scene file text
+pub fn write(statements: &[Statement]) -> String {
+    let mut out = String::new();
+    for statement in statements.iter() {
+        write_statement(statement, 0, &mut out);
+    }
+    out
+}
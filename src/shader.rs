@@ -6,16 +6,20 @@ use geometry::{Intersect, Ray};
 use std::collections::HashMap;
 use snowflake::ProcessUniqueId;
 use light::Illum;
+use scene_file::{Statement, SceneFileError};
+use sampler::sample_1d;
 
 pub mod phong;
 pub mod texture;
 pub mod reflection;
 pub mod translucent;
+pub mod ambient_occlusion;
 
 pub use self::phong::PhongShader;
 pub use self::texture::TextureShader;
 pub use self::reflection::ReflectionShader;
 pub use self::translucent::TranslucentShader;
+pub use self::ambient_occlusion::{AmbientOcclusionShader, ambient_visibility};
 
 pub trait Shadable: ShadableClone {
     fn get_color(&self, scene: &Scene, intersect: Intersect) -> Color;
@@ -24,9 +28,27 @@ pub trait Shadable: ShadableClone {
         Color::WHITE
     }
 
+    // Color of the surface when path tracing, where indirect light comes from following
+    // one random bounce instead of ambient light. Shaders that don't bounce light around
+    // look the same either way.
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        self.get_color(scene, intersect)
+    }
+
     fn modify_intersect(&self, _: &Scene, intersect: Intersect) -> Intersect {
         intersect
     }
+
+    // Base color of the surface without any lighting, for the albedo pass.
+    // Shaders that only redirect light, like glass, count as white.
+    fn get_albedo(&self, _: &Scene, _: Intersect) -> Color {
+        Color::WHITE
+    }
+
+    // Describes the shader as a `material` statement for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("material has no scene file description"))
+    }
 }
 
 pub trait ShadableClone {
@@ -68,9 +90,22 @@ impl Shadable for MixShader {
         self.shaders.iter().fold(Color::WHITE, |acc, x| acc*x.get_color(&scene, intersect.contributes(acc)))
     }
 
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        self.shaders.iter().fold(Color::WHITE, |acc, x| acc*x.get_path_color(scene, intersect.contributes(acc)))
+    }
+
     fn get_opacity(&self) -> Color {
         self.shaders.iter().fold(Color::WHITE, |acc, x| acc*x.get_opacity())
     }
+
+    fn get_albedo(&self, scene: &Scene, intersect: Intersect) -> Color {
+        self.shaders.iter().fold(Color::WHITE, |acc, x| acc*x.get_albedo(scene, intersect))
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let shaders = self.shaders.iter().map(|shader| shader.to_statement()).collect::<Result<Vec<Statement>, SceneFileError>>()?;
+        Ok(Statement::of_kind("material", "mix").with_children(shaders))
+    }
 }
 
 #[derive(Clone)]
@@ -101,6 +136,20 @@ impl Shadable for CompositeShader {
         total_color
     }
 
+    // Following every layer would split the path at each bounce, so only one layer is
+    // picked, with a chance matching its weight. On average that comes out to the same color.
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let total_weight: f64 = self.shaders.iter().map(|(weight, _)| *weight).sum();
+        let mut choice = sample_1d() * total_weight.max(f64::MIN_POSITIVE);
+        for (weight, shader) in self.shaders.iter() {
+            if choice < *weight {
+                return total_weight * shader.get_path_color(scene, intersect.contributes(total_weight*Color::WHITE));
+            }
+            choice -= *weight;
+        }
+        Color::BLACK
+    }
+
     fn get_opacity(&self) -> Color {
         let mut total_opacity = Color::BLACK; 
         for (weight, shader) in self.shaders.iter() {
@@ -108,6 +157,22 @@ impl Shadable for CompositeShader {
         }
         total_opacity
     }
+
+    fn get_albedo(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let mut total_albedo = Color::BLACK;
+        for (weight, shader) in self.shaders.iter() {
+            total_albedo += *weight * shader.get_albedo(scene, intersect);
+        }
+        total_albedo
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let mut statement = Statement::of_kind("material", "composite");
+        for (weight, shader) in self.shaders.iter() {
+            statement = statement.with_child(Statement::new("layer").with_number(*weight).with_child(shader.to_statement()?));
+        }
+        Ok(statement)
+    }
 }
 
 #[derive(Clone)]
@@ -143,6 +208,17 @@ impl Shadable for ChainShader {
         }
     }
 
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let intersect = self.modify_intersect(scene, intersect);
+
+        if let Some(shader) = self.shaders.last() {
+            shader.get_path_color(scene, intersect)
+        }
+        else {
+            panic!("Chain Shader needs at least one shader to function")
+        }
+    }
+
     fn modify_intersect(&self, scene: &Scene, intersect: Intersect) -> Intersect {
         let mut cur_intersect = intersect;
         for shader in self.shaders.iter() {
@@ -150,6 +226,22 @@ impl Shadable for ChainShader {
         }
         cur_intersect
     } 
+
+    fn get_albedo(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let intersect = self.modify_intersect(scene, intersect);
+
+        if let Some(shader) = self.shaders.last() {
+            shader.get_albedo(scene, intersect)
+        }
+        else {
+            panic!("Chain Shader needs at least one shader to function")
+        }
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let shaders = self.shaders.iter().map(|shader| shader.to_statement()).collect::<Result<Vec<Statement>, SceneFileError>>()?;
+        Ok(Statement::of_kind("material", "chain").with_children(shaders))
+    }
 }
 
 #[derive(Clone)]
@@ -179,4 +271,13 @@ impl Shadable for NormalMapShader {
         assert!(intersect.surface_normal.length() - 1.0 < 0.0001, "normal post: {}", intersect.surface_normal);
         intersect
     }
+
+    // The normal map describes itself as eg. `normal_map "path"`
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let normal_map = self.normal_map.to_statement()?;
+        let mut statement = Statement::of_kind("material", &normal_map.name);
+        statement.args.extend(normal_map.args);
+        statement.children.extend(normal_map.children);
+        Ok(statement)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+use super::*;
+
+// Ambient light, darkened in creases and corners where nearby geometry blocks it.
+// Meant to be layered with a shader that has no ambient of its own, eg. in a CompositeShader.
+#[derive(Clone)]
+pub struct AmbientOcclusionShader {
+    ambient: Color,
+    radius: f64,
+    samples: u32,
+}
+
+impl AmbientOcclusionShader {
+    pub const RADIUS: f64 = 100.0;
+    pub const SAMPLES: u32 = 16;
+
+    pub fn new(ambient: Color, radius: f64, samples: u32) -> Box<AmbientOcclusionShader> {
+        Box::new(AmbientOcclusionShader{ambient, radius, samples})
+    }
+}
+
+// How much of the hemisphere above the surface is open, from 0.0 when fully boxed in to 1.0.
+// Only geometry within radius of the hit point blocks anything.
+pub fn ambient_visibility(scene: &Scene, intersect: Intersect, radius: f64, samples: u32) -> f64 {
+    if samples == 0 {
+        return 1.0;
+    }
+    // Probes only need to go one bounce, whatever depth the ray had left
+    let ray = Ray::new(intersect.ray.origin, intersect.ray.direction, 1);
+    let mut open = 0;
+    for _ in 0..samples {
+        let probe = ray.scatter_off(intersect.hit_point, intersect.surface_normal);
+        if scene.trace_shadow(probe, radius).is_none() {
+            open += 1;
+        }
+    }
+    open as f64 / samples as f64
+}
+
+impl Shadable for AmbientOcclusionShader {
+    fn get_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let ambient = self.ambient * scene.ambient_light.color_intensity();
+        if ambient == Color::BLACK {
+            return Color::BLACK;
+        }
+        ambient * ambient_visibility(scene, intersect, self.radius, self.samples)
+    }
+
+    // Indirect light already takes care of this when path tracing
+    fn get_path_color(&self, _: &Scene, _: Intersect) -> Color {
+        Color::BLACK
+    }
+
+    // Only adds light, so it doesn't change the color of whatever it's layered on
+    fn get_albedo(&self, _: &Scene, _: Intersect) -> Color {
+        Color::BLACK
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("material", "ambient_occlusion").with_children(vec!(
+            Statement::new("ambient").with_color(self.ambient),
+            Statement::new("radius").with_number(self.radius),
+            Statement::new("samples").with_number(self.samples as f64),
+        )))
+    }
+}
@@ -26,10 +26,11 @@ fn total_light_blocked(opacity: Color, enter: f64, exit: f64) -> Color {
     total_blocked
 }
 
-impl Shadable for PhongShader {
-    fn get_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+impl PhongShader {
+    // Light reaching the surface straight from the scene's lights
+    fn get_direct_color(&self, scene: &Scene, intersect: Intersect) -> Color {
         assert!(intersect.surface_normal.length() - 1.0 < 0.0001, "normal: {}", intersect.surface_normal);
-        let mut total_color = self.ambient * scene.ambient_light.color_intensity();
+        let mut total_color = Color::BLACK;
         for light in &scene.lights {
 
             let illums = light.get_illums_at(scene, intersect);
@@ -45,4 +46,47 @@ impl Shadable for PhongShader {
 
         total_color
     }
+}
+
+impl Shadable for PhongShader {
+    fn get_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        self.ambient * scene.ambient_light.color_intensity() + self.get_direct_color(scene, intersect)
+    }
+
+    // Ambient light is replaced by whatever one diffuse bounce runs into.
+    // Lights already fold the 1/pi of a matte surface into their intensity, so with
+    // cosine weighted bounces the indirect light is just weighted by the diffuse color.
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let mut total_color = self.get_direct_color(scene, intersect);
+        let ray = intersect.ray;
+        if ray.get_depth() <= 1 {
+            return total_color;
+        }
+
+        // Russian roulette, paths that can't add much are more likely to stop here.
+        // The ones that carry on make up for the others by being brighter.
+        let throughput = ray.contributes(self.diffuse).get_contribution() / 3.0;
+        let survival = throughput.min(1.0);
+        if survival <= 0.0 || sample_1d() >= survival {
+            return total_color;
+        }
+
+        let weight = self.diffuse / survival;
+        let bounce_ray = ray.scatter_off(intersect.hit_point, intersect.surface_normal).contributes(weight);
+        total_color += weight * scene.cast_path(bounce_ray);
+        total_color
+    }
+
+    fn get_albedo(&self, _: &Scene, _: Intersect) -> Color {
+        self.diffuse
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("material", "phong").with_children(vec!(
+            Statement::new("diffuse").with_color(self.diffuse),
+            Statement::new("specular").with_color(self.specular),
+            Statement::new("ambient").with_color(self.ambient),
+            Statement::new("shininess").with_number(self.shininess),
+        )))
+    }
 }
\ No newline at end of file
@@ -16,4 +16,17 @@ impl Shadable for ReflectionShader {
         let reflected_ray = intersect.ray.reflect_off(intersect.hit_point, intersect.surface_normal);
         self.reflectivity * scene.cast_ray(reflected_ray.contributes(self.reflectivity))
     }
+
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let reflected_ray = intersect.ray.reflect_off(intersect.hit_point, intersect.surface_normal);
+        self.reflectivity * scene.cast_path(reflected_ray.contributes(self.reflectivity))
+    }
+
+    fn get_albedo(&self, _: &Scene, _: Intersect) -> Color {
+        self.reflectivity
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("material", "reflection").with_child(Statement::new("color").with_color(self.reflectivity)))
+    }
 }
\ No newline at end of file
@@ -17,4 +17,16 @@ impl Shadable for TextureShader {
     fn get_color(&self, _: &Scene, intersect: Intersect) -> Color {
         self.texture.get_color(intersect.surface_coord)
     }
+
+    fn get_albedo(&self, _: &Scene, intersect: Intersect) -> Color {
+        self.texture.get_color(intersect.surface_coord)
+    }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        let texture = self.texture.to_statement()?;
+        let mut statement = Statement::of_kind("material", &texture.name);
+        statement.args.extend(texture.args);
+        statement.children.extend(texture.children);
+        Ok(statement)
+    }
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ use super::*;
 use geometry::Ray;
 use euler::DVec3;
 use color::*;
+use ray_tree::{Fresnel, record_fresnel};
 
 #[derive(Clone)]
 pub struct TranslucentShader {
@@ -50,6 +51,7 @@ impl Shadable for TranslucentShader {
     fn get_color(&self, scene: &Scene, intersect: Intersect) -> Color {
 
         let (kt, kr) = self.get_fresnel(&intersect);
+        record_fresnel(Fresnel { reflected: kr, transmitted: kt });
         let reflected_ray = intersect.ray.reflect_off(intersect.hit_point, intersect.surface_normal);
         let transmitted_ray = intersect.ray.transmit_through(intersect.hit_point, intersect.surface_normal, self.refractive_index);
 
@@ -61,7 +63,27 @@ impl Shadable for TranslucentShader {
         (kr * color_r) + (kt * color_t)
     }
 
+    // Follows either the reflected or the transmitted ray, picked by the fresnel ratio,
+    // so a path through glass doesn't split in two at every surface
+    fn get_path_color(&self, scene: &Scene, intersect: Intersect) -> Color {
+        let (kt, kr) = self.get_fresnel(&intersect);
+        record_fresnel(Fresnel { reflected: kr, transmitted: kt });
+        if sample_1d() < kr {
+            scene.cast_path(intersect.ray.reflect_off(intersect.hit_point, intersect.surface_normal))
+        }
+        else {
+            scene.cast_path(intersect.ray.transmit_through(intersect.hit_point, intersect.surface_normal, self.refractive_index))
+        }
+    }
+
     fn get_opacity(&self) -> Color {
         Color::WHITE - self.translucency
     }
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Ok(Statement::of_kind("material", "translucent").with_children(vec!(
+            Statement::new("translucency").with_color(self.translucency),
+            Statement::new("refractive_index").with_number(self.refractive_index),
+        )))
+    }
 }
\ No newline at end of file
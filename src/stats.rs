@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use geometry::RayKind;
+
+// What sort of primitive an intersection test was against, see Intersectable::kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrimitiveKind {
+    Sphere,
+    Cube,
+    Tetrahedron,
+    Plane,
+    Rectangle,
+    Triangle,
+    Polygon,
+    Polyhedron,
+    Mesh,
+    OneWay,
+    Inverted,
+    And,
+    MultiAnd,
+    Or,
+    Subtract,
+    Xor,
+    // Primitives that don't say what they are
+    Other,
+}
+
+impl PrimitiveKind {
+    pub const COUNT: usize = 17;
+
+    pub const ALL: [PrimitiveKind; PrimitiveKind::COUNT] = [
+        PrimitiveKind::Sphere, PrimitiveKind::Cube, PrimitiveKind::Tetrahedron, PrimitiveKind::Plane,
+        PrimitiveKind::Rectangle, PrimitiveKind::Triangle, PrimitiveKind::Polygon, PrimitiveKind::Polyhedron,
+        PrimitiveKind::Mesh, PrimitiveKind::OneWay, PrimitiveKind::Inverted, PrimitiveKind::And,
+        PrimitiveKind::MultiAnd, PrimitiveKind::Or, PrimitiveKind::Subtract, PrimitiveKind::Xor,
+        PrimitiveKind::Other,
+    ];
+
+    // The name the primitive has in scene files
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrimitiveKind::Sphere => "sphere",
+            PrimitiveKind::Cube => "cube",
+            PrimitiveKind::Tetrahedron => "tetrahedron",
+            PrimitiveKind::Plane => "plane",
+            PrimitiveKind::Rectangle => "rectangle",
+            PrimitiveKind::Triangle => "triangle",
+            PrimitiveKind::Polygon => "polygon",
+            PrimitiveKind::Polyhedron => "polyhedron",
+            PrimitiveKind::Mesh => "mesh",
+            PrimitiveKind::OneWay => "one_way",
+            PrimitiveKind::Inverted => "inverted",
+            PrimitiveKind::And => "and",
+            PrimitiveKind::MultiAnd => "multi_and",
+            PrimitiveKind::Or => "or",
+            PrimitiveKind::Subtract => "subtract",
+            PrimitiveKind::Xor => "xor",
+            PrimitiveKind::Other => "other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PrimitiveKind> {
+        PrimitiveKind::ALL.iter().find(|kind| kind.name() == name).cloned()
+    }
+
+    // ALL is in the same order the kinds are declared in
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+// Counts of the work a render did, to tell why a scene is slow.
+// Times under a phase are added up over every thread, so they can come to more than elapsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+    // Rays from the camera, one per sample
+    pub primary_rays: u64,
+    // Reflected, transmitted and scattered rays
+    pub secondary_rays: u64,
+    // Rays towards lights and ambient occlusion probes
+    pub shadow_rays: u64,
+    // Tests of rays against scene node primitives and the shapes inside composite shapes,
+    // see RenderStats::intersection_tests
+    pub intersection_test_counts: [u64; PrimitiveKind::COUNT],
+    // Nodes of mesh bounding trees a ray had to check
+    pub bvh_node_visits: u64,
+    // Triangles tested in the leaves of mesh bounding trees
+    pub mesh_triangle_tests: u64,
+    // Each pixel's first sample
+    pub trace_time: Duration,
+    // Extra samples for anti-aliasing, or for random integrators
+    pub anti_alias_time: Duration,
+    pub aov_time: Duration,
+    pub denoise_time: Duration,
+    // Wall clock time of the whole render
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    pub fn total_rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays + self.shadow_rays
+    }
+
+    pub fn intersection_tests(&self, kind: PrimitiveKind) -> u64 {
+        self.intersection_test_counts[kind.index()]
+    }
+
+    pub fn total_intersection_tests(&self) -> u64 {
+        self.intersection_test_counts.iter().sum()
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 { self.total_rays() as f64 / seconds } else { 0.0 }
+    }
+
+    // Adds other's counts and phase times to these, elapsed is left alone
+    pub fn add(&mut self, other: &RenderStats) {
+        self.primary_rays += other.primary_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.shadow_rays += other.shadow_rays;
+        for (count, other_count) in self.intersection_test_counts.iter_mut().zip(other.intersection_test_counts.iter()) {
+            *count += other_count;
+        }
+        self.bvh_node_visits += other.bvh_node_visits;
+        self.mesh_triangle_tests += other.mesh_triangle_tests;
+        self.trace_time += other.trace_time;
+        self.anti_alias_time += other.anti_alias_time;
+        self.aov_time += other.aov_time;
+        self.denoise_time += other.denoise_time;
+    }
+
+    // A few lines to print after a render
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        writeln!(report, "rays: {} primary, {} secondary, {} shadow, {:.0} per second",
+                 self.primary_rays, self.secondary_rays, self.shadow_rays, self.rays_per_second()).unwrap();
+        let tests: Vec<String> = PrimitiveKind::ALL.iter()
+            .filter(|kind| self.intersection_tests(**kind) > 0)
+            .map(|kind| format!("{} {}", kind.name(), self.intersection_tests(*kind)))
+            .collect();
+        writeln!(report, "intersection tests: {} ({})", self.total_intersection_tests(), tests.join(", ")).unwrap();
+        if self.bvh_node_visits > 0 {
+            writeln!(report, "mesh bounding tree: {} node visits, {} triangle tests", self.bvh_node_visits, self.mesh_triangle_tests).unwrap();
+        }
+        write!(report, "time: {:.3}s elapsed, thread time {:.3}s tracing, {:.3}s anti-aliasing, {:.3}s aovs",
+               self.elapsed.as_secs_f64(), self.trace_time.as_secs_f64(), self.anti_alias_time.as_secs_f64(), self.aov_time.as_secs_f64()).unwrap();
+        if self.denoise_time > Duration::default() {
+            write!(report, ", {:.3}s denoising", self.denoise_time.as_secs_f64()).unwrap();
+        }
+        report.push('\n');
+        report
+    }
+}
+
+// Parts of the render that are timed, see time_phase
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderPhase {
+    Trace,
+    AntiAlias,
+    Aov,
+}
+
+thread_local! {
+    static THREAD_STATS: RefCell<RenderStats> = RefCell::new(RenderStats::default());
+}
+
+// Counters are kept per thread so workers don't have to wait on each other to count,
+// each one hands its counts in with the tile it finished, see take_thread_stats
+fn with_stats<F: FnOnce(&mut RenderStats)>(count: F) {
+    THREAD_STATS.with(|stats| count(&mut stats.borrow_mut()));
+}
+
+pub fn count_ray(kind: RayKind) {
+    with_stats(|stats| match kind {
+        RayKind::Camera => stats.primary_rays += 1,
+        RayKind::Reflected | RayKind::Transmitted | RayKind::Scattered => stats.secondary_rays += 1,
+        RayKind::Shadow => stats.shadow_rays += 1,
+    });
+}
+
+pub fn count_intersection_test(kind: PrimitiveKind) {
+    with_stats(|stats| stats.intersection_test_counts[kind.index()] += 1);
+}
+
+pub fn count_bvh_node_visit() {
+    with_stats(|stats| stats.bvh_node_visits += 1);
+}
+
+pub fn count_mesh_triangle_test() {
+    with_stats(|stats| stats.mesh_triangle_tests += 1);
+}
+
+// Runs work and adds how long it took to the phase's time on this thread
+pub fn time_phase<T, F: FnOnce() -> T>(phase: RenderPhase, work: F) -> T {
+    let start = Instant::now();
+    let result = work();
+    let elapsed = start.elapsed();
+    with_stats(|stats| match phase {
+        RenderPhase::Trace => stats.trace_time += elapsed,
+        RenderPhase::AntiAlias => stats.anti_alias_time += elapsed,
+        RenderPhase::Aov => stats.aov_time += elapsed,
+    });
+    result
+}
+
+// Runs work without adding anything it counts to this thread's stats,
+// for looking at the scene outside of the render itself, like AOV lookups
+pub fn uncounted<T, F: FnOnce() -> T>(work: F) -> T {
+    let counted = take_thread_stats();
+    let result = work();
+    THREAD_STATS.with(|stats| stats.replace(counted));
+    result
+}
+
+// Intersection tests of every kind on this thread since take_thread_stats was last called,
+// including the nodes and triangles of mesh bounding trees
+pub fn thread_intersection_tests() -> u64 {
+    THREAD_STATS.with(|stats| {
+        let stats = stats.borrow();
+        stats.total_intersection_tests() + stats.bvh_node_visits + stats.mesh_triangle_tests
+    })
+}
+
+// Everything counted on this thread since the last call
+pub fn take_thread_stats() -> RenderStats {
+    THREAD_STATS.with(|stats| stats.replace(RenderStats::default()))
+}
@@ -1,10 +1,18 @@
-use color::Color;
+use color::{Color, ColorEncoding};
 use image::RgbImage;
 use geometry::SurfaceCoord;
 use std::sync::Arc;
+use scene_file::{Statement, SceneFileError};
+use asset_manager::open_image;
+use error::Error;
 
 pub trait TextureMappable: TextureMappableClone {
     fn get_color(&self, surface_coord: SurfaceCoord) -> Color;
+
+    // Describes the texture as eg. `texture "path"` for scene files
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        Err(SceneFileError::unsupported("texture has no scene file description"))
+    }
 }
 
 pub trait TextureMappableClone {
@@ -28,7 +36,11 @@ impl Clone for Box<TextureMappable + Send + Sync> {
 
 #[derive(Clone)]
 pub struct ImageTexture {
-    image: Arc<RgbImage>
+    image: Arc<RgbImage>,
+    // Where the image was loaded from, if anywhere
+    pub path: Option<String>,
+    // Textures are sRGB unless they hold data that was stored linearly
+    pub encoding: ColorEncoding,
     // TODO: add tiling
 }
 
@@ -37,14 +49,18 @@ impl ImageTexture {
         use image::imageops::flip_vertical;
         Box::new(ImageTexture {
             image: Arc::new(flip_vertical(&image)),
+            path: None,
+            encoding: ColorEncoding::Srgb,
         })
     }
 
-    pub fn from_path(path: &str) -> Box<ImageTexture> {
+    pub fn from_path(path: &str) -> Result<Box<ImageTexture>, Error> {
         use image::imageops::flip_vertical;
-        Box::new(ImageTexture {
-            image: Arc::new(flip_vertical(&image::open(path).unwrap().to_rgb())),
-        })
+        Ok(Box::new(ImageTexture {
+            image: Arc::new(flip_vertical(&open_image(path)?.to_rgb())),
+            path: Some(String::from(path)),
+            encoding: ColorEncoding::Srgb,
+        }))
     }
 }
 
@@ -52,10 +68,10 @@ impl TextureMappable for ImageTexture {
     fn get_color(&self, surface_coord: SurfaceCoord) -> Color {
         let (u, v) = surface_coord.get_uv_index(self.image.width()-1, self.image.height()-1);
 
-        let up_left = Color::from_rgb(self.image.get_pixel(u, v));
-        let up_right = Color::from_rgb(self.image.get_pixel(u+1, v));
-        let bot_left = Color::from_rgb(self.image.get_pixel(u, v+1));
-        let bot_right = Color::from_rgb(self.image.get_pixel(u+1, v+1));
+        let up_left = Color::from_encoded_rgb(self.image.get_pixel(u, v), self.encoding);
+        let up_right = Color::from_encoded_rgb(self.image.get_pixel(u+1, v), self.encoding);
+        let bot_left = Color::from_encoded_rgb(self.image.get_pixel(u, v+1), self.encoding);
+        let bot_right = Color::from_encoded_rgb(self.image.get_pixel(u+1, v+1), self.encoding);
 
         let (u, v) = surface_coord.get_uv_decimal(self.image.width()-1, self.image.height()-1);
 
@@ -67,4 +83,11 @@ impl TextureMappable for ImageTexture {
         bot_left * (1.0-u)*(1.0-v) +
         bot_right * u*(1.0-v)
     }
-}
\ No newline at end of file
+
+    fn to_statement(&self) -> Result<Statement, SceneFileError> {
+        match self.path {
+            Some(ref path) => Ok(Statement::new("texture").with_string(path).with_encoding(self.encoding, ColorEncoding::Srgb)),
+            None => Err(SceneFileError::unsupported("texture was not loaded from a file")),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use render::ImageDimension;
+
+// A rectangle of the image rendered as one job, the ones on the right and bottom edges can be smaller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Tile {
+        Tile { x, y, width, height }
+    }
+
+    pub fn area(&self) -> u32 {
+        self.width * self.height
+    }
+
+    // Pixels of the tile row by row, the same order their results are stored in
+    pub fn pixels(&self) -> Vec<(u32, u32)> {
+        let mut pixels = Vec::with_capacity(self.area() as usize);
+        for y in self.y..self.y + self.height {
+            for x in self.x..self.x + self.width {
+                pixels.push((x, y));
+            }
+        }
+        pixels
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    // The part of the tile inside the image, or None if there isn't any
+    pub fn clip(&self, image_dimension: ImageDimension) -> Option<Tile> {
+        let x = self.x.min(image_dimension.width);
+        let y = self.y.min(image_dimension.height);
+        let width = (self.x.saturating_add(self.width)).min(image_dimension.width) - x;
+        let height = (self.y.saturating_add(self.height)).min(image_dimension.height) - y;
+        if width == 0 || height == 0 {
+            None
+        }
+        else {
+            Some(Tile::new(x, y, width, height))
+        }
+    }
+
+    // The tile with margin pixels added on every side, without going past the edges of the image
+    pub fn grow(&self, margin: u32, image_dimension: ImageDimension) -> Tile {
+        let x = self.x.saturating_sub(margin);
+        let y = self.y.saturating_sub(margin);
+        Tile::new(x, y,
+                  (self.x + self.width + margin).min(image_dimension.width) - x,
+                  (self.y + self.height + margin).min(image_dimension.height) - y)
+    }
+}
+
+// Which tiles get rendered first
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TileOrder {
+    // Left to right, top to bottom
+    Scanline,
+    // Outwards from the middle of the image, so the interesting part shows up first
+    #[default]
+    Spiral,
+    // Along a Hilbert curve, each tile is next to the last one so nearby geometry stays in cache
+    Hilbert,
+}
+
+impl TileOrder {
+    pub const ALL: [TileOrder; 3] = [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TileOrder::Scanline => "scanline",
+            TileOrder::Spiral => "spiral",
+            TileOrder::Hilbert => "hilbert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TileOrder> {
+        TileOrder::ALL.iter().find(|order| order.name() == name).cloned()
+    }
+}
+
+// Splits the image into tile_size by tile_size squares, in the order they should be rendered
+pub fn image_tiles(image_dimension: ImageDimension, tile_size: u32, order: TileOrder) -> Vec<Tile> {
+    region_tiles(Tile::new(0, 0, image_dimension.width, image_dimension.height), tile_size, order)
+}
+
+// Same as image_tiles, but only covering region, eg. when rendering a crop of the image
+pub fn region_tiles(region: Tile, tile_size: u32, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let columns = region.width.div_ceil(tile_size);
+    let rows = region.height.div_ceil(tile_size);
+    let tile = |column: u32, row: u32| -> Tile {
+        let x = column * tile_size;
+        let y = row * tile_size;
+        Tile::new(region.x + x, region.y + y, tile_size.min(region.width - x), tile_size.min(region.height - y))
+    };
+
+    let grid = match order {
+        TileOrder::Scanline => (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => hilbert(columns, rows),
+    };
+    grid.into_iter().map(|(column, row)| tile(column, row)).collect()
+}
+
+// Walks a square spiral out from the middle of the grid, skipping anything outside of it
+fn spiral(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let total = (columns * rows) as usize;
+    let mut cells = Vec::with_capacity(total);
+    let (mut column, mut row) = (((columns as i64) - 1) / 2, ((rows as i64) - 1) / 2);
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut step = 0;
+    while cells.len() < total {
+        // Sides of the spiral go 1, 1, 2, 2, 3, 3...
+        let (dx, dy) = directions[step % 4];
+        for _ in 0..step / 2 + 1 {
+            if column >= 0 && row >= 0 && column < columns as i64 && row < rows as i64 {
+                cells.push((column as u32, row as u32));
+            }
+            column += dx;
+            row += dy;
+        }
+        step += 1;
+    }
+    cells
+}
+
+// Hilbert curve over the smallest power of two square that covers the grid, skipping anything outside of it
+fn hilbert(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let size = columns.max(rows).next_power_of_two();
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for d in 0..size * size {
+        let (column, row) = hilbert_cell(size, d);
+        if column < columns && row < rows {
+            cells.push((column, row));
+        }
+    }
+    cells
+}
+
+// The d'th cell along a Hilbert curve filling a size by size square
+fn hilbert_cell(size: u32, d: u32) -> (u32, u32) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < size {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
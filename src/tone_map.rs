@@ -0,0 +1,70 @@
+use color::Color;
+
+// How linear colors get squeezed into the displayable 0.0 to 1.0 range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapOperator {
+    // Anything brighter than 1.0 gets cut off, same as Color::clamp
+    Clamp,
+    // Rolls off highlights by luminance, so colors keep their hue
+    Reinhard,
+    // Krzysztof Narkowicz's fit of the ACES filmic curve, adds some contrast
+    Aces,
+}
+
+impl ToneMapOperator {
+    pub const ALL: [ToneMapOperator; 3] = [ToneMapOperator::Clamp, ToneMapOperator::Reinhard, ToneMapOperator::Aces];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMapOperator::Clamp => "clamp",
+            ToneMapOperator::Reinhard => "reinhard",
+            ToneMapOperator::Aces => "aces",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ToneMapOperator> {
+        ToneMapOperator::ALL.iter().find(|operator| operator.name() == name).cloned()
+    }
+}
+
+// Applied between the float framebuffer and 8 bit images.
+// Exposure is in stops, each one doubles the brightness before the operator is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    pub exposure: f64,
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneMapOperator, exposure: f64) -> ToneMapping {
+        ToneMapping { operator, exposure }
+    }
+
+    // Takes a linear color and gives back a linear color in the 0.0 to 1.0 range
+    pub fn apply(&self, color: Color) -> Color {
+        let color = color * 2f64.powf(self.exposure);
+        match self.operator {
+            ToneMapOperator::Clamp => color.clamp(),
+            ToneMapOperator::Reinhard => {
+                let luminance = color.luminance();
+                if luminance <= 0.0 {
+                    return Color::BLACK;
+                }
+                (color * (1.0 / (1.0 + luminance))).clamp()
+            },
+            ToneMapOperator::Aces => Color::new(aces(color.red), aces(color.green), aces(color.blue)).clamp(),
+        }
+    }
+}
+
+// Hard clamp with no exposure change, what renders have always looked like
+impl Default for ToneMapping {
+    fn default() -> ToneMapping {
+        ToneMapping::new(ToneMapOperator::Clamp, 0.0)
+    }
+}
+
+fn aces(x: f64) -> f64 {
+    let x = x.max(0.0);
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
@@ -1,20 +1,20 @@
 use super::*;
 use euler::*;
 
-pub fn texture_phong_material(path: &str, diffuse: f64, specular: f64, ambient: f64, shininess: f64) -> Box<MixShader> {
-    MixShader::from_shaders(
+pub fn texture_phong_material(path: &str, diffuse: f64, specular: f64, ambient: f64, shininess: f64) -> Result<Box<MixShader>, Error> {
+    Ok(MixShader::from_shaders(
         vec!(
-            TextureShader::new(ImageTexture::from_path(path)),
+            TextureShader::new(ImageTexture::from_path(path)?),
             PhongShader::new(Color::WHITE*diffuse, Color::WHITE*specular, Color::WHITE*ambient, shininess),
         ),
-    )
+    ))
 }
 
-pub fn brick_shader() -> Box<ChainShader> {
+pub fn brick_shader() -> Result<Box<ChainShader>, Error> {
     let mut brick_shader = ChainShader::new();
     brick_shader.push_shader(
         NormalMapShader::new(
-            NormalMap::from_path("assets/images/normal_maps/brick_wall.jpg")
+            NormalMap::from_path("assets/images/normal_maps/brick_wall.jpg")?
         )
     );
     /*
@@ -25,7 +25,7 @@ pub fn brick_shader() -> Box<ChainShader> {
     brick_shader.push_shader(
         PhongShader::new(Color::WHITE*0.9, Color::WHITE*0.1, Color::WHITE*0.0, 2.0)
     );
-    brick_shader
+    Ok(brick_shader)
 }
 
 pub fn no_ambient() -> AmbientLight {
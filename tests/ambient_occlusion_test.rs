@@ -0,0 +1,76 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+// A cube sitting on a floor, lit only by ambient light
+fn cube_on_floor(floor_material: Box<dyn Shadable + Send + Sync>) -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    root.add_child(geometry_node(rotation(Axis::X, -90.0), floor_material.clone(), Rectangle::new(400.0, 400.0), vec!()));
+    root.add_child(geometry_node(translation(0.0, 50.0, 0.0), floor_material, Cube::new(100.0), vec!()));
+    scene.root = Box::new(root);
+    scene.ambient_light = AmbientLight::new(Color::WHITE, 1.0);
+    scene
+}
+
+fn ao_material() -> Box<dyn Shadable + Send + Sync> {
+    CompositeShader::from_shaders(vec!(
+        (1.0, PhongShader::new(Color::WHITE*0.5, Color::BLACK, Color::BLACK, 1.0)),
+        (1.0, AmbientOcclusionShader::new(Color::WHITE, 50.0, 64)),
+    ))
+}
+
+// Looking straight down from far away, so the sides of the cube don't hide the floor next to it.
+// The floor fills the image and +x is to the right.
+fn render_top_down(scene: Scene, integrator: Box<dyn Integrator + Send + Sync>) -> FrameBuffer {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.integrator = integrator;
+    config.samples = 64;
+    let camera = CameraConfig { origin: dvec3!(0.0, 4000.0, 0.0), target: dvec3!(0.0, 0.0, 0.0), up: dvec3!(0.0, 0.0, -1.0), fov_y: 5.725 };
+    render_framebuffer_with_config(scene, image(64, 64), camera, config)
+}
+
+// Floor pixels right next to the cube and far away from it
+const NEAR: (u32, u32) = (40, 31);
+const FAR: (u32, u32) = (60, 31);
+
+fn floor_visibility(scene: &Scene, x: f64) -> f64 {
+    let ray = Ray::new(dvec3!(x, 200.0, 0.0), dvec3!(0.0, -1.0, 0.0), 5);
+    let node_intersect = scene.root.trace(ray).unwrap();
+    ambient_visibility(scene, node_intersect.intersect, 50.0, 2000)
+}
+
+#[test]
+fn ambient_occlusion_visibility() {
+    let scene = cube_on_floor(PhongShader::new(Color::WHITE, Color::BLACK, Color::BLACK, 1.0));
+    assert_eq!(floor_visibility(&scene, 180.0), 1.0);
+    let near = floor_visibility(&scene, 55.0);
+    assert!(near > 0.3 && near < 0.8, "visibility next to the cube was {}", near);
+    assert!(floor_visibility(&scene, 51.0) < near);
+}
+
+#[test]
+fn ambient_occlusion_shader() {
+    let framebuffer = render_top_down(cube_on_floor(ao_material()), WhittedIntegrator::new());
+    let far = framebuffer.colors[framebuffer.index(FAR.0, FAR.1)];
+    let near = framebuffer.colors[framebuffer.index(NEAR.0, NEAR.1)];
+    assert_eq!(far, Color::WHITE);
+    assert!(near.red < far.red - 0.2, "near {:?} far {:?}", near, far);
+    write_to_png(framebuffer.to_rgb_image(), "output/ambient_occlusion_shader").unwrap();
+}
+
+#[test]
+fn ambient_occlusion_integrator() {
+    // The integrator ignores materials and lights
+    let scene = cube_on_floor(PhongShader::new(Color::RED, Color::BLACK, Color::BLACK, 1.0));
+    let framebuffer = render_top_down(scene, AmbientOcclusionIntegrator::new(50.0));
+    let far = framebuffer.colors[framebuffer.index(FAR.0, FAR.1)];
+    let near = framebuffer.colors[framebuffer.index(NEAR.0, NEAR.1)];
+    assert_eq!(far, Color::WHITE);
+    assert!(near.red < far.red - 0.2, "near {:?} far {:?}", near, far);
+    assert_eq!(near.red, near.green);
+    write_to_png(framebuffer.to_rgb_image(), "output/ambient_occlusion_integrator").unwrap();
+}
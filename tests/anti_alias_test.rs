@@ -5,6 +5,8 @@ use raytracer::*;
 use raytracer::matrix::*;
 use euler::*;
 use std::f64::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[test]
 fn anti_alias_comparison() {
@@ -17,12 +19,59 @@ fn anti_alias_comparison() {
     let sphere = create_cube(50.0, translation(0.0, -75.0, 0.0), Color::WHITE);
     test_scene.root.add_child(Box::new(sphere));
 
-
     let image1 = render(test_scene.clone(), image(512, 512), camera([0.0, 0.0, room_size/2.0], [0.0, -(room_size/2.0)*0.6, 0.0]));
-    write_to_png( image1, "output/anti_alias_0");
+    write_to_png( image1, "output/anti_alias_0").unwrap();
 
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image2 = render_with_config(test_scene, image(512, 512), camera([0.0, 0.0, room_size/2.0], [0.0, -(room_size/2.0)*0.6, 0.0]), render_config);
-    write_to_png( image2, "output/anti_alias_1");
-}
\ No newline at end of file
+    write_to_png( image2, "output/anti_alias_1").unwrap();
+}
+
+// Counts the rays it's given, and returns white for the ones going right of the camera.
+// With noisy set, every ray is randomly black or white instead.
+#[derive(Clone)]
+struct CountingIntegrator {
+    rays: Arc<AtomicUsize>,
+    noisy: bool,
+}
+
+impl Integrator for CountingIntegrator {
+    fn trace(&self, _: &Scene, ray: Ray) -> (f64, Color) {
+        let count = self.rays.fetch_add(1, Ordering::SeqCst);
+        let white = if self.noisy { count % 3 == 1 } else { ray.direction.x > 0.0 };
+        (100.0, if white { Color::WHITE } else { Color::BLACK })
+    }
+}
+
+fn count_rays(noisy: bool, config: RenderConfig) -> (usize, FrameBuffer) {
+    let rays = Arc::new(AtomicUsize::new(0));
+    let mut config = config;
+    config.integrator = Box::new(CountingIntegrator { rays: rays.clone(), noisy });
+    let framebuffer = render_framebuffer_with_config(Scene::new(), image(8, 8), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), config);
+    (rays.load(Ordering::SeqCst), framebuffer)
+}
+
+#[test]
+fn anti_alias_adaptive_samples() {
+    let mut config = RenderConfig::default();
+    config.aa_min_samples = 4;
+    config.aa_max_samples = 32;
+    // Spread over a 2x2 grid, the first few rays always land on both sides of the edge
+    config.sampler = SamplerKind::Stratified;
+
+    // Pixels that agree with themselves stop at the minimum, noisy ones go all the way
+    let (edge_rays, edge) = count_rays(false, config.clone());
+    let (noisy_rays, _) = count_rays(true, config.clone());
+    assert!(edge_rays > 64 * 4 && edge_rays < 64 * 32, "{} rays", edge_rays);
+    assert_eq!(noisy_rays, 64 * 32);
+
+    // Pixel centres sit half a pixel to the right, so column 3 is split down the middle
+    let middle = edge.get_color(3, 4);
+    assert!(middle.red > 0.1 && middle.red < 0.9, "edge pixel was {:?}", middle);
+    assert_eq!(edge.get_color(0, 4), Color::BLACK);
+    assert_eq!(edge.get_color(7, 4), Color::WHITE);
+
+    config.anti_alias = false;
+    assert_eq!(count_rays(true, config).0, 64);
+}
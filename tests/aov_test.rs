@@ -0,0 +1,113 @@
+extern crate raytracer;
+extern crate euler;
+extern crate snowflake;
+
+use raytracer::*;
+use euler::*;
+use snowflake::ProcessUniqueId;
+
+mod common;
+
+// A red sphere on the left and a textured sphere on the right, returns the ids of both
+fn two_sphere_scene() -> (Scene, NodeIds) {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+
+    let mut left = common::red_sphere();
+    left.set_transform(translation(-60.0, 0.0, 0.0));
+
+    let mut right = SceneNode::new();
+    right.set_primitive(Sphere::from_radius(50.0));
+    right.set_material(TextureShader::new(ImageTexture::from_path("assets/images/textures/wood_boards.jpg").unwrap()));
+    right.set_transform(translation(60.0, 0.0, 0.0));
+
+    let ids = NodeIds { left: left.get_id(), right: right.get_id() };
+    root.add_child(Box::new(left));
+    root.add_child(Box::new(right));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(common::front_light()));
+    (scene, ids)
+}
+
+struct NodeIds {
+    left: ProcessUniqueId,
+    right: ProcessUniqueId,
+}
+
+fn aov_config(aovs: AovConfig) -> RenderConfig {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.aovs = aovs;
+    config
+}
+
+fn render_aovs(aovs: AovConfig) -> (FrameBuffer, NodeIds) {
+    let (scene, ids) = two_sphere_scene();
+    (render_framebuffer_with_config(scene, image(96, 48), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), aov_config(aovs)), ids)
+}
+
+#[test]
+fn aov_all_passes() {
+    let (framebuffer, ids) = render_aovs(AovConfig::all());
+    let left = framebuffer.index(38, 24);
+    let right = framebuffer.index(58, 24);
+    let background = framebuffer.index(48, 2);
+
+    // Depth is measured along the view direction, so it's shorter than the distance away from the middle
+    let depths = framebuffer.depths.as_ref().unwrap();
+    assert!((depths[left] - 150.0).abs() < 5.0, "depth was {}", depths[left]);
+    assert!(depths[left] < framebuffer.distances[left]);
+    assert_eq!(depths[background], f64::INFINITY);
+
+    let normals = framebuffer.normals.as_ref().unwrap();
+    assert!(normals[left].z > 0.9 && normals[right].z > 0.9);
+    assert!((normals[left].length() - 1.0).abs() < 1e-6);
+    assert_eq!(normals[background], dvec3!(0.0, 0.0, 0.0));
+
+    let uvs = framebuffer.uvs.as_ref().unwrap();
+    assert!(uvs[left].x >= 0.0 && uvs[left].x < 1.0 && uvs[left].y >= 0.0 && uvs[left].y < 1.0);
+
+    let albedos = framebuffer.albedos.as_ref().unwrap();
+    assert_eq!(albedos[left], Color::new(0.8, 0.1, 0.1));
+    assert!(albedos[right] != albedos[left]);
+    assert_eq!(albedos[background], Color::BLACK);
+
+    // Ids follow the order nodes were made in, 0 is the background
+    let object_ids = framebuffer.object_ids.as_ref().unwrap();
+    assert_eq!(object_ids[left], 1);
+    assert_eq!(object_ids[right], 2);
+    assert_eq!(object_ids[background], 0);
+    assert_eq!(framebuffer.get_object(38, 24), Some(ids.left));
+    assert_eq!(framebuffer.get_object(58, 24), Some(ids.right));
+    assert_eq!(framebuffer.get_object(48, 2), None);
+
+    write_to_png( framebuffer.to_rgb_image(), "output/aov_beauty").unwrap();
+    for pass in AovPass::ALL.iter() {
+        write_to_png( framebuffer.pass_to_rgb_image(*pass).unwrap(), &format!("output/aov_{}", pass.name())).unwrap();
+    }
+}
+
+#[test]
+fn aov_passes_are_optional() {
+    let (without, _) = render_aovs(AovConfig::none());
+    assert!(AovPass::ALL.iter().all(|pass| !without.has_pass(*pass)));
+    assert!(without.pass_to_rgb_image(AovPass::Depth).is_none());
+
+    let (with, _) = render_aovs(AovConfig::from_passes(&[AovPass::Depth, AovPass::ObjectId]));
+    assert!(with.has_pass(AovPass::Depth) && with.has_pass(AovPass::ObjectId));
+    assert!(!with.has_pass(AovPass::Normal) && !with.has_pass(AovPass::Uv) && !with.has_pass(AovPass::Albedo));
+
+    // Asking for passes doesn't change the beauty render
+    assert_eq!(with.colors, without.colors);
+}
+
+#[test]
+fn aov_exr_layers() {
+    let (framebuffer, _) = render_aovs(AovConfig::all());
+    let exr = framebuffer.to_exr_image();
+    let mut names: Vec<&str> = exr.get_channels().iter().map(|channel| channel.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!("B", "G", "R", "albedo.B", "albedo.G", "albedo.R", "depth.Z",
+                           "normal.X", "normal.Y", "normal.Z", "object_id", "uv.U", "uv.V"));
+    exr.save("output/aov_passes.exr").unwrap();
+}
@@ -0,0 +1,71 @@
+extern crate raytracer;
+extern crate euler;
+extern crate image;
+
+use raytracer::*;
+use euler::*;
+use image::RgbImage;
+use std::path::Path;
+use std::error::Error as StdError;
+
+const MISSING: &str = "assets/images/does_not_exist.png";
+
+fn assert_reports_path(err: Option<Error>, path: &str) {
+    let err = err.expect("loading a missing asset should fail");
+    assert_eq!(err.path(), path);
+    assert!(err.to_string().contains(path), "'{}' should mention the path", err);
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn asset_missing_files() {
+    assert_reports_path(ImageTexture::from_path(MISSING).err(), MISSING);
+    assert_reports_path(NormalMap::from_path(MISSING).err(), MISSING);
+    assert_reports_path(BumpMap::from_path(MISSING, 10.0).err(), MISSING);
+    assert_reports_path(SkyBox::from_path(MISSING, DMat4::identity()).err(), MISSING);
+    assert_reports_path(Scene::new().set_background_from_path(MISSING).err(), MISSING);
+
+    match ImageTexture::from_path(MISSING) {
+        Err(Error::Io { .. }) => {},
+        _ => panic!("a missing image should be an io error"),
+    }
+
+    let model = "assets/models/does_not_exist.obj";
+    match Mesh::from_path(Path::new(model)) {
+        Err(err @ Error::Model { .. }) => assert_eq!(err.path(), model),
+        _ => panic!("a missing model should be a model error"),
+    }
+}
+
+#[test]
+fn asset_not_an_image() {
+    match ImageTexture::from_path("Cargo.toml") {
+        Err(err @ Error::Image { .. }) => assert_eq!(err.path(), "Cargo.toml"),
+        _ => panic!("a file that isn't an image should be an image error"),
+    }
+}
+
+#[test]
+fn asset_manager_errors() {
+    let mut assets = AssetManager::new();
+    assert_reports_path(assets.image_texture_from_path(MISSING).err(), MISSING);
+    assert_reports_path(assets.normal_map_from_path(MISSING).err(), MISSING);
+    assert_reports_path(assets.bump_map_from_path(MISSING, 10.0).err(), MISSING);
+
+    // Good assets still load and get cached after a failure
+    let path = "assets/images/textures/test3.png";
+    assert!(assets.image_texture_from_path(path).is_ok());
+    assert!(assets.image_texture_from_path(path).is_ok());
+}
+
+#[test]
+fn asset_write_to_png() {
+    let file_name = "output/asset_test/nested/directories/write_to_png";
+    let _ = std::fs::remove_dir_all("output/asset_test");
+    write_to_png(RgbImage::new(4, 4), file_name).unwrap();
+    assert!(Path::new(&format!("{}.png", file_name)).is_file());
+
+    // Can't make a directory inside a file
+    let err = write_to_png(RgbImage::new(4, 4), "Cargo.toml/write_to_png").expect_err("saving inside a file should fail");
+    assert_eq!(err.path(), "Cargo.toml/write_to_png.png");
+}
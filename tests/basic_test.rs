@@ -29,7 +29,7 @@ fn white_sphere_one_light() {
     root.set_material(test_material);
     test_scene.root = Box::new(root);
     let image = render(test_scene, square_image(512), setup_camera());
-    write_to_png( image, "output/test1");
+    write_to_png( image, "output/test1").unwrap();
 }
 
 #[test]
@@ -41,7 +41,7 @@ fn phong_test_1() {
     root.set_material(test_material);
     test_scene.root = Box::new(root);
     let image = render(test_scene, square_image(512), setup_camera());
-    write_to_png( image, "output/phong1");
+    write_to_png( image, "output/phong1").unwrap();
 }
 
 #[test]
@@ -53,7 +53,7 @@ fn phong_test_2() {
     root.set_material(test_material);
     test_scene.root = Box::new(root);
     let image = render(test_scene, square_image(512), setup_camera());
-    write_to_png( image, "output/phong2");
+    write_to_png( image, "output/phong2").unwrap();
 }
 
 #[test]
@@ -70,7 +70,7 @@ fn plane_test_1() {
                                       up: dvec3!(0.0, 1.0, 0.0),
                                       fov_y: 90.0};
     let image = render(test_scene, square_image(512), camera_config);
-    write_to_png( image, "output/plane1");
+    write_to_png( image, "output/plane1").unwrap();
 }
 
 fn setup_diffuse_cube(transform: DMat4) -> Scene {
@@ -91,7 +91,7 @@ fn cube_test_1() {
     let test_scene = setup_diffuse_cube(DMat4::identity());
     let camera_config = camera([50.0, 50.0, 200.0], [0.0; 3]);
     let image = render(test_scene, square_image(512), camera_config);
-    write_to_png( image, "output/cube1");
+    write_to_png( image, "output/cube1").unwrap();
 }
 
 #[test]
@@ -99,7 +99,7 @@ fn translate_test_1() {
     let test_scene = setup_diffuse_cube(translation(1.0, 0.0, 0.0));
     let camera_config = camera([50.0, 50.0, 200.0], [0.0; 3]);
     let image = render(test_scene, square_image(512), camera_config);
-    write_to_png( image, "output/translate_1");
+    write_to_png( image, "output/translate_1").unwrap();
 }
 
 #[test]
@@ -107,7 +107,7 @@ fn rotate_test_1() {
     let test_scene = setup_diffuse_cube(rotation(Axis::Z, 15.0));
     let camera_config = camera([50.0, 50.0, 200.0], [0.0; 3]);
     let image = render(test_scene, square_image(512), camera_config);
-    write_to_png( image, "output/rotate_1");
+    write_to_png( image, "output/rotate_1").unwrap();
 }
 
 #[test]
@@ -115,7 +115,7 @@ fn scale_test_1() {
     let test_scene = setup_diffuse_cube(scaling(2.9, 1.5, 0.3));
     let camera_config = camera([50.0, 50.0, 200.0], [0.0; 3]);
     let image = render(test_scene, square_image(512), camera_config);
-    write_to_png( image, "output/scale_1");
+    write_to_png( image, "output/scale_1").unwrap();
 }
 
 fn setup_multiple_cubes_same_transform(num_cubes: u32, transform: DMat4) -> Scene {
@@ -144,7 +144,7 @@ fn child_transform_test_1() {
     let test_scene = setup_multiple_cubes_same_transform(5, rotation(Axis::Z, 15.0) * translation(50.0, 0.0, 0.0));
     let camera_config = camera([0.0, 0.0, 200.0], [0.0; 3]);
     let image = render(test_scene, square_image(512), camera_config);
-    write_to_png( image, "output/child_transform_1");
+    write_to_png( image, "output/child_transform_1").unwrap();
 }
 
 #[test]
@@ -163,5 +163,5 @@ fn shadow_test_1() {
     test_scene.root = Box::new(root);
     test_scene.add_light(Box::new(PointLight::new(dvec3!(200.0, 200.0, 200.0), Color::new(1.0, 1.0, 1.0), 1.0, (1.0, 0.0, 0.0))));
     let image = render(test_scene, square_image(512), setup_camera());
-    write_to_png( image, "output/shadow_1");
+    write_to_png( image, "output/shadow_1").unwrap();
 }
\ No newline at end of file
@@ -37,19 +37,19 @@ fn bump_map_basic_1() {
             DMat4::identity(),
             vec!(
                 create_room_from_material(700.0, RoomMaterialScheme {
-                    ceiling: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    floor: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    front: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    back: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    left: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    right: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/bumps.png", 1.0)),
+                    ceiling: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    floor: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    front: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    back: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    left: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    right: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/bumps.png", 1.0).unwrap()),
                 }),
             ),
         ),
     );
 
     let image = render(scene, image(500, 500), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/basic_bump_room_1");
+    write_to_png( image, "output/basic_bump_room_1").unwrap();
 }
 
 #[test]
@@ -63,17 +63,17 @@ fn bump_map_basic_2() {
             DMat4::identity(),
             vec!(
                 create_room_from_material(700.0, RoomMaterialScheme {
-                    ceiling: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    floor: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    front: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    back: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    left: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0)),
-                    right: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/bumps.png", 1.0)),
+                    ceiling: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    floor: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    front: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    back: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    left: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/dot.png", 1.0).unwrap()),
+                    right: test_bump_shader(Color::WHITE, am.bump_map_from_path("assets/images/bump_maps/bumps.png", 1.0).unwrap()),
                 }),
             ),
         ),
     );
 
     let image = render(scene, image(500, 500), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/basic_bump_room_2");
+    write_to_png( image, "output/basic_bump_room_2").unwrap();
 }
@@ -0,0 +1,58 @@
+extern crate raytracer;
+extern crate euler;
+extern crate image;
+
+use raytracer::*;
+use euler::*;
+use image::{ImageBuffer, Rgb, RgbImage};
+
+fn flat_image(value: u8) -> RgbImage {
+    ImageBuffer::from_pixel(4, 4, Rgb { data: [value, value, value] })
+}
+
+#[test]
+fn srgb_transfer_functions() {
+    assert_eq!(srgb_encode(0.0), 0.0);
+    assert!((srgb_encode(1.0) - 1.0).abs() < 1e-12);
+    assert!((srgb_encode(0.5) - 0.735_356_983).abs() < 1e-6);
+    assert!((srgb_decode(0.5) - 0.214_041_140).abs() < 1e-6);
+
+    // The linear segment near black
+    assert!((srgb_encode(0.001) - 0.012_92).abs() < 1e-12);
+    assert!((srgb_decode(0.04) - 0.04 / 12.92).abs() < 1e-12);
+
+    // Every 8 bit value survives a trip through linear
+    for value in 0..=255u8 {
+        assert_eq!(ColorEncoding::Srgb.encode(ColorEncoding::Srgb.decode(value)), value);
+        assert_eq!(ColorEncoding::Linear.encode(ColorEncoding::Linear.decode(value)), value);
+        let rgb = Rgb { data: [value, 255 - value, value / 2] };
+        assert_eq!(Color::from_rgb(&rgb).to_rgb(), rgb);
+    }
+}
+
+#[test]
+fn texture_encoding() {
+    let coord = SurfaceCoord::new(0.5, 0.5);
+    let srgb = ImageTexture::new(flat_image(128));
+    let mut linear = ImageTexture::new(flat_image(128));
+    linear.encoding = ColorEncoding::Linear;
+
+    assert!((srgb.get_color(coord).red - srgb_decode(128.0 / 255.0)).abs() < 1e-9);
+    assert!((linear.get_color(coord).red - 128.0 / 255.0).abs() < 1e-9);
+}
+
+#[test]
+fn normal_map_encoding() {
+    let coord = SurfaceCoord::new(0.5, 0.5);
+    let linear = NormalMap::new(flat_image(100));
+    let mut srgb = NormalMap::new(flat_image(100));
+    srgb.encoding = ColorEncoding::Srgb;
+
+    // Data maps are linear unless told otherwise
+    assert_eq!(linear.encoding, ColorEncoding::Linear);
+    assert_eq!(BumpMap::new(ImageBuffer::from_pixel(4, 4, image::Luma { data: [100] }), 1.0).encoding, ColorEncoding::Linear);
+    let expected = dvec3!(28.0, 28.0, 100.0).normalize();
+    let normal = linear.get_normal(coord);
+    assert!((normal - expected).length() < 1e-9);
+    assert!((srgb.get_normal(coord) - expected).length() > 0.1);
+}
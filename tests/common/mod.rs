@@ -0,0 +1,27 @@
+// Fixtures shared by the integration tests, pulled in with `mod common;`.
+// Not every test uses all of them.
+#![allow(dead_code)]
+
+use raytracer::*;
+use euler::*;
+
+// A red sphere at the origin, 50 in radius
+pub fn red_sphere() -> SceneNode {
+    let mut sphere = SceneNode::new();
+    sphere.set_primitive(Sphere::from_radius(50.0));
+    sphere.set_material(PhongShader::new(Color::new(0.8, 0.1, 0.1), Color::WHITE*0.2, Color::BLACK, 4.0));
+    sphere
+}
+
+// A light above and in front of the origin, where the camera usually is
+pub fn front_light() -> PointLight {
+    PointLight::new(dvec3!(0.0, 200.0, 300.0), Color::WHITE, 1.0, (1.0, 0.0, 0.0))
+}
+
+// The red sphere on its own, lit from above
+pub fn sphere_scene() -> Scene {
+    let mut scene = Scene::new();
+    scene.root = Box::new(red_sphere());
+    scene.add_light(Box::new(front_light()));
+    scene
+}
@@ -25,7 +25,7 @@ fn subtract_sphere() {
     scene.add_light(Box::new(PointLight::new(dvec3!(-100.0, 300.0, 200.0), Color::WHITE, 100000.0, (0.0, 0.0, 4.0*PI))));
 
     let image = render(scene, image(1280, 720), camera([0.0, 150.0, 100.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/subtract_shape");
+    write_to_png( image, "output/subtract_shape").unwrap();
 }
 
 fn light1() -> Box<PointLight> {
@@ -44,7 +44,7 @@ fn many_weirds() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/forest2.jpg",
             rotation(Axis::Y, 180.0),
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -58,7 +58,7 @@ fn many_weirds() {
     );
 
     let image = render(scene, image(1920, 1080), camera([0.0, 300.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/many_weirds");
+    write_to_png( image, "output/many_weirds").unwrap();
 }
 
 fn default_material(color: Color) -> Box<PhongShader> {
@@ -173,7 +173,7 @@ fn subtraction_room() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(1920, 1080), camera([0.0, 0.0, 350.0], [0.0, -100.0, -350.0]), render_config);
-    write_to_png( image, "output/subtraction_room");
+    write_to_png( image, "output/subtraction_room").unwrap();
 }
 
 #[test]
@@ -185,7 +185,7 @@ fn subtraction_outside1() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/building.jpg",
             rotation(Axis::Y, 0.0),
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -208,7 +208,7 @@ fn subtraction_outside1() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(5000, 5000), camera([0.0, 0.0, 350.0], [0.0, -100.0, -350.0]), render_config);
-    write_to_png( image, "output/subtraction_outside");
+    write_to_png( image, "output/subtraction_outside").unwrap();
 }
 
 #[test]
@@ -220,7 +220,7 @@ fn subtraction_outside3() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/building.jpg",
             rotation(Axis::Y, 11.0)
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -237,7 +237,7 @@ fn subtraction_outside3() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(5000, 5000), camera([0.0, 0.0, 350.0], [0.0, -100.0, -350.0]), render_config);
-    write_to_png( image, "output/subtraction_outside3");
+    write_to_png( image, "output/subtraction_outside3").unwrap();
 }
 
 fn make_round_cube(size: f64) -> Box<AndShape> {
@@ -272,7 +272,7 @@ fn rounded_cube() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/building.jpg",
             rotation(Axis::Y, 0.0),
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -289,7 +289,7 @@ fn rounded_cube() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(1920, 1080), camera([0.0, 0.0, 350.0], [0.0, -100.0, -350.0]), render_config);
-    write_to_png( image, "output/rounded_cube");
+    write_to_png( image, "output/rounded_cube").unwrap();
 }
 
 fn make_xor_sphere(size: f64) -> Box<XorShape> {
@@ -315,7 +315,7 @@ fn xor_test() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/building.jpg",
             rotation(Axis::Y, 77.0),
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -332,7 +332,7 @@ fn xor_test() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(1920, 1080), camera([0.0, 0.0, 350.0], [0.0, -100.0, -350.0]), render_config);
-    write_to_png( image, "output/xor_test");
+    write_to_png( image, "output/xor_test").unwrap();
 }
 
 #[test]
@@ -344,7 +344,7 @@ fn subtraction_outside4() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/building.jpg",
             rotation(Axis::Y, 130.0),
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -365,7 +365,7 @@ fn subtraction_outside4() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(5000, 5000), camera([0.0, 0.0, 350.0], [0.0, -100.0, -350.0]), render_config);
-    write_to_png( image, "output/subtraction_outside4");
+    write_to_png( image, "output/subtraction_outside4").unwrap();
 }
 
 #[test]
@@ -377,7 +377,7 @@ fn reuleaux() {
         Some(SkyBox::from_path(
             "assets/images/backgrounds/building.jpg",
             rotation(Axis::Y, 200.0),
-        )),
+        ).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -397,5 +397,5 @@ fn reuleaux() {
     let mut render_config = RenderConfig::default();
     render_config.anti_alias = true;
     let image = render_with_config(scene, image(512, 512), camera([-200.0, 350.0, 50.0], [10.0, 0.0, 40.0]), render_config);
-    write_to_png( image, "output/reuleaux");
+    write_to_png( image, "output/reuleaux").unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+// A sphere in front of a wall under a square light, so pixels are anti-aliased and have soft shadows
+fn crop_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    let mut sphere = SceneNode::new();
+    sphere.set_primitive(Sphere::from_radius(30.0));
+    sphere.set_material(PhongShader::new(Color::new(0.2, 0.6, 0.2), Color::WHITE*0.3, Color::BLACK, 16.0));
+    root.add_child(Box::new(sphere));
+    root.add_child(Box::new(create_wall(400.0, Color::WHITE*0.8, translation(0.0, 0.0, -60.0))));
+    scene.root = Box::new(root);
+    scene.add_light(SquareLight::new(dvec3!(50.0, 80.0, 80.0), 40.0, Color::WHITE, 200000.0, (1.0, 0.0, 0.0)));
+    scene
+}
+
+fn render_crop(crop: Option<Tile>, config: RenderConfig) -> FrameBuffer {
+    let mut config = config;
+    config.crop = crop;
+    render_framebuffer_with_config(crop_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), config)
+}
+
+// The pixels of framebuffer inside crop
+fn cut_out<T: Copy>(values: &[T], crop: Tile) -> Vec<T> {
+    crop.pixels().into_iter().map(|(x, y)| values[(y * 32 + x) as usize]).collect()
+}
+
+#[test]
+fn crop_tiles() {
+    let image = ImageDimension::new(32, 24);
+    let crop = Tile::new(5, 3, 10, 7);
+    assert_eq!(crop.clip(image), Some(crop));
+    assert_eq!(Tile::new(30, 20, 10, 10).clip(image), Some(Tile::new(30, 20, 2, 4)));
+    assert_eq!(Tile::new(32, 0, 4, 4).clip(image), None);
+    assert_eq!(crop.grow(2, image), Tile::new(3, 1, 14, 11));
+    assert_eq!(Tile::new(0, 20, 4, 4).grow(2, image), Tile::new(0, 18, 6, 6));
+    assert!(crop.contains(5, 3) && crop.contains(14, 9));
+    assert!(!crop.contains(15, 9) && !crop.contains(4, 3));
+
+    // Tiles cover the crop and nothing else
+    let tiles = region_tiles(crop, 4, TileOrder::Hilbert);
+    let mut covered: Vec<(u32, u32)> = tiles.iter().flat_map(|tile| tile.pixels()).collect();
+    covered.sort();
+    let mut expected = crop.pixels();
+    expected.sort();
+    assert_eq!(covered, expected);
+    assert_eq!(image_tiles(image, 8, TileOrder::Scanline), region_tiles(Tile::new(0, 0, 32, 24), 8, TileOrder::Scanline));
+}
+
+#[test]
+fn crop_matches_whole_render() {
+    let mut config = RenderConfig::default();
+    config.aovs = AovConfig::from_passes(&[AovPass::Normal, AovPass::Depth]);
+    let whole = render_crop(None, config.clone());
+    let crop = Tile::new(5, 3, 10, 7);
+    let cropped = render_crop(Some(crop), config.clone());
+    assert_eq!((cropped.width, cropped.height), (10, 7));
+    assert_eq!(cropped.colors, cut_out(&whole.colors, crop));
+    assert_eq!(cropped.distances, cut_out(&whole.distances, crop));
+    assert_eq!(cropped.normals.unwrap(), cut_out(whole.normals.as_ref().unwrap(), crop));
+    assert!(cropped.uvs.is_none());
+
+    // Wide filters get the samples from just outside the crop too
+    config.filter = PixelFilter::from_kind(FilterKind::Gaussian);
+    let whole = render_crop(None, config.clone());
+    let cropped = render_crop(Some(crop), config.clone());
+    for (a, b) in cropped.colors.iter().zip(cut_out(&whole.colors, crop)) {
+        assert!(a.diff(b) < 1e-9, "{:?} {:?}", a, b);
+    }
+
+    // The same goes for progressive renders, whose noise only counts the crop
+    config.progressive = Some(ProgressiveConfig { samples: 4, samples_per_pass: 2, ..ProgressiveConfig::default() });
+    config.filter = PixelFilter::default();
+    let whole = render_crop(None, config.clone());
+    let cropped = render_crop(Some(Tile::new(31, 23, 1, 1)), config);
+    assert_eq!(cropped.colors, vec![whole.colors[whole.index(31, 23)]]);
+}
+
+#[test]
+fn crop_single_pixel() {
+    let mut config = RenderConfig::default();
+    config.aovs = AovConfig::all();
+    let whole = render_crop(None, config.clone());
+    for &(x, y) in [(0, 0), (16, 12), (31, 5)].iter() {
+        let pixel = render_pixel(crop_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), config.clone(), x, y);
+        let i = whole.index(x, y);
+        assert_eq!(pixel.color, whole.colors[i]);
+        assert_eq!(pixel.distance, whole.distances[i]);
+        assert_eq!(pixel.aov.normal, whole.normals.as_ref().unwrap()[i]);
+        assert_eq!(pixel.aov.hit_id.is_some(), whole.get_object(x, y).is_some());
+    }
+    let middle = render_pixel(crop_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), config, 16, 12);
+    assert!(middle.aov.hit_id.is_some());
+    assert!((middle.distance - 70.0).abs() < 3.0, "{}", middle.distance);
+}
@@ -0,0 +1,135 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+// The same noise every run, roughly between -amount and amount
+fn noisy(color: Color, i: usize, amount: f64) -> Color {
+    let hash = |n: usize| ((n as f64 * 12.9898).sin() * 43758.5453).fract();
+    color + Color::new(hash(i * 3), hash(i * 3 + 1), hash(i * 3 + 2)) * amount
+}
+
+// A 32x32 image with a grey left half facing the camera and a brighter right half facing up
+fn split_image(amount: f64) -> (Vec<Color>, DenoiseGuides) {
+    let mut colors = Vec::new();
+    let mut normals = Vec::new();
+    for y in 0..32 {
+        for x in 0..32 {
+            let left = x < 16;
+            colors.push(noisy(if left { Color::WHITE * 0.3 } else { Color::WHITE * 0.7 }, y * 32 + x, amount));
+            normals.push(if left { dvec3!(0.0, 0.0, 1.0) } else { dvec3!(0.0, 1.0, 0.0) });
+        }
+    }
+    (colors, DenoiseGuides { normals: Some(normals), ..DenoiseGuides::default() })
+}
+
+fn mean_error(colors: &[Color], expected: impl Fn(usize) -> Color) -> f64 {
+    colors.iter().enumerate().map(|(i, color)| color.diff(expected(i))).sum::<f64>() / colors.len() as f64
+}
+
+fn split_expected(i: usize) -> Color {
+    if i % 32 < 16 { Color::WHITE * 0.3 } else { Color::WHITE * 0.7 }
+}
+
+#[test]
+fn denoise_keeps_edges() {
+    let (colors, guides) = split_image(0.2);
+    let denoised = denoise(&colors, 32, 32, &guides, &DenoiseConfig::default());
+    let before = mean_error(&colors, split_expected);
+    let after = mean_error(&denoised, split_expected);
+    assert!(after < before * 0.3, "error went from {} to {}", before, after);
+
+    // Either side of the edge stays on its own side
+    for y in 0..32 {
+        assert!(denoised[y * 32 + 15].red < 0.45, "{:?}", denoised[y * 32 + 15]);
+        assert!(denoised[y * 32 + 16].red > 0.55, "{:?}", denoised[y * 32 + 16]);
+    }
+
+    // Without the normals to go on, the edge gets blurred
+    let blurred = denoise(&colors, 32, 32, &DenoiseGuides::default(), &DenoiseConfig { color_sigma: 10.0, ..DenoiseConfig::default() });
+    assert!(mean_error(&blurred, split_expected) > after);
+
+    // No iterations leaves the image alone
+    assert_eq!(denoise(&colors, 32, 32, &guides, &DenoiseConfig { iterations: 0, ..DenoiseConfig::default() }), colors);
+}
+
+// A floor lit only by light bouncing off a red wall, so a few paths per pixel leave it noisy
+fn bounce_scene() -> Scene {
+    let mut root = SceneNode::new();
+    root.add_child(Box::new(create_wall(400.0, Color::WHITE * 0.8, rotation(Axis::X, -90.0) * translation(0.0, 0.0, -40.0))));
+    root.add_child(Box::new(create_wall(400.0, Color::new(0.8, 0.1, 0.1), translation(0.0, 0.0, -100.0))));
+    let mut sphere = SceneNode::new();
+    sphere.set_primitive(Sphere::from_radius(20.0));
+    sphere.set_material(PhongShader::new(Color::new(0.2, 0.4, 0.8), Color::BLACK, Color::BLACK, 1.0));
+    sphere.set_transform(translation(0.0, -20.0, -40.0));
+    root.add_child(Box::new(sphere));
+
+    let mut scene = Scene::new();
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(0.0, 100.0, 50.0), Color::WHITE, 60000.0, (0.0, 0.0, 1.0))));
+    scene
+}
+
+fn render_bounce(samples: u32, denoise: Option<DenoiseConfig>, aovs: AovConfig) -> FrameBuffer {
+    let mut config = RenderConfig::default();
+    config.integrator = PathIntegrator::new();
+    config.samples = samples;
+    config.denoise = denoise;
+    config.aovs = aovs;
+    render_framebuffer_with_config(bounce_scene(), image(48, 32), camera([0.0, 40.0, 120.0], [0.0, -20.0, -40.0]), config)
+}
+
+#[test]
+fn denoise_render() {
+    let reference = render_bounce(256, None, AovConfig::none());
+    let noisy = render_bounce(4, None, AovConfig::none());
+    let denoised = render_bounce(4, Some(DenoiseConfig::default()), AovConfig::none());
+    let (before, after) = (noisy.mean_difference(&reference), denoised.mean_difference(&reference));
+    assert!(after < before * 0.6, "difference from reference went from {} to {}", before, after);
+    write_to_png(noisy.to_rgb_image(), "output/denoise_before").unwrap();
+    write_to_png(denoised.to_rgb_image(), "output/denoise_after").unwrap();
+
+    // The passes the denoiser used aren't handed back unless they were asked for
+    assert!(denoised.normals.is_none() && denoised.albedos.is_none());
+    let with_depth = render_bounce(4, Some(DenoiseConfig::default()), AovConfig::from_passes(&[AovPass::Depth, AovPass::Normal]));
+    assert!(with_depth.depths.is_some() && with_depth.normals.is_some() && with_depth.albedos.is_none());
+    assert_eq!(with_depth.colors, denoised.colors);
+
+    // Progressive renders denoise the last pass
+    let mut config = RenderConfig::default();
+    config.integrator = PathIntegrator::new();
+    config.denoise = Some(DenoiseConfig::default());
+    let progressive = ProgressiveConfig { samples: 4, samples_per_pass: 2, ..ProgressiveConfig::default() };
+    let mut passes = Vec::new();
+    let last = render_progressive(bounce_scene(), image(48, 32), camera([0.0, 40.0, 120.0], [0.0, -20.0, -40.0]), config, progressive, |framebuffer, _| {
+        passes.push(framebuffer.clone());
+    });
+    assert_eq!(passes.len(), 2);
+    assert!(passes.iter().all(|pass| pass.normals.is_none()));
+    assert!(last.mean_difference(&reference) < passes[0].mean_difference(&reference));
+}
+
+#[test]
+fn denoise_saved_image() {
+    let (colors, guides) = split_image(0.2);
+    HdrImage::from_pixels(32, 32, colors.clone()).save("output/denoise_split.pfm").unwrap();
+    let mut normal_pass = FrameBuffer::new(32, 32);
+    normal_pass.normals = guides.normals;
+    normal_pass.pass_to_rgb_image(AovPass::Normal).unwrap().save("output/denoise_split.normal.png").unwrap();
+
+    let paths = DenoiseGuidePaths { normal: Some(String::from("output/denoise_split.normal.png")), ..DenoiseGuidePaths::default() };
+    let denoised = denoise_image_file("output/denoise_split.pfm", &paths, &DenoiseConfig::default()).unwrap();
+    assert_eq!((denoised.width, denoised.height), (32, 32));
+    assert!(mean_error(&denoised.pixels, split_expected) < mean_error(&colors, split_expected) * 0.3);
+    for y in 0..32 {
+        assert!(denoised.get_pixel(15, y).red < 0.45 && denoised.get_pixel(16, y).red > 0.55);
+    }
+
+    // Guides have to be the same size as the image
+    HdrImage::new(8, 8).save("output/denoise_small.pfm").unwrap();
+    let small = DenoiseGuidePaths { albedo: Some(String::from("output/denoise_small.pfm")), ..DenoiseGuidePaths::default() };
+    assert!(denoise_image_file("output/denoise_split.pfm", &small, &DenoiseConfig::default()).is_err());
+    let missing = denoise_image_file("output/no_such_image.pfm", &DenoiseGuidePaths::default(), &DenoiseConfig::default());
+    assert_eq!(missing.err().unwrap().path(), "output/no_such_image.pfm");
+}
@@ -28,7 +28,7 @@ fn wood_material() -> Box<CompositeShader> {
             (0.8, MixShader::from_shaders(
                 vec!(
                     PhongShader::new(Color::WHITE*0.5, Color::WHITE*0.5, Color::BLACK, 2.0),
-                    TextureShader::new(ImageTexture::from_path("assets/images/textures/light_wood.jpg")),            
+                    TextureShader::new(ImageTexture::from_path("assets/images/textures/light_wood.jpg").unwrap()),            
                 ),
             )),
             (0.2, ReflectionShader::new(Color::WHITE)),
@@ -39,7 +39,7 @@ fn wood_material() -> Box<CompositeShader> {
 fn make_character_sheet(size: f64, transform: DMat4) -> Box<SceneNode> {
     geometry_node(
         transform*translation(0.0, 0.01, 0.0)*rotation(Axis::X, -90.0),
-        texture_phong_material("assets/images/textures/gurf-1.jpg", 1.0, 0.0, 0.0, 1.0),
+        texture_phong_material("assets/images/textures/gurf-1.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
         Rectangle::new(1.7*size, 2.2*size),
         vec!(),
     )
@@ -49,10 +49,10 @@ fn make_d6(size: f64, transform: DMat4) -> Box<SceneNode> {
     geometry_node(
         transform*translation(0.0, size/2.0, 0.0),
         ChainShader::from_shaders(vec!(
-            NormalMapShader::new(BumpMap::from_path("assets/images/bump_maps/d6_num.png", 10.0)),
+            NormalMapShader::new(BumpMap::from_path("assets/images/bump_maps/d6_num.png", 10.0).unwrap()),
             CompositeShader::from_shaders(vec!(
                 (0.05, ReflectionShader::new(Color::WHITE)),
-                (0.95, texture_phong_material("assets/images/textures/d6_num.png", 0.9, 0.1, 0.0, 2.0)),
+                (0.95, texture_phong_material("assets/images/textures/d6_num.png", 0.9, 0.1, 0.0, 2.0).unwrap()),
             )),
         )),
         Cube::new(size),
@@ -63,7 +63,7 @@ fn make_d6(size: f64, transform: DMat4) -> Box<SceneNode> {
 fn make_d4(size: f64, transform: DMat4) -> Box<SceneNode> {
     geometry_node(
         transform,
-        texture_phong_material("assets/images/textures/d4_num.png", 1.0, 0.0, 0.0, 1.0),
+        texture_phong_material("assets/images/textures/d4_num.png", 1.0, 0.0, 0.0, 1.0).unwrap(),
         Tetrahedron::new(size),
         vec!()
     )
@@ -73,7 +73,7 @@ fn make_dice_scene() -> Scene {
     build_scene(
         vec!(light1(), light2()),
         no_ambient(),
-        Some(SkyBox::from_path("assets/images/backgrounds/nice_room.jpg", rotation(Axis::Y, 20.0))),
+        Some(SkyBox::from_path("assets/images/backgrounds/nice_room.jpg", rotation(Axis::Y, 20.0)).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -94,7 +94,7 @@ fn make_camera() -> CameraConfig {
 fn dice_scene_lo_res() {
     let scene = make_dice_scene();
     let image = render(scene, image(192, 108), camera([-30.0, 20.0, 70.0], [50.0, -50.0, -80.0]));
-    write_to_png( image, "output/dice_scene_lo_res");
+    write_to_png( image, "output/dice_scene_lo_res").unwrap();
 }
 
 #[test]
@@ -103,7 +103,7 @@ fn dice_scene_hi_res() {
     let mut config = RenderConfig::default();
     config.anti_alias = false;
     let image = render_with_config(scene, image(1920, 1080), camera([-30.0, 20.0, 70.0], [50.0, -50.0, -80.0]), config);
-    write_to_png( image, "output/dice_scene_hi_res");
+    write_to_png( image, "output/dice_scene_hi_res").unwrap();
 }
 
 #[test]
@@ -117,7 +117,7 @@ fn polygon_basic() {
             vec!(
                 geometry_node(
                     rotation(Axis::Y, 0.0),
-                    texture_phong_material("assets/images/textures/granite.jpg", 1.0, 0.0, 0.0, 1.0),
+                    texture_phong_material("assets/images/textures/granite.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
                     Polygon::from_vertices(vec!(dvec3!(-10.0, -10.0, 0.0), 
                                                 dvec3!(0.0, -10.0, 0.0), 
                                                 dvec3!(10.0, 0.0, 0.0), 
@@ -128,7 +128,7 @@ fn polygon_basic() {
         ),
     );
     let image = render(scene, image(512, 512), camera([0.0, 0.0, 10.0], [0.0, 0.0, -10.0]));
-    write_to_png( image, "output/polygon_basic");
+    write_to_png( image, "output/polygon_basic").unwrap();
 }
 
 #[test]
@@ -142,7 +142,7 @@ fn triangle_basic() {
             vec!(
                 geometry_node(
                     rotation(Axis::Y, 0.0),
-                    texture_phong_material("assets/images/textures/granite.jpg", 1.0, 0.0, 0.0, 1.0),
+                    texture_phong_material("assets/images/textures/granite.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
                     Triangle::from_vertices(dvec3!(-10.0, -10.0, 0.0), dvec3!(0.0, -10.0, 0.0), dvec3!(10.0, 10.0, 0.0)),
                     vec!()
                 ),
@@ -150,7 +150,7 @@ fn triangle_basic() {
         ),
     );
     let image = render(scene, image(512, 512), camera([0.0, 0.0, 10.0], [0.0, 0.0, -10.0]));
-    write_to_png( image, "output/triangle");
+    write_to_png( image, "output/triangle").unwrap();
 }
 
 #[test]
@@ -167,5 +167,5 @@ fn tetrahedron_basic() {
         ),
     );
     let image = render(scene, image(512, 512), camera([0.0, 20.0, 40.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/tetrahedron");
+    write_to_png( image, "output/tetrahedron").unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,148 @@
+extern crate raytracer;
+
+use raytracer::*;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+const SCENE: &str = "
+image 48 32
+render { tile_size 8; filter gaussian; aa_max_samples 8 }
+camera { origin 0 40 120; target 0 0 0 }
+light point { position 0 100 50; color 1 1 1; power 100000; falloff 0 0 1 }
+node { transform { rotate x -90; translate 0 0 -20 } primitive rectangle 300 300; material phong { diffuse 0.8 0.8 0.8; specular 0 0 0; ambient 0 0 0; shininess 1 } }
+node { primitive sphere 20; material reflection { color 0.9 0.9 0.9 } }
+node { transform { translate 40 0 0 } primitive sphere 15; material translucent { translucency 1 1 1; refractive_index 1.5 } }
+";
+
+fn description() -> SceneDescription {
+    SceneDescription::parse(SCENE).unwrap()
+}
+
+// Depth and normal passes and a heatmap, of part of the image
+fn with_passes(description: SceneDescription) -> SceneDescription {
+    let mut description = description;
+    description.render.aovs = AovConfig::from_passes(&[AovPass::Depth, AovPass::Normal]);
+    description.render.heatmap = Some(CostMetric::Tests);
+    description.render.crop = Some(Tile::new(10, 5, 20, 17));
+    description
+}
+
+fn path_traced_denoised(description: SceneDescription) -> SceneDescription {
+    let mut description = description;
+    description.render.integrator = PathIntegrator::new();
+    description.render.samples = 4;
+    description.render.denoise = Some(DenoiseConfig::default());
+    description
+}
+
+fn render_locally(description: SceneDescription) -> (FrameBuffer, RenderStats) {
+    render_framebuffer_with_stats(description.scene, description.image, description.camera, description.render)
+}
+
+// Starts a coordinator on a free port on this machine
+fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    (listener, address)
+}
+
+fn start_worker(address: &str, num_threads: usize) -> thread::JoinHandle<Result<RenderStats, Error>> {
+    let address = address.to_string();
+    thread::spawn(move || render_worker(description().scene, &address, num_threads))
+}
+
+fn assert_same(distributed: &FrameBuffer, local: &FrameBuffer) {
+    assert_eq!((distributed.width, distributed.height), (local.width, local.height));
+    assert!(distributed.colors == local.colors);
+    assert!(distributed.distances == local.distances);
+}
+
+#[test]
+fn distributed_matches_local() {
+    let (local, local_stats) = render_locally(description());
+    let (listener, address) = listen();
+    let workers = vec![start_worker(&address, 2), start_worker(&address, 1)];
+    let (distributed, stats) = render_distributed(description(), listener).unwrap();
+    assert_same(&distributed, &local);
+
+    // Between them the workers did the same work as a local render
+    let worker_stats: Vec<RenderStats> = workers.into_iter().map(|worker| worker.join().unwrap().unwrap()).collect();
+    assert_eq!(stats.primary_rays, local_stats.primary_rays);
+    assert_eq!(stats.intersection_test_counts, local_stats.intersection_test_counts);
+    assert_eq!(worker_stats.iter().map(|stats| stats.primary_rays).sum::<u64>(), stats.primary_rays);
+    write_to_png(distributed.to_rgb_image(), "output/distributed").unwrap();
+
+    // Passes, heatmap costs and crops come back the same too
+    let (local, _) = render_locally(with_passes(description()));
+    let (listener, address) = listen();
+    let worker = start_worker(&address, 2);
+    let (distributed, _) = render_distributed(with_passes(description()), listener).unwrap();
+    worker.join().unwrap().unwrap();
+    assert_same(&distributed, &local);
+    assert!(distributed.depths == local.depths && distributed.normals == local.normals);
+    assert_eq!(distributed.costs, local.costs);
+    assert_eq!(distributed.width, 20);
+}
+
+#[test]
+fn distributed_denoise() {
+    let (local, _) = render_locally(path_traced_denoised(description()));
+    let (listener, address) = listen();
+    let worker = start_worker(&address, 2);
+    let (distributed, stats) = render_distributed(path_traced_denoised(description()), listener).unwrap();
+    worker.join().unwrap().unwrap();
+    assert_same(&distributed, &local);
+    assert!(distributed.normals.is_none());
+    assert!(stats.denoise_time > Default::default());
+}
+
+#[test]
+fn distributed_workers_come_and_go() {
+    let (local, _) = render_locally(description());
+    let (listener, address) = listen();
+    let coordinator = thread::spawn(move || render_distributed(description(), listener));
+
+    // A worker that takes a tile and goes away without finishing it
+    let mut quitter = TcpStream::connect(&address).unwrap();
+    quitter.write_all(b"RTDIST01").unwrap();
+    quitter.write_all(&1u32.to_le_bytes()).unwrap();
+    let mut header = [0; 12];
+    quitter.read_exact(&mut header).unwrap();
+    assert_eq!(&header[..8], b"RTDIST01");
+    let mut settings = vec![0; u32::from_le_bytes([header[8], header[9], header[10], header[11]]) as usize + 8];
+    quitter.read_exact(&mut settings).unwrap();
+    quitter.write_all(&[1]).unwrap();
+    let mut tile = [0; 21];
+    quitter.read_exact(&mut tile).unwrap();
+    assert_eq!(tile[0], 1);
+    drop(quitter);
+
+    // A worker with a different scene is turned away
+    let mut other = description();
+    other.scene.ambient_light = AmbientLight::new(Color::WHITE, 0.5);
+    let err = render_worker(other.scene, &address, 1).unwrap_err();
+    assert!(err.to_string().contains("isn't the same"), "{}", err);
+
+    // The one that's left renders everything, including the tile the first one took
+    start_worker(&address, 1).join().unwrap().unwrap();
+    let (distributed, _) = coordinator.join().unwrap().unwrap();
+    assert_same(&distributed, &local);
+}
+
+#[test]
+fn distributed_unsupported() {
+    let mut progressive = description();
+    progressive.render.progressive = Some(ProgressiveConfig::default());
+    assert!(render_distributed(progressive, listen().0).is_err());
+
+    let mut object_ids = description();
+    object_ids.render.aovs = AovConfig::all();
+    assert!(render_distributed(object_ids, listen().0).is_err());
+
+    // Workers get the settings without the scene, which put back together describe the same render
+    let settings = description().settings_source().unwrap();
+    assert!(settings.contains("render") && !settings.contains("sphere"), "{}", settings);
+    let rebuilt = SceneDescription { scene: description().scene, ..SceneDescription::parse(&settings).unwrap() };
+    assert_eq!(rebuilt.to_source().unwrap(), description().to_source().unwrap());
+}
@@ -0,0 +1,99 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+fn read_i32(bytes: &[u8], at: usize) -> i32 {
+    i32::from_le_bytes([bytes[at], bytes[at+1], bytes[at+2], bytes[at+3]])
+}
+
+fn read_f32(bytes: &[u8], at: usize) -> f32 {
+    f32::from_le_bytes([bytes[at], bytes[at+1], bytes[at+2], bytes[at+3]])
+}
+
+fn read_string(bytes: &[u8], at: &mut usize) -> String {
+    let start = *at;
+    while bytes[*at] != 0 {
+        *at += 1;
+    }
+    *at += 1;
+    String::from_utf8(bytes[start..*at-1].to_vec()).unwrap()
+}
+
+// Returns the channel names, and where the offset table starts
+fn read_header(bytes: &[u8]) -> (Vec<String>, usize) {
+    assert_eq!(&bytes[0..4], &[0x76, 0x2f, 0x31, 0x01]);
+    assert_eq!(read_i32(bytes, 4), 2);
+    let mut at = 8;
+    let mut channels: Vec<String> = Vec::new();
+    loop {
+        let name = read_string(bytes, &mut at);
+        if name.is_empty() {
+            return (channels, at);
+        }
+        let kind = read_string(bytes, &mut at);
+        let size = read_i32(bytes, at) as usize;
+        at += 4;
+        if kind == "chlist" {
+            let mut channel_at = at;
+            loop {
+                let channel = read_string(bytes, &mut channel_at);
+                if channel.is_empty() {
+                    break;
+                }
+                channels.push(channel);
+                channel_at += 16;
+            }
+        }
+        at += size;
+    }
+}
+
+#[test]
+fn exr_multiple_layers() {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    root.set_primitive(Sphere::from_radius(100.0));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(200.0, 200.0, 200.0), Color::WHITE, 4.0, (1.0, 0.0, 0.0))));
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    let beauty = render_hdr_with_config(scene, image(64, 48), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config);
+
+    let depth: Vec<f64> = (0..64*48).map(|i| i as f64 * 0.5).collect();
+    let normals: Vec<DVec3> = (0..64*48).map(|i| dvec3!(0.0, 1.0, i as f64)).collect();
+    let ids: Vec<u32> = (0..64*48).map(|i| i % 7).collect();
+
+    let mut exr = ExrImage::from_hdr_image(&beauty);
+    exr.add_float_channel("depth.Z", &depth);
+    exr.add_vector_layer("normal", &normals);
+    exr.add_uint_channel("id", &ids);
+    exr.save("output/exr_multiple_layers.exr").unwrap();
+    beauty.save("output/exr_beauty.exr").unwrap();
+    write_to_png( beauty.to_rgb_image(), "output/exr_beauty").unwrap();
+
+    let bytes = std::fs::read("output/exr_multiple_layers.exr").unwrap();
+    let (channels, table_start) = read_header(&bytes);
+    assert_eq!(channels, vec!("B", "G", "R", "depth.Z", "id", "normal.X", "normal.Y", "normal.Z"));
+
+    // Check the block for line 10, channels are stored one after the other in sorted order
+    let offset = u64::from_le_bytes([bytes[table_start+80], bytes[table_start+81], bytes[table_start+82], bytes[table_start+83],
+                                     bytes[table_start+84], bytes[table_start+85], bytes[table_start+86], bytes[table_start+87]]) as usize;
+    assert_eq!(read_i32(&bytes, offset), 10);
+    assert_eq!(read_i32(&bytes, offset+4), 4 * 64 * 8);
+    let line = offset + 8;
+    let pixel = beauty.get_pixel(3, 10);
+    assert_eq!(read_f32(&bytes, line + 4*3), pixel.blue as f32);
+    assert_eq!(read_f32(&bytes, line + 4*(64*2 + 3)), pixel.red as f32);
+    assert_eq!(read_f32(&bytes, line + 4*(64*3 + 3)), depth[10*64 + 3] as f32);
+    assert_eq!(read_i32(&bytes, line + 4*(64*4 + 3)) as u32, ids[10*64 + 3]);
+    assert_eq!(read_f32(&bytes, line + 4*(64*7 + 3)), (10*64 + 3) as f32);
+    assert_eq!(bytes.len(), offset + (48 - 10) * (8 + 4 * 64 * 8));
+
+    // Missing directories are made first, and errors say which file couldn't be written
+    let _ = std::fs::remove_dir_all("output/exr_nested");
+    exr.save("output/exr_nested/layers.exr").unwrap();
+    assert_eq!(std::fs::read("output/exr_nested/layers.exr").unwrap(), bytes);
+    assert_eq!(exr.save("output/exr_nested/layers.exr/inside.exr").unwrap_err().path(), "output/exr_nested/layers.exr/inside.exr");
+}
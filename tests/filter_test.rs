@@ -0,0 +1,110 @@
+extern crate raytracer;
+
+use raytracer::*;
+
+// White for rays going right of the camera, black for the rest, so the image is split down the middle
+#[derive(Clone)]
+struct EdgeIntegrator;
+
+impl Integrator for EdgeIntegrator {
+    fn trace(&self, _: &Scene, ray: Ray) -> (f64, Color) {
+        (100.0, if ray.direction.x > 0.0 { Color::WHITE } else { Color::BLACK })
+    }
+}
+
+fn render_edge(filter: PixelFilter, tile_size: u32) -> FrameBuffer {
+    let mut config = RenderConfig::default();
+    config.integrator = Box::new(EdgeIntegrator);
+    config.anti_alias = false;
+    config.filter = filter;
+    config.tile_size = tile_size;
+    render_framebuffer_with_config(Scene::new(), image(16, 8), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), config)
+}
+
+#[test]
+fn filter_weights() {
+    let box_filter = PixelFilter::default();
+    assert_eq!(box_filter, PixelFilter::new(FilterKind::Box, 0.5));
+    assert_eq!(box_filter.weight(0.0, 0.0), 1.0);
+    assert_eq!(box_filter.weight(-0.5, 0.2), 1.0);
+    assert_eq!(box_filter.weight(0.5, 0.2), 0.0);
+    assert_eq!(box_filter.reach(), 0);
+
+    let tent = PixelFilter::from_kind(FilterKind::Tent);
+    assert_eq!(tent.weight(0.5, 0.0), 0.5);
+    assert_eq!(tent.weight(1.0, 0.0), 0.0);
+    assert_eq!(tent.reach(), 1);
+
+    let gaussian = PixelFilter::new(FilterKind::Gaussian, 1.5);
+    assert!(gaussian.weight(0.0, 0.0) > gaussian.weight(0.7, 0.0));
+    assert!(gaussian.weight(1.4, 0.0) > 0.0);
+    assert_eq!(gaussian.weight(1.5, 0.0), 0.0);
+    assert_eq!(gaussian.reach(), 1);
+
+    // Both are negative a little way out, which is what keeps them sharp
+    let mitchell = PixelFilter::from_kind(FilterKind::Mitchell);
+    assert!((mitchell.weight(0.0, 0.0) - 64.0 / 81.0).abs() < 1e-9);
+    assert!(mitchell.weight(1.5, 0.0) < 0.0);
+    assert_eq!(mitchell.weight(2.0, 0.0), 0.0);
+    assert_eq!(mitchell.reach(), 2);
+
+    let lanczos = PixelFilter::from_kind(FilterKind::Lanczos);
+    assert_eq!(lanczos.weight(0.0, 0.0), 1.0);
+    assert!(lanczos.weight(0.0, 1.5) < 0.0);
+    assert!(lanczos.weight(1.0, 0.0).abs() < 1e-9);
+
+    assert_eq!(FilterKind::from_name("mitchell"), Some(FilterKind::Mitchell));
+    assert_eq!(FilterKind::from_name("sinc"), None);
+}
+
+#[test]
+fn filter_spreads_samples() {
+    let tent = PixelFilter::from_kind(FilterKind::Tent);
+    let mut film = FilmTile::new(Tile::new(2, 2, 2, 2), tent, ImageDimension::new(8, 8));
+    assert_eq!(film.region, Tile::new(1, 1, 4, 4));
+
+    // Right on the border between two pixels counts half for each of them
+    film.add_sample(tent, 3.0, 2.5, Color::WHITE);
+    let index = |x: u32, y: u32| ((y - 1) * 4 + x - 1) as usize;
+    assert_eq!(film.pixels[index(2, 2)].weight(), 0.5);
+    assert_eq!(film.pixels[index(3, 2)].weight(), 0.5);
+    assert_eq!(film.pixels[index(2, 3)].weight(), 0.0);
+
+    // Nothing lands outside of the image
+    let mut corner = FilmTile::new(Tile::new(0, 0, 2, 2), tent, ImageDimension::new(8, 8));
+    assert_eq!(corner.region, Tile::new(0, 0, 3, 3));
+    corner.add_sample(tent, 0.1, 0.1, Color::WHITE);
+    assert!(corner.pixels[0].weight() > 0.0);
+}
+
+#[test]
+fn filter_smooths_edges() {
+    let boxed = render_edge(PixelFilter::default(), 32);
+    assert!(boxed.colors.iter().all(|color| *color == Color::WHITE || *color == Color::BLACK));
+
+    // With one sample in the middle of each pixel a tent of radius 1 doesn't reach the neighbours
+    let tent = render_edge(PixelFilter::from_kind(FilterKind::Tent), 32);
+    assert_eq!(tent.colors, boxed.colors);
+
+    // Anything wider blends the pixels either side of the edge, but leaves the rest alone
+    let gaussian = render_edge(PixelFilter::from_kind(FilterKind::Gaussian), 32);
+    let row: Vec<f64> = (0..16).map(|x| gaussian.colors[gaussian.index(x, 3)].red).collect();
+    let edge = row.iter().position(|red| *red > 0.0).unwrap();
+    assert!(row[edge] < 0.5 && row[edge + 1] > 0.5 && row[edge + 1] < 1.0, "{:?}", row);
+    assert_eq!(row[edge + 2], 1.0);
+    assert_eq!(row[edge - 1], 0.0);
+
+    // Negative lobes are clamped off instead of leaving pixels darker than black
+    let lanczos = render_edge(PixelFilter::new(FilterKind::Lanczos, 3.0), 32);
+    assert!(lanczos.colors.iter().all(|color| color.red >= 0.0));
+}
+
+#[test]
+fn filter_does_not_show_tiles() {
+    let filter = PixelFilter::from_kind(FilterKind::Mitchell);
+    let small = render_edge(filter, 3);
+    let large = render_edge(filter, 64);
+    for (a, b) in small.colors.iter().zip(large.colors.iter()) {
+        assert!((a.red - b.red).abs() < 1e-9);
+    }
+}
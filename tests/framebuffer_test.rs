@@ -0,0 +1,48 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+fn sphere_scene(light_power: f64) -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    root.set_primitive(Sphere::from_radius(100.0));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(200.0, 200.0, 200.0), Color::WHITE, light_power, (1.0, 0.0, 0.0))));
+    scene
+}
+
+fn no_aa() -> RenderConfig {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config
+}
+
+#[test]
+fn framebuffer_distances() {
+    let framebuffer = render_framebuffer_with_config(sphere_scene(1.0), image(65, 65), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), no_aa());
+    assert_eq!(framebuffer.colors.len(), 65*65);
+    assert_eq!(framebuffer.distances.len(), 65*65);
+
+    // Camera is 200 away from the center of a sphere with radius 100
+    assert!((framebuffer.get_distance(32, 32) - 100.0).abs() < 0.1);
+    assert!(framebuffer.get_distance(20, 32) > 100.0);
+    assert_eq!(framebuffer.get_distance(0, 0), f64::INFINITY);
+    assert_eq!(framebuffer.get_color(0, 0), Color::BLACK);
+
+    write_to_png( framebuffer.to_rgb_image(), "output/framebuffer_distances").unwrap();
+}
+
+#[test]
+fn framebuffer_compare_renders() {
+    let dimension = image(48, 48);
+    let camera = camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]);
+    let first = render_framebuffer_with_config(sphere_scene(1.0), dimension, camera, no_aa());
+    let second = render_framebuffer_with_config(sphere_scene(1.0), dimension, camera, no_aa());
+    let brighter = render_framebuffer_with_config(sphere_scene(2.0), dimension, camera, no_aa());
+
+    assert_eq!(first.mean_difference(&second), 0.0);
+    assert!(first.mean_difference(&brighter) > 0.0);
+    assert_eq!(first.to_rgb_image().into_raw(), render_with_config(sphere_scene(1.0), dimension, camera, no_aa()).into_raw());
+}
@@ -0,0 +1,112 @@
+extern crate raytracer;
+extern crate euler;
+extern crate image;
+
+use raytracer::*;
+use euler::*;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+fn bright_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    root.set_primitive(Sphere::from_radius(100.0));
+    root.set_material(PhongShader::new(Color::WHITE*0.8, Color::WHITE*0.5, Color::WHITE*0.1, 4.0));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(200.0, 200.0, 200.0), Color::WHITE, 5.0, (1.0, 0.0, 0.0))));
+    scene.add_light(Box::new(PointLight::new(dvec3!(-200.0, -100.0, 200.0), Color::new(1.0, 0.5, 0.2), 0.5, (1.0, 0.0, 0.0))));
+    scene
+}
+
+fn render_bright_scene() -> HdrImage {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    render_hdr_with_config(bright_scene(), image(96, 64), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config)
+}
+
+#[test]
+fn hdr_keeps_highlights() {
+    let image = render_bright_scene();
+    assert!(image.pixels.iter().any(|color| color.red > 1.0), "expected some pixels brighter than 1.0");
+
+    let rgb = image.to_rgb_image();
+    assert_eq!(rgb.get_pixel(48, 32), &image.get_pixel(48, 32).clamp().to_rgb());
+    write_to_png( rgb, "output/hdr_highlights").unwrap();
+}
+
+#[test]
+fn hdr_radiance_output() {
+    let image = render_bright_scene();
+    image.save("output/hdr_highlights.hdr").unwrap();
+
+    let decoder = image::hdr::HDRDecoder::new(BufReader::new(File::open("output/hdr_highlights.hdr").unwrap())).unwrap();
+    let metadata = decoder.metadata();
+    assert_eq!((metadata.width, metadata.height), (96, 64));
+    let pixels = decoder.read_image_hdr().unwrap();
+    for (written, read) in image.pixels.iter().zip(pixels.iter()) {
+        // RGBE has 8 bits of mantissa shared by the three channels
+        let tolerance = written.red.max(written.green).max(written.blue) / 128.0;
+        assert!((written.red - read.data[0] as f64).abs() <= tolerance);
+        assert!((written.green - read.data[1] as f64).abs() <= tolerance);
+        assert!((written.blue - read.data[2] as f64).abs() <= tolerance);
+    }
+}
+
+#[test]
+fn hdr_pfm_output() {
+    let image = render_bright_scene();
+    image.save("output/hdr_highlights.pfm").unwrap();
+
+    let bytes = fs::read("output/hdr_highlights.pfm").unwrap();
+    let header = b"PF\n96 64\n-1.0\n";
+    assert_eq!(&bytes[..header.len()], &header[..]);
+    let floats: Vec<f32> = bytes[header.len()..].chunks(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    assert_eq!(floats.len(), 96 * 64 * 3);
+
+    // First row in the file is the bottom row of the image
+    let bottom_left = image.get_pixel(0, 63);
+    assert_eq!(&floats[0..3], &[bottom_left.red as f32, bottom_left.green as f32, bottom_left.blue as f32]);
+    let top_right = image.get_pixel(95, 0);
+    assert_eq!(&floats[floats.len()-3..], &[top_right.red as f32, top_right.green as f32, top_right.blue as f32]);
+
+    assert_eq!(image.save("output/hdr_highlights.jpg").unwrap_err().path(), "output/hdr_highlights.jpg");
+
+    // Missing directories are made first, like write_to_png
+    let _ = fs::remove_dir_all("output/hdr_nested");
+    image.save("output/hdr_nested/highlights.pfm").unwrap();
+    assert!(Path::new("output/hdr_nested/highlights.pfm").is_file());
+}
+
+#[test]
+fn hdr_open() {
+    let image = render_bright_scene();
+    image.save("output/hdr_open.pfm").unwrap();
+    let pfm = HdrImage::open("output/hdr_open.pfm").unwrap();
+    assert_eq!((pfm.width, pfm.height), (96, 64));
+    for (written, read) in image.pixels.iter().zip(pfm.pixels.iter()) {
+        assert_eq!(read.red, written.red as f32 as f64);
+        assert_eq!(read.blue, written.blue as f32 as f64);
+    }
+
+    image.save("output/hdr_open.hdr").unwrap();
+    let hdr = HdrImage::open("output/hdr_open.hdr").unwrap();
+    assert_eq!((hdr.width, hdr.height), (96, 64));
+    assert!(hdr.pixels.iter().any(|color| color.red > 1.0));
+
+    // Big endian greyscale, the other kind of PFM
+    let mut bytes = b"Pf\n2 1\n1.0\n".to_vec();
+    bytes.extend_from_slice(&0.25f32.to_be_bytes());
+    bytes.extend_from_slice(&4.0f32.to_be_bytes());
+    fs::write("output/hdr_open_grey.pfm", &bytes).unwrap();
+    assert_eq!(HdrImage::open("output/hdr_open_grey.pfm").unwrap().pixels, vec![Color::from_f64(0.25), Color::from_f64(4.0)]);
+    fs::write("output/hdr_open_short.pfm", &bytes[..bytes.len() - 1]).unwrap();
+    assert!(HdrImage::open("output/hdr_open_short.pfm").is_err());
+
+    // 8 bit images are sRGB encoded
+    let rgb = image.to_rgb_image();
+    rgb.save("output/hdr_open.png").unwrap();
+    let png = HdrImage::open("output/hdr_open.png").unwrap();
+    assert_eq!(png.to_rgb_image().into_raw(), rgb.into_raw());
+    assert!(HdrImage::open("output/no_such_image.png").is_err());
+}
@@ -0,0 +1,120 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+use std::path::Path;
+
+// A hollowed out cube, a mesh and a mirror ball on a floor under a light, with sky above,
+// so some pixels cost a lot more than others
+fn costly_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+
+    let mut hollow = SceneNode::new();
+    let cube = Box::new(BaseShape::new(DMat4::identity(), Cube::new(30.0)));
+    let hole = Box::new(BaseShape::new(DMat4::identity(), Sphere::from_radius(19.0)));
+    hollow.set_primitive(Box::new(SubtractShape::new(cube, hole)));
+    hollow.set_material(slightly_shiney(Color::new(0.2, 0.6, 0.2)));
+    root.add_child(Box::new(hollow));
+
+    let mut model = SceneNode::new();
+    model.set_primitive(Mesh::from_path(Path::new("assets/models/cube.obj")).unwrap());
+    model.set_material(slightly_shiney(Color::new(0.2, 0.2, 0.6)));
+    model.set_transform(translation(40.0, 0.0, 0.0) * scaling(10.0, 10.0, 10.0));
+    root.add_child(Box::new(model));
+
+    let mut ball = SceneNode::new();
+    ball.set_primitive(Sphere::from_radius(15.0));
+    ball.set_material(ReflectionShader::new(Color::WHITE * 0.9));
+    ball.set_transform(translation(-40.0, 0.0, 0.0));
+    root.add_child(Box::new(ball));
+
+    root.add_child(Box::new(create_wall(200.0, Color::WHITE*0.8, rotation(Axis::X, -90.0) * translation(0.0, 0.0, -20.0))));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(0.0, 100.0, 50.0), Color::WHITE, 100000.0, (0.0, 0.0, 1.0))));
+    scene
+}
+
+fn render_heatmap(metric: CostMetric, config: RenderConfig) -> (FrameBuffer, RenderStats) {
+    let mut config = config;
+    config.heatmap = Some(metric);
+    render_framebuffer_with_stats(costly_scene(), image(32, 24), camera([0.0, 20.0, 120.0], [0.0, 0.0, 0.0]), config)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[test]
+fn heatmap_colors_and_scale() {
+    assert!(heatmap_color(0.0) != heatmap_color(1.0));
+    assert_eq!(heatmap_color(-1.0), heatmap_color(0.0));
+    assert_eq!(heatmap_color(2.0), heatmap_color(1.0));
+    assert!(heatmap_color(1.0).red > heatmap_color(1.0).blue && heatmap_color(0.0).blue > heatmap_color(0.0).red);
+
+    // A single outlier doesn't set the scale
+    let mut costs: Vec<f64> = (0..200).map(|i| (i % 10) as f64).collect();
+    costs.push(1000.0);
+    assert_eq!(heatmap_scale(&costs), 9.0);
+    let colors = heatmap_colors(&costs);
+    assert_eq!(colors[9], heatmap_color(1.0));
+    assert_eq!(colors[200], heatmap_color(1.0));
+    assert_eq!(colors[0], heatmap_color(0.0));
+    assert_eq!(heatmap_colors(&[0.0, 0.0]), vec![heatmap_color(0.0); 2]);
+}
+
+#[test]
+fn heatmap_counts_tests() {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    let (framebuffer, stats) = render_heatmap(CostMetric::Tests, config.clone());
+    let costs = framebuffer.costs.clone().unwrap();
+    assert_eq!(costs.len(), 32 * 24);
+    assert_eq!(framebuffer.colors, heatmap_colors(&costs));
+
+    // Every test in the render belongs to some pixel
+    let total = stats.total_intersection_tests() + stats.bvh_node_visits + stats.mesh_triangle_tests;
+    assert_eq!(costs.iter().sum::<f64>(), total as f64);
+
+    // Looking at the sky is cheaper than lighting something
+    let (sky, hit): (Vec<usize>, Vec<usize>) = (0..costs.len()).partition(|&i| framebuffer.distances[i].is_infinite());
+    assert!(!sky.is_empty() && !hit.is_empty());
+    let sky_costs: Vec<f64> = sky.iter().map(|&i| costs[i]).collect();
+    let hit_costs: Vec<f64> = hit.iter().map(|&i| costs[i]).collect();
+    assert!(mean(&sky_costs) < mean(&hit_costs));
+    write_to_png(framebuffer.to_rgb_image(), "output/heatmap_tests").unwrap();
+
+    // The same whichever thread did the work, and cropping keeps each pixel's cost
+    config.num_threads = 1;
+    config.crop = Some(Tile::new(10, 6, 8, 5));
+    let (cropped, _) = render_heatmap(CostMetric::Tests, config);
+    let expected: Vec<f64> = Tile::new(10, 6, 8, 5).pixels().into_iter().map(|(x, y)| costs[framebuffer.index(x, y)]).collect();
+    assert_eq!(cropped.costs.unwrap(), expected);
+
+    // Normal renders don't have costs
+    let plain = render_framebuffer_with_config(costly_scene(), image(8, 6), camera([0.0, 20.0, 120.0], [0.0, 0.0, 0.0]), RenderConfig::default());
+    assert!(plain.costs.is_none());
+}
+
+#[test]
+fn heatmap_time_and_progressive() {
+    let mut config = RenderConfig::default();
+    config.num_threads = 1;
+    let (framebuffer, _) = render_heatmap(CostMetric::Time, config.clone());
+    assert!(framebuffer.costs.unwrap().iter().all(|cost| *cost > 0.0));
+
+    // Progressive passes add to the costs, and denoising is left out
+    config.integrator = PathIntegrator::new();
+    config.denoise = Some(DenoiseConfig::default());
+    config.heatmap = Some(CostMetric::Tests);
+    let progressive = ProgressiveConfig { samples: 4, samples_per_pass: 2, ..ProgressiveConfig::default() };
+    let mut passes = Vec::new();
+    let last = render_progressive(costly_scene(), image(32, 24), camera([0.0, 20.0, 120.0], [0.0, 0.0, 0.0]), config, progressive, |framebuffer, _| {
+        passes.push(framebuffer.costs.clone().unwrap());
+    });
+    assert_eq!(passes.len(), 2);
+    assert!(passes[0].iter().zip(passes[1].iter()).all(|(first, second)| second > first));
+    assert_eq!(last.colors, heatmap_colors(last.costs.as_ref().unwrap()));
+    assert!(last.normals.is_none());
+}
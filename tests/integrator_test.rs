@@ -0,0 +1,67 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use common::sphere_scene;
+
+mod common;
+
+fn render_sphere(integrator: Box<dyn Integrator + Send + Sync>) -> FrameBuffer {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.integrator = integrator;
+    config.aovs = AovConfig::from_passes(&[AovPass::Uv]);
+    render_framebuffer_with_config(sphere_scene(), image(33, 33), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config)
+}
+
+// Anything that can be hit is white, to check integrators can be written outside the crate
+#[derive(Clone)]
+struct SilhouetteIntegrator;
+
+impl Integrator for SilhouetteIntegrator {
+    fn trace(&self, scene: &Scene, ray: Ray) -> (f64, Color) {
+        match scene.root.trace(ray) {
+            Some(node_intersect) => (node_intersect.get_distance(), Color::WHITE),
+            None => (f64::INFINITY, Color::BLACK),
+        }
+    }
+}
+
+#[test]
+fn integrator_whitted_is_default() {
+    let default = render_sphere(RenderConfig::default().integrator);
+    let whitted = render_sphere(WhittedIntegrator::new());
+    assert_eq!(default.colors, whitted.colors);
+    assert!(default.colors[default.index(16, 16)].red > 0.0);
+}
+
+#[test]
+fn integrator_debug_views() {
+    let middle = 16 * 33 + 16;
+    let corner = 0;
+
+    let normals = render_sphere(DebugIntegrator::new(DebugView::Normal));
+    // Facing the camera, so mostly +z
+    assert!(normals.colors[middle].blue > 0.95, "normal was {:?}", normals.colors[middle]);
+    assert_eq!(normals.colors[corner], Color::BLACK);
+
+    let uvs = render_sphere(DebugIntegrator::new(DebugView::Uv));
+    let uv = uvs.uvs.as_ref().unwrap()[middle];
+    assert_eq!(uvs.colors[middle], Color::new(uv.x, uv.y, 0.0));
+
+    let depths = render_sphere(DebugIntegrator::with_far(300.0));
+    assert!((depths.colors[middle].red - 0.5).abs() < 0.02, "depth was {:?}", depths.colors[middle]);
+    assert!((depths.distances[middle] - 150.0).abs() < 1.0);
+    assert_eq!(depths.distances[corner], f64::INFINITY);
+
+    let silhouette = render_sphere(Box::new(SilhouetteIntegrator));
+    assert_eq!(silhouette.colors[middle], Color::WHITE);
+    assert_eq!(silhouette.colors[corner], Color::BLACK);
+    let mut description = SceneDescription::parse("").unwrap();
+    description.render.integrator = Box::new(SilhouetteIntegrator);
+    assert!(description.to_source().is_err());
+
+    write_to_png(normals.to_rgb_image(), "output/integrator_normal").unwrap();
+    write_to_png(uvs.to_rgb_image(), "output/integrator_uv").unwrap();
+    write_to_png(depths.to_rgb_image(), "output/integrator_depth").unwrap();
+}
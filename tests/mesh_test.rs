@@ -43,7 +43,7 @@ fn glass() -> Box<CompositeShader> {
 
 fn frosted_glass() -> Box<ChainShader> {
     ChainShader::from_shaders(vec!(
-        NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/concrete.jpg")),
+        NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/concrete.jpg").unwrap()),
         CompositeShader::from_shaders(vec!(
             (0.1, ReflectionShader::new(Color::WHITE)),
             (0.9, TranslucentShader::new(Color::WHITE, 1.517)),
@@ -70,8 +70,8 @@ fn mesh_basic() {
                 }),
                 geometry_node(
                     translation(0.0, 0.0, 0.0)*scaling(200.0, 200.0, 200.0)*rotation(Axis::Y, -30.0),
-                    texture_phong_material("assets/images/textures/test3.png", 1.0, 0.0, 0.0, 1.0),
-                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")),
+                    texture_phong_material("assets/images/textures/test3.png", 1.0, 0.0, 0.0, 1.0).unwrap(),
+                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")).unwrap(),
                     vec!(),
                 ),
                 /*
@@ -89,7 +89,7 @@ fn mesh_basic() {
     let mut config = RenderConfig::default();
     config.anti_alias = false;
     let image = render_with_config(scene, image(256, 256), camera([-310.0, 200.0, 300.0], [0.0, 0.0, 0.0]), config);
-    write_to_png( image, "output/mesh_basic");
+    write_to_png( image, "output/mesh_basic").unwrap();
 }
 
 #[test]
@@ -112,7 +112,7 @@ fn glass_monkey() {
                 geometry_node(
                     translation(0.0, 0.0, 0.0)*scaling(200.0, 200.0, 200.0)*rotation(Axis::Y, -30.0),
                     glass(),
-                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")),
+                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")).unwrap(),
                     vec!(),
                 ),
                 /*
@@ -130,16 +130,16 @@ fn glass_monkey() {
     let mut config = RenderConfig::default();
     config.anti_alias = true;
     let image = render_with_config(scene, image(1000, 1000), camera([-310.0, 200.0, 300.0], [0.0, 0.0, 0.0]), config);
-    write_to_png( image, "output/glass_monkey");
+    write_to_png( image, "output/glass_monkey").unwrap();
 }
 
 fn ceramic_tile() -> Box<ChainShader> {
     ChainShader::from_shaders(vec!(
-        NormalMapShader::new(BumpMap::from_path("assets/images/bump_maps/tiles.jpg", 10.0)),
+        NormalMapShader::new(BumpMap::from_path("assets/images/bump_maps/tiles.jpg", 10.0).unwrap()),
         CompositeShader::from_shaders(vec!(
             (0.8, MixShader::from_shaders(vec!(
                     PhongShader::new(Color::WHITE*0.5, Color::WHITE*0.5, Color::BLACK, 2.0),
-                    TextureShader::new(ImageTexture::from_path("assets/images/textures/marble.png")),
+                    TextureShader::new(ImageTexture::from_path("assets/images/textures/marble.png").unwrap()),
                   ))
             ),
             (0.2, ReflectionShader::new(Color::WHITE))
@@ -149,17 +149,17 @@ fn ceramic_tile() -> Box<ChainShader> {
 
 fn concrete(color: Color) -> Box<ChainShader> {
     ChainShader::from_shaders(vec!(
-        NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/concrete.jpg")),
+        NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/concrete.jpg").unwrap()),
         MixShader::from_shaders(vec!(
             PhongShader::new(color*1.0, Color::WHITE*0.0, Color::BLACK, 1.0),
-            TextureShader::new(ImageTexture::from_path("assets/images/textures/smooth_concrete.jpg")),
+            TextureShader::new(ImageTexture::from_path("assets/images/textures/smooth_concrete.jpg").unwrap()),
         ))
     ))
 }
 
 fn metallic() -> Box<ChainShader> {
     ChainShader::from_shaders(vec!(
-        NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/concrete.jpg")),
+        NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/concrete.jpg").unwrap()),
         CompositeShader::from_shaders(vec!(
             (0.3, ReflectionShader::new(Color::WHITE)),
             (0.7, PhongShader::new(Color::WHITE*0.2, Color::BLACK, Color::BLACK, 2.0)),
@@ -187,7 +187,7 @@ fn monkey_1() {
                 geometry_node(
                     translation(0.0, 0.0, 0.0)*scaling(150.0, 150.0, 150.0)*rotation(Axis::Y, -30.0),
                     concrete(Color::WHITE),
-                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")),
+                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")).unwrap(),
                     vec!(),
                 ),
                 /*
@@ -203,7 +203,7 @@ fn monkey_1() {
     );
 
     let image = render(scene, image(1000, 1000), camera([-310.0, 200.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/monkey_1");
+    write_to_png( image, "output/monkey_1").unwrap();
 }
 
 #[test]
@@ -226,7 +226,7 @@ fn monkey_2() {
                 geometry_node(
                     translation(0.0, 0.0, 0.0)*scaling(150.0, 150.0, 150.0)*rotation(Axis::Y, -30.0),
                     metallic(),
-                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")),
+                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")).unwrap(),
                     vec!(),
                 ),
                 /*
@@ -242,7 +242,7 @@ fn monkey_2() {
     );
 
     let image = render(scene, image(1920, 1920), camera([-310.0, 200.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/monkey_2");
+    write_to_png( image, "output/monkey_2").unwrap();
 }
 
 #[test]
@@ -265,7 +265,7 @@ fn monkey_3() {
                 geometry_node(
                     translation(0.0, 0.0, 0.0)*scaling(150.0, 150.0, 150.0)*rotation(Axis::Y, -30.0),
                     frosted_glass(),
-                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")),
+                    Mesh::from_path(&Path::new("assets/models/monkey2.obj")).unwrap(),
                     vec!(),
                 ),
             ),
@@ -273,5 +273,5 @@ fn monkey_3() {
     );
 
     let image = render(scene, image(1920, 1920), camera([-310.0, 200.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/monkey_3");
+    write_to_png( image, "output/monkey_3").unwrap();
 }
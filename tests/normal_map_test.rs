@@ -27,7 +27,7 @@ fn default_material(color: Color) -> Box<PhongShader> {
 
 #[test]
 fn normal_map_room_1() {
-    let brick_shader = brick_shader();
+    let brick_shader = brick_shader().unwrap();
     let scene = build_scene(
         vec!(light1()),
         no_ambient(),
@@ -48,12 +48,12 @@ fn normal_map_room_1() {
     );
 
     let image = render(scene, image(1920, 1080), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/normal_map_room_1");
+    write_to_png( image, "output/normal_map_room_1").unwrap();
 }
 
 #[test]
 fn normal_map_room_2() {
-    let brick_shader = brick_shader();
+    let brick_shader = brick_shader().unwrap();
     let scene = build_scene(
         vec!(light2()),
         no_ambient(),
@@ -74,5 +74,5 @@ fn normal_map_room_2() {
     );
 
     let image = render(scene, image(1920, 1080), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/normal_map_room_2");
+    write_to_png( image, "output/normal_map_room_2").unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+extern crate raytracer;
+extern crate euler;
+extern crate snowflake;
+
+use raytracer::*;
+use euler::*;
+use snowflake::ProcessUniqueId;
+
+// A white room with a red left wall. A shelf under the light keeps every bit of
+// direct light off the floor, so anything lighting the floor has bounced off a wall.
+fn shaded_room() -> (Scene, ProcessUniqueId) {
+    let size = 200.0;
+    let white = Color::WHITE * 0.8;
+    let floor = create_wall(size*1.01, white, rotation(Axis::X, -90.0) * translation(0.0, 0.0, -size/2.0));
+    let floor_id = floor.get_id();
+
+    let mut room = SceneNode::new();
+    room.add_child(Box::new(floor));
+    room.add_child(Box::new(create_wall(size*1.01, white, rotation(Axis::X, 90.0) * translation(0.0, 0.0, -size/2.0))));
+    room.add_child(Box::new(create_wall(size*1.01, white, translation(0.0, 0.0, -size/2.0))));
+    room.add_child(Box::new(create_wall(size*1.01, white, rotation(Axis::X, 180.0) * translation(0.0, 0.0, -size/2.0))));
+    room.add_child(Box::new(create_wall(size*1.01, Color::new(0.8, 0.05, 0.05), rotation(Axis::Y, 90.0) * translation(0.0, 0.0, -size/2.0))));
+    room.add_child(Box::new(create_wall(size*1.01, white, rotation(Axis::Y, -90.0) * translation(0.0, 0.0, -size/2.0))));
+    room.add_child(Box::new(create_wall(120.0, white, translation(0.0, 60.0, 0.0) * rotation(Axis::X, -90.0))));
+
+    let mut scene = Scene::new();
+    scene.root = Box::new(room);
+    scene.add_light(Box::new(PointLight::new(dvec3!(0.0, 90.0, 0.0), Color::WHITE, 40000.0, (0.0, 0.0, 1.0))));
+    (scene, floor_id)
+}
+
+fn render_room(integrator: Box<dyn Integrator + Send + Sync>) -> (FrameBuffer, ProcessUniqueId) {
+    let (scene, floor_id) = shaded_room();
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.integrator = integrator;
+    config.samples = 64;
+    config.aovs = AovConfig::from_passes(&[AovPass::ObjectId]);
+    let framebuffer = render_framebuffer_with_config(scene, image(32, 32), camera([0.0, 20.0, 90.0], [0.0, -100.0, -20.0]), config);
+    (framebuffer, floor_id)
+}
+
+// Average color of the floor on the left and right half of the image
+fn floor_colors(framebuffer: &FrameBuffer, floor_id: ProcessUniqueId) -> (Color, Color) {
+    let mut totals = [(Color::BLACK, 0), (Color::BLACK, 0)];
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            if framebuffer.get_object(x, y) == Some(floor_id) {
+                let side = &mut totals[if x < framebuffer.width / 2 { 0 } else { 1 }];
+                side.0 += framebuffer.colors[framebuffer.index(x, y)];
+                side.1 += 1;
+            }
+        }
+    }
+    assert!(totals[0].1 > 0 && totals[1].1 > 0, "floor should be on both sides of the image");
+    (totals[0].0 / totals[0].1 as f64, totals[1].0 / totals[1].1 as f64)
+}
+
+#[test]
+fn path_tracing_indirect_light() {
+    let (whitted, floor_id) = render_room(WhittedIntegrator::new());
+    let (left, right) = floor_colors(&whitted, floor_id);
+    assert_eq!(left, Color::BLACK);
+    assert_eq!(right, Color::BLACK);
+
+    let (path_traced, floor_id) = render_room(PathIntegrator::new());
+    let (left, right) = floor_colors(&path_traced, floor_id);
+    assert!(left.luminance() > 0.01 && right.luminance() > 0.01, "floor should be lit by bounced light");
+
+    // The floor picks up color from the red wall next to it
+    assert!(left.red / left.green > right.red / right.green, "left {:?} right {:?}", left, right);
+    assert!(left.red > left.green);
+
+    write_to_png(whitted.to_rgb_image(), "output/path_tracing_whitted").unwrap();
+    write_to_png(path_traced.to_rgb_image(), "output/path_tracing_room").unwrap();
+}
+
+#[test]
+fn path_tracing_samplers_agree() {
+    // Spreading samples out evenly shouldn't change how bright the floor comes out on average
+    let mut floors = Vec::new();
+    for kind in SamplerKind::ALL.iter() {
+        let (scene, floor_id) = shaded_room();
+        let mut config = RenderConfig::default();
+        config.anti_alias = false;
+        config.integrator = PathIntegrator::new();
+        config.samples = 64;
+        config.sampler = *kind;
+        config.aovs = AovConfig::from_passes(&[AovPass::ObjectId]);
+        let framebuffer = render_framebuffer_with_config(scene, image(32, 32), camera([0.0, 20.0, 90.0], [0.0, -100.0, -20.0]), config);
+        let (left, right) = floor_colors(&framebuffer, floor_id);
+        floors.push((left + right).luminance());
+    }
+    for (kind, floor) in SamplerKind::ALL.iter().zip(floors.iter()) {
+        assert!((floor / floors[0] - 1.0).abs() < 0.1, "{} floor {} vs {}", kind.name(), floor, floors[0]);
+    }
+}
+
+#[test]
+fn path_tracing_scatter() {
+    let ray = Ray::new(dvec3!(0.0, 10.0, 0.0), dvec3!(0.0, -1.0, 0.0), 5);
+    let normal = dvec3!(0.0, 1.0, 0.0);
+    let mut total_cos = 0.0;
+    for _ in 0..2000 {
+        let bounce = ray.scatter_off(dvec3!(0.0, 0.0, 0.0), normal);
+        assert_eq!(bounce.get_depth(), 4);
+        assert!((bounce.direction.length() - 1.0).abs() < 1e-9);
+        assert!(bounce.direction.dot(normal) >= 0.0);
+        total_cos += bounce.direction.dot(normal);
+    }
+    // Cosine weighted directions average out to a cosine of 2/3
+    assert!((total_cos / 2000.0 - 2.0/3.0).abs() < 0.03, "average cosine {}", total_cos / 2000.0);
+
+    // Hitting the back of a surface bounces back to that side
+    let bounce = ray.scatter_off(dvec3!(0.0, 0.0, 0.0), -1.0 * normal);
+    assert!(bounce.direction.y > 0.0);
+}
@@ -54,7 +54,7 @@ fn polyhedron_basic() {
                 }),
                 geometry_node(
                     scaling(50.0, 200.0, 100.0)*rotation(Axis::Y, -45.0),
-                    texture_phong_material("assets/images/textures/granite.jpg", 0.5, 0.5, 0.0, 2.0),
+                    texture_phong_material("assets/images/textures/granite.jpg", 0.5, 0.5, 0.0, 2.0).unwrap(),
                     triangular_prism(),
                     vec!(),
                 ),
@@ -63,7 +63,7 @@ fn polyhedron_basic() {
     );
 
     let image = render(scene, image(512, 512), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/polyhedron_basic");
+    write_to_png( image, "output/polyhedron_basic").unwrap();
 }
 
 #[test]
@@ -85,7 +85,7 @@ fn octahedron_basic() {
                 }),
                 geometry_node(
                     translation(0.0, 0.0, 0.0),
-                    texture_phong_material("assets/images/textures/d8_num.png", 1.0, 0.0, 0.0, 2.0),
+                    texture_phong_material("assets/images/textures/d8_num.png", 1.0, 0.0, 0.0, 2.0).unwrap(),
                     Polyhedron::octahedron(200.0),
                     vec!(),
                 ),
@@ -94,8 +94,8 @@ fn octahedron_basic() {
     );
 
     let img = render(scene.clone(), image(512, 512), camera([-300.0, 0.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( img, "output/octahedron_basic_01");
+    write_to_png( img, "output/octahedron_basic_01").unwrap();
 
     let img = render(scene.clone(), image(512, 512), camera([300.0, 0.0, -300.0], [0.0, 0.0, 0.0]));
-    write_to_png( img, "output/octahedron_basic_02");
+    write_to_png( img, "output/octahedron_basic_02").unwrap();
 }
@@ -0,0 +1,110 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use std::time::Duration;
+
+mod common;
+
+// The shared red sphere with some ambient light
+fn sphere_scene() -> Scene {
+    let mut scene = common::sphere_scene();
+    scene.ambient_light = AmbientLight::new(Color::WHITE, 0.2);
+    scene
+}
+
+fn render_passes(integrator: Box<dyn Integrator + Send + Sync>, progressive: ProgressiveConfig) -> (FrameBuffer, Vec<ProgressiveStatus>) {
+    let mut config = RenderConfig::default();
+    config.integrator = integrator;
+    config.aovs = AovConfig::from_passes(&[AovPass::Depth]);
+    let mut statuses = Vec::new();
+    let framebuffer = render_progressive(sphere_scene(), image(24, 24), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config, progressive,
+                                         |framebuffer, status| {
+                                             assert_eq!(framebuffer.colors.len(), 24 * 24);
+                                             statuses.push(*status);
+                                         });
+    (framebuffer, statuses)
+}
+
+#[test]
+fn progressive_sample_count() {
+    let progressive = ProgressiveConfig { samples: 10, samples_per_pass: 4, ..ProgressiveConfig::default() };
+    let (framebuffer, statuses) = render_passes(WhittedIntegrator::new(), progressive);
+
+    // 4 + 4 + 2, with a callback after every pass
+    let samples: Vec<u32> = statuses.iter().map(|status| status.samples).collect();
+    assert_eq!(samples, vec![4, 8, 10]);
+    assert_eq!(statuses.iter().map(|status| status.pass).collect::<Vec<u32>>(), vec![1, 2, 3]);
+    assert!(statuses.iter().rev().skip(1).all(|status| !status.finished));
+    assert!(statuses.last().unwrap().finished);
+
+    // Distances and AOVs come from the ray through the middle of the pixel
+    // Pixel centres sit half a pixel down and to the right, so this one looks straight at the sphere
+    let middle = framebuffer.index(11, 11);
+    assert!((framebuffer.distances[middle] - 150.0).abs() < 1e-6, "{}", framebuffer.distances[middle]);
+    assert!((framebuffer.depths.as_ref().unwrap()[middle] - 150.0).abs() < 1e-6);
+    assert_eq!(framebuffer.distances[0], f64::INFINITY);
+    assert!(framebuffer.colors[middle].red > framebuffer.colors[middle].green);
+}
+
+#[test]
+fn progressive_noise_threshold() {
+    // Samples that all agree have no noise at all
+    let mut samples = PixelSamples::default();
+    assert_eq!(samples.noise(), f64::INFINITY);
+    samples.add(Color::WHITE * 0.5);
+    samples.add(Color::WHITE * 0.5);
+    assert_eq!(samples.noise(), 0.0);
+    samples.add(Color::WHITE);
+    assert!(samples.noise() > 0.1);
+    assert_eq!(samples.mean(), Color::WHITE * (2.0 / 3.0));
+
+    // Red and green that are just as bright as each other are still noise
+    let mut samples = PixelSamples::default();
+    let red = Color::new(1.0, 0.0, 0.0);
+    let green = Color::new(0.0, red.luminance() / Color::new(0.0, 1.0, 0.0).luminance(), 0.0);
+    assert!((red.luminance() - green.luminance()).abs() < 1e-12);
+    for _ in 0..4 {
+        samples.add(red);
+        samples.add(green);
+    }
+    assert!(samples.noise() > 0.1);
+
+    // Up close the sphere fills the image, so there are no edges and every pixel settles quickly
+    let close_up = |noise_threshold| {
+        let mut config = RenderConfig::default();
+        config.progressive = Some(ProgressiveConfig { samples: 64, samples_per_pass: 4, noise_threshold: Some(noise_threshold), ..ProgressiveConfig::default() });
+        let mut statuses = Vec::new();
+        render_progressive(sphere_scene(), image(16, 16), camera([0.0, 0.0, 60.0], [0.0, 0.0, 0.0]), config.clone(), config.progressive.unwrap(),
+                           |_, status| statuses.push(*status));
+        statuses
+    };
+    let statuses = close_up(0.05);
+    assert_eq!(statuses.len(), 1);
+    assert!(statuses[0].noise <= 0.05);
+    assert!(statuses[0].finished);
+
+    let statuses = close_up(1e-12);
+    assert_eq!(statuses.last().unwrap().samples, 64);
+}
+
+#[test]
+fn progressive_time_limit() {
+    let progressive = ProgressiveConfig { samples: 1000000, time_limit: Some(Duration::from_millis(200)), ..ProgressiveConfig::default() };
+    let (_, statuses) = render_passes(PathIntegrator::new(), progressive);
+    let last = statuses.last().unwrap();
+    assert!(last.finished);
+    assert!(last.samples < 1000000);
+    assert!(last.elapsed >= Duration::from_millis(200));
+    assert!(statuses.iter().rev().skip(1).all(|status| status.elapsed < Duration::from_millis(200)));
+}
+
+#[test]
+fn progressive_render_config() {
+    // render_framebuffer_with_config hands over to the progressive renderer
+    let mut config = RenderConfig::default();
+    config.progressive = Some(ProgressiveConfig { samples: 8, ..ProgressiveConfig::default() });
+    let framebuffer = render_framebuffer_with_config(sphere_scene(), image(24, 24), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config);
+    assert!(framebuffer.colors[framebuffer.index(12, 12)].red > 0.0);
+    write_to_png(framebuffer.to_rgb_image(), "output/progressive_sphere").unwrap();
+}
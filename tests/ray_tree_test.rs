@@ -0,0 +1,107 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+// A glass ball in front of a lit wall, so the ray through the middle splits at the glass
+// and the wall behind it casts shadow rays
+fn glass_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    let mut ball = SceneNode::new();
+    ball.set_primitive(Sphere::from_radius(30.0));
+    ball.set_material(TranslucentShader::new(Color::WHITE, 1.5));
+    root.add_child(Box::new(ball));
+    root.add_child(Box::new(create_wall(400.0, Color::WHITE*0.8, translation(0.0, 0.0, -60.0))));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(0.0, 80.0, 80.0), Color::WHITE, 200000.0, (0.0, 0.0, 1.0))));
+    scene
+}
+
+fn glass_config() -> RenderConfig {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.recursion_depth = 4;
+    config
+}
+
+fn glass_tree(x: u32, y: u32) -> RayNode {
+    trace_pixel_tree(&glass_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), &glass_config(), x, y)
+}
+
+#[test]
+fn ray_tree_glass() {
+    let tree = glass_tree(16, 12);
+    assert_eq!(tree.kind, RayKind::Camera);
+    assert_eq!(tree.depth, 4);
+    assert!(tree.hit_id.is_some());
+    assert!((tree.distance - 70.0).abs() < 3.0, "{}", tree.distance);
+
+    // The glass sends off a reflected and a transmitted ray, weighted by how much light goes each way
+    let fresnel = tree.fresnel.unwrap();
+    assert!((fresnel.reflected + fresnel.transmitted - 1.0).abs() < 1e-9);
+    assert!(fresnel.transmitted > fresnel.reflected);
+    let kinds: Vec<RayKind> = tree.children.iter().map(|child| child.kind).collect();
+    assert!(kinds.contains(&RayKind::Reflected) && kinds.contains(&RayKind::Transmitted), "{:?}", kinds);
+    assert!(tree.children.iter().all(|child| child.depth == 3 && child.origin == tree.hit_point.unwrap()));
+
+    // Going through the ball reaches the wall, which checks for the light
+    let shadows: Vec<&RayNode> = tree.iter().into_iter().filter(|node| node.kind == RayKind::Shadow).collect();
+    assert!(!shadows.is_empty());
+    assert!(shadows.iter().all(|shadow| shadow.children.is_empty()));
+    assert_eq!(tree.count(), tree.iter().len());
+    assert!(tree.count() > tree.children.len() + 1);
+
+    // Off the ball it's just the wall and its shadow ray
+    let wall = glass_tree(0, 0);
+    assert!(wall.fresnel.is_none());
+    assert_eq!(wall.children.iter().map(|child| child.kind).collect::<Vec<RayKind>>(), vec![RayKind::Shadow]);
+    assert_eq!(wall.children[0].color, Color::WHITE);
+}
+
+#[test]
+fn ray_tree_matches_render() {
+    for &(x, y) in [(16, 12), (10, 8), (0, 0)].iter() {
+        let tree = glass_tree(x, y);
+        let pixel = render_pixel(glass_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), glass_config(), x, y);
+        assert_eq!(tree.color, pixel.color);
+        assert_eq!(tree.distance, pixel.distance);
+    }
+
+    // Path tracing records the one path the first sample took
+    let mut config = glass_config();
+    config.integrator = PathIntegrator::new();
+    let tree = trace_pixel_tree(&glass_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), &config, 16, 12);
+    assert_eq!(tree.kind, RayKind::Camera);
+    assert!(tree.children.iter().filter(|child| child.kind != RayKind::Shadow).count() <= 1);
+
+    // Views that don't trace through the scene still get the camera ray
+    config.integrator = integrator_from_name("normal").unwrap();
+    let tree = trace_pixel_tree(&glass_scene(), image(32, 24), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), &config, 16, 12);
+    assert_eq!(tree.count(), 1);
+    assert!(tree.hit_id.is_some());
+}
+
+#[test]
+fn ray_tree_output() {
+    let tree = glass_tree(16, 12);
+    let text = tree.to_text();
+    assert_eq!(text.lines().count(), tree.count());
+    assert!(text.starts_with("camera "));
+    assert!(text.contains("\n  transmitted ") && text.contains("fresnel reflected"));
+
+    let json = tree.to_json();
+    assert!(json.starts_with("{\"kind\": \"camera\""));
+    assert_eq!(json.matches('{').count(), json.matches('}').count());
+    assert_eq!(json.matches('[').count(), json.matches(']').count());
+    assert_eq!(json.matches("\"kind\"").count(), tree.count());
+    assert!(!json.contains("inf") && !json.contains("NaN"));
+
+    // Nothing is recorded outside of record_ray_tree
+    let scene = glass_scene();
+    let (color, recorded) = record_ray_tree(|| scene.cast_ray(Ray::new(dvec3!(0.0, 0.0, 100.0), dvec3!(0.0, 0.0, -1.0), 2)));
+    assert_eq!(recorded.unwrap().color, color);
+    scene.cast_ray(Ray::new(dvec3!(0.0, 0.0, 100.0), dvec3!(0.0, 0.0, -1.0), 2));
+    assert!(record_ray_tree(|| ()).1.is_none());
+}
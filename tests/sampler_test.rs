@@ -0,0 +1,116 @@
+extern crate raytracer;
+
+use raytracer::*;
+
+// The strata the first count samples of a pixel land in, for one dimension
+fn strata_1d(sampler: &mut dyn Sampler, dimension: usize, count: u32) -> Vec<usize> {
+    (0..count).map(|index| {
+        sampler.start_sample(5, 9, index);
+        for _ in 0..dimension {
+            sampler.next_1d();
+        }
+        (sampler.next_1d() * count as f64) as usize
+    }).collect()
+}
+
+fn each_once(mut strata: Vec<usize>) -> bool {
+    strata.sort();
+    strata.iter().enumerate().all(|(i, stratum)| i == *stratum)
+}
+
+#[test]
+fn sampler_repeats_samples() {
+    for kind in SamplerKind::ALL.iter() {
+        let mut sampler = kind.create(7, 16);
+        let mut sample = |x: u32, y: u32, index: u32| -> Vec<f64> {
+            sampler.start_sample(x, y, index);
+            let mut values = Vec::new();
+            for _ in 0..20 {
+                let (u, v) = sampler.next_2d();
+                values.extend_from_slice(&[u, v, sampler.next_1d()]);
+            }
+            values
+        };
+        let first = sample(3, 4, 5);
+        assert!(first.iter().all(|value| (0.0..1.0).contains(value)), "{} {:?}", kind.name(), first);
+        sample(10, 1, 2);
+        assert_eq!(sample(3, 4, 5), first, "{} should only depend on the pixel and sample", kind.name());
+        assert!(sample(4, 4, 5) != first);
+        assert!(sample(3, 4, 6) != first);
+        assert!(kind.create(8, 16).as_mut().next_1d() >= 0.0);
+    }
+
+    assert_eq!(SamplerKind::from_name("sobol"), Some(SamplerKind::Sobol));
+    assert_eq!(SamplerKind::from_name("random"), None);
+    assert_eq!(SamplerKind::default(), SamplerKind::Independent);
+}
+
+#[test]
+fn sampler_stratifies() {
+    // Every one of the first n samples lands in a different nth of each dimension
+    for kind in [SamplerKind::Stratified, SamplerKind::Sobol].iter() {
+        let mut sampler = kind.create(3, 16);
+        for dimension in 0..6 {
+            assert!(each_once(strata_1d(sampler.as_mut(), dimension, 16)), "{} dimension {}", kind.name(), dimension);
+        }
+    }
+    // Halton uses base 2 for the first dimension and 3 for the second
+    let mut halton = SamplerKind::Halton.create(3, 16);
+    assert!(each_once(strata_1d(halton.as_mut(), 0, 16)));
+    assert!(each_once(strata_1d(halton.as_mut(), 1, 9)));
+
+    // Pairs are spread over a grid
+    for kind in [SamplerKind::Stratified, SamplerKind::Sobol].iter() {
+        let mut sampler = kind.create(3, 16);
+        let cells: Vec<usize> = (0..16).map(|index| {
+            sampler.start_sample(2, 2, index);
+            let (u, v) = sampler.next_2d();
+            (v * 4.0) as usize * 4 + (u * 4.0) as usize
+        }).collect();
+        assert!(each_once(cells), "{}", kind.name());
+    }
+
+    // Pixels that get more samples than expected still get numbers in range
+    let mut stratified = SamplerKind::Stratified.create(3, 4);
+    let extra = strata_1d(stratified.as_mut(), 0, 12);
+    assert!(extra.iter().all(|stratum| *stratum < 12));
+}
+
+#[test]
+fn sampler_converges_faster() {
+    // Area of the square under the line u + v = 1 is 0.5, worked out from 16 samples a pixel over lots of pixels
+    let error = |kind: SamplerKind| -> f64 {
+        let mut sampler = kind.create(11, 16);
+        let mut squared_error = 0.0;
+        for pixel in 0..400 {
+            let mut under = 0;
+            for index in 0..16 {
+                sampler.start_sample(pixel % 20, pixel / 20, index);
+                let (u, v) = sampler.next_2d();
+                if u + v < 1.0 {
+                    under += 1;
+                }
+            }
+            squared_error += (under as f64 / 16.0 - 0.5).powi(2);
+        }
+        (squared_error / 400.0).sqrt()
+    };
+    let independent = error(SamplerKind::Independent);
+    for kind in [SamplerKind::Stratified, SamplerKind::Halton, SamplerKind::Sobol].iter() {
+        let spread = error(*kind);
+        assert!(spread < independent * 0.6, "{} error {} vs independent {}", kind.name(), spread, independent);
+    }
+}
+
+#[test]
+fn sampler_thread_sampler() {
+    // Without a sampler set, numbers are just random
+    let value = sample_1d();
+    assert!((0.0..1.0).contains(&value));
+
+    set_thread_sampler(SamplerKind::Sobol.create(1, 16));
+    start_pixel_sample(1, 2, 3);
+    let first = (sample_2d(), sample_1d());
+    start_pixel_sample(1, 2, 3);
+    assert_eq!((sample_2d(), sample_1d()), first);
+}
@@ -0,0 +1,358 @@
+extern crate raytracer;
+extern crate euler;
+extern crate image;
+
+use raytracer::*;
+use euler::*;
+use std::time::Duration;
+
+fn assert_error_at(source: &str, line: usize, column: usize) {
+    match SceneDescription::parse(source) {
+        Err(SceneFileError::Invalid{line: l, column: c, message}) => {
+            assert_eq!((l, c), (line, column), "wrong position for error: {}", message);
+        },
+        Err(error) => panic!("expected a positioned error, got: {}", error),
+        Ok(_) => panic!("expected scene to fail to load"),
+    }
+}
+
+#[test]
+fn scene_file_mesh_basic() {
+    let description = SceneDescription::from_path("assets/scenes/mesh_basic.scene").unwrap();
+    assert_eq!(description.image.width, 256);
+    assert!(!description.render.anti_alias);
+    assert_eq!(description.camera.origin.x, -310.0);
+    assert_eq!(description.scene.lights.len(), 1);
+
+    let image = render_with_config(description.scene, description.image, description.camera, description.render);
+    write_to_png( image, "output/scene_file_mesh_basic").unwrap();
+}
+
+#[test]
+fn scene_file_composite_shapes() {
+    let source = r#"
+        image 128 128
+        render { anti_alias false; threads 4 }
+        camera { origin 0 120 200; target 0 0 0 }
+        ambient { color 1 1 1; power 0.2 }
+        light square { position 0 150 0; size 50; power 1500000; falloff 0 0 12.57 }
+        node {
+            primitive subtract {
+                transform { rotate y 45 }
+                shape cube 100
+                shape sphere 65
+            }
+            material composite {
+                layer 0.8 { material phong { diffuse 0 0 0.5; shininess 4 } }
+                layer 0.2 { material reflection { color 1 1 1 } }
+            }
+        }
+        node {
+            transform { translate 0 -60 0 }
+            primitive plane { normal 0 1 0 }
+            material translucent { refractive_index 1.5 }
+        }
+    "#;
+    let description = SceneDescription::parse(source).unwrap();
+    assert_eq!(description.render.num_threads, 4);
+    let image = render_with_config(description.scene, description.image, description.camera, description.render);
+    write_to_png( image, "output/scene_file_composite_shapes").unwrap();
+}
+
+#[test]
+fn scene_file_errors() {
+    assert_error_at("image 10 10\nlamp point {}", 2, 1);
+    assert_error_at("node {\n    primitive sphere \"big\"\n}", 2, 22);
+    assert_error_at("node {\n    primitive blob 1\n}", 2, 15);
+    assert_error_at("camera { origin 0 0 }", 1, 10);
+    assert_error_at("node {\n  material phong { diffuse 1 1 1 }\n", 3, 1);
+    assert_error_at("image 10 \"ten", 1, 10);
+    assert_error_at("node { material texture \"does/not/exist.png\" }", 1, 25);
+    assert_error_at("node { primitive mesh \"does/not/exist.obj\" }", 1, 23);
+    assert_error_at("node { material mix {} }", 1, 8);
+    assert_error_at("node { material reflection 1 1 1 }", 1, 8);
+    assert_error_at("node { primitive plane { normal 0 1 0; tangent 0 -2 0 } }", 1, 8);
+    assert_error_at("node { primitive plane { normal 0 1 0; tangent 1e-12 1 0 } }", 1, 8);
+
+    match SceneDescription::from_path("does/not/exist.scene") {
+        Err(SceneFileError::Io(_)) => (),
+        _ => panic!("expected an io error"),
+    }
+}
+
+#[test]
+fn scene_file_round_trip() {
+    let mut floor = ChainShader::new();
+    floor.push_shader(NormalMapShader::new(NormalMap::from_path("assets/images/normal_maps/brick_wall_01.png").unwrap()));
+    floor.push_shader(NormalMapShader::new(BumpMap::from_path("assets/images/bump_maps/tiles.jpg", 2.0).unwrap()));
+    floor.push_shader(basic_diffuse(Color::WHITE));
+
+    let room = create_room_from_material(256.0, RoomMaterialScheme {
+        ceiling: basic_diffuse(Color::WHITE),
+        floor,
+        front: texture_phong_material("assets/images/textures/wood_boards.jpg", 0.7, 0.2, 0.01, 4.0).unwrap(),
+        back: basic_diffuse(Color::WHITE),
+        left: basic_diffuse(Color::RED),
+        right: basic_diffuse(Color::BLUE),
+    });
+    let mut tetra = SceneNode::new();
+    tetra.set_primitive(reuleaux_tetrahedron(rotation(Axis::Y, 30.0), 40.0));
+    tetra.set_material(PhongShader::new(Color::GREEN*0.5, Color::WHITE*0.5, Color::GREEN*0.01, 4.0));
+    let mut sphere = create_comp_sphere(30.0, translation(60.0, -80.0, 0.0), Color::WHITE);
+    sphere.add_child(Box::new(create_translucent_cube(20.0, translation(0.0, 50.0, 0.0), Color::WHITE, 1.5)));
+
+    let mut root = SceneNode::new();
+    root.add_child(room);
+    root.add_child(Box::new(tetra));
+    root.add_child(Box::new(sphere));
+    let lights: Vec<Box<dyn Lightable + Send + Sync>> = vec!(
+        Box::new(PointLight::new(dvec3!(0.0, 100.0, 0.0), Color::WHITE, 100000.0, (0.0, 0.0, 12.57))),
+        Box::new(DirectionLight::new(dvec3!(0.0, -1.0, 0.0), Color::WHITE, 0.1)),
+    );
+    let scene = build_scene(lights, AmbientLight::new(Color::WHITE, 0.1), None, Box::new(root));
+
+    let mut description = SceneDescription::from_scene(scene);
+    description.image = square_image(128);
+    description.camera = camera([0.0, 0.0, 120.0], [0.0, 0.0, 0.0]);
+    description.render.anti_alias = false;
+
+    let source = description.to_source().unwrap();
+    let reloaded = SceneDescription::parse(&source).unwrap();
+    assert_eq!(reloaded.to_source().unwrap(), source);
+    assert_eq!(reloaded.scene.lights.len(), 2);
+
+    // The reloaded scene renders exactly like the one it was written out from
+    let tone_mapping = reloaded.render.tone_mapping;
+    let original = render_framebuffer_with_config(description.scene, description.image, description.camera, description.render);
+    let framebuffer = render_framebuffer_with_config(reloaded.scene, reloaded.image, reloaded.camera, reloaded.render);
+    assert!(framebuffer.colors == original.colors);
+    assert!(framebuffer.distances == original.distances);
+    write_to_png(framebuffer.tone_map(tone_mapping), "output/scene_file_round_trip").unwrap();
+
+    // Textures made in memory have no path to write out
+    let mut node = SceneNode::new();
+    node.set_primitive(Sphere::from_radius(1.0));
+    node.set_material(TextureShader::new(ImageTexture::new(image::RgbImage::new(4, 4))));
+    let description = SceneDescription::from_scene(build_scene(vec!(), no_ambient(), None, Box::new(node)));
+    match description.to_source() {
+        Err(SceneFileError::Unsupported(_)) => (),
+        _ => panic!("expected an in-memory texture to be unsupported"),
+    }
+}
+
+#[test]
+fn scene_file_ambient_occlusion() {
+    let source = "render { integrator ambient_occlusion 25 }\n\
+                  node { primitive sphere 10; material ambient_occlusion { ambient 0.2 0.2 0.2; radius 30; samples 8 } }";
+    let description = SceneDescription::parse(source).unwrap();
+    let written = description.to_source().unwrap();
+    assert!(written.contains("integrator ambient_occlusion 25"));
+    assert!(written.contains("material ambient_occlusion"));
+    assert!(written.contains("radius 30"));
+    assert!(SceneDescription::parse(&written).is_ok());
+
+    assert!(integrator_from_name("ambient_occlusion").is_some());
+    assert!(SceneDescription::parse("render { integrator ambient_occlusion 0 }").is_err());
+    assert!(SceneDescription::parse("node { material ambient_occlusion { shininess 2 } }").is_err());
+}
+
+#[test]
+fn scene_file_anti_alias() {
+    let description = SceneDescription::parse("render { aa_noise_threshold 0.01; aa_min_samples 8; aa_max_samples 128 }").unwrap();
+    assert_eq!(description.render.aa_noise_threshold, 0.01);
+    assert_eq!(description.render.aa_min_samples, 8);
+    assert_eq!(description.render.aa_max_samples, 128);
+    let written = description.to_source().unwrap();
+    assert!(written.contains("aa_max_samples 128"));
+    assert_eq!(SceneDescription::parse(&written).unwrap().render.aa_min_samples, 8);
+
+    assert!(SceneDescription::parse("render { aa_min_samples 0 }").is_err());
+    assert!(SceneDescription::parse("render { aa_min_samples 16; aa_max_samples 8 }").is_err());
+    assert!(SceneDescription::parse("render { aa_rays 5 }").is_err());
+
+    // Settings changed after loading, eg. from the command line, are checked the same way
+    let mut config = description.render.clone();
+    assert!(config.check().is_ok());
+    config.aa_max_samples = 4;
+    assert!(config.check().is_err());
+}
+
+#[test]
+fn scene_file_aovs() {
+    let description = SceneDescription::parse("render { aovs depth albedo }\nnode { primitive sphere 50 }").unwrap();
+    assert!(description.render.aovs == AovConfig::from_passes(&[AovPass::Depth, AovPass::Albedo]));
+    assert!(description.to_source().unwrap().contains("aovs depth albedo"));
+    assert!(SceneDescription::parse("render { aovs shadows }").is_err());
+}
+
+#[test]
+fn scene_file_encoding() {
+    let source = "node {\n    primitive sphere 1\n    material texture \"assets/images/textures/wood_boards.jpg\" { encoding linear }\n}\n\
+                  node {\n    primitive sphere 1\n    material normal_map \"assets/images/normal_maps/brick_wall_01.png\" { encoding srgb }\n}\n\
+                  node {\n    primitive sphere 1\n    material texture \"assets/images/textures/wood_boards.jpg\"\n}\n";
+    let written = SceneDescription::parse(source).unwrap().to_source().unwrap();
+    assert!(written.contains("encoding linear"));
+    assert!(written.contains("encoding srgb"));
+    assert_eq!(written.matches("encoding").count(), 2);
+    assert!(SceneDescription::parse("background \"assets/images/textures/wood_boards.jpg\" { encoding gamma }").is_err());
+}
+
+#[test]
+fn scene_file_crop() {
+    let description = SceneDescription::parse("image 64 48\nrender { crop 8 4 16 12 }").unwrap();
+    assert_eq!(description.render.crop, Some(Tile::new(8, 4, 16, 12)));
+    let reread = SceneDescription::parse(&description.to_source().unwrap()).unwrap();
+    assert_eq!(reread.render.crop, description.render.crop);
+    assert_eq!(SceneDescription::parse("").unwrap().render.crop, None);
+
+    // The image can come after the render settings
+    assert!(SceneDescription::parse("render { crop 60 4 16 12 }\nimage 64 48").is_err());
+    assert!(SceneDescription::parse("render { crop 0 0 0 12 }").is_err());
+    assert!(SceneDescription::parse("render { crop 8 4 16 }").is_err());
+}
+
+#[test]
+fn scene_file_denoise() {
+    let description = SceneDescription::parse("render { denoise { iterations 3; color_sigma 0.2 } }").unwrap();
+    let config = description.render.denoise.unwrap();
+    assert_eq!(config.iterations, 3);
+    assert_eq!(config.color_sigma, 0.2);
+    assert_eq!(config.normal_sigma, DenoiseConfig::default().normal_sigma);
+    let reread = SceneDescription::parse(&description.to_source().unwrap()).unwrap();
+    assert_eq!(reread.render.denoise, Some(config));
+
+    assert_eq!(SceneDescription::parse("render { denoise {} }").unwrap().render.denoise, Some(DenoiseConfig::default()));
+    assert_eq!(SceneDescription::parse("").unwrap().render.denoise, None);
+    assert!(SceneDescription::parse("render { denoise { depth_sigma 0 } }").is_err());
+    assert!(SceneDescription::parse("render { denoise { radius 2 } }").is_err());
+}
+
+#[test]
+fn scene_file_filter() {
+    let description = SceneDescription::parse("render { filter mitchell 1.5 }").unwrap();
+    assert_eq!(description.render.filter, PixelFilter::new(FilterKind::Mitchell, 1.5));
+    let reread = SceneDescription::parse(&description.to_source().unwrap()).unwrap();
+    assert_eq!(reread.render.filter, description.render.filter);
+
+    assert_eq!(SceneDescription::parse("render { filter gaussian }").unwrap().render.filter, PixelFilter::new(FilterKind::Gaussian, 1.5));
+    assert_eq!(SceneDescription::parse("").unwrap().render.filter, PixelFilter::default());
+    assert!(SceneDescription::parse("render { filter sinc }").is_err());
+    assert!(SceneDescription::parse("render { filter tent 0 }").is_err());
+    assert!(SceneDescription::parse("render { filter tent 1 2 }").is_err());
+}
+
+#[test]
+fn scene_file_heatmap() {
+    let description = SceneDescription::parse("render { heatmap time }").unwrap();
+    assert_eq!(description.render.heatmap, Some(CostMetric::Time));
+    let reread = SceneDescription::parse(&description.to_source().unwrap()).unwrap();
+    assert_eq!(reread.render.heatmap, Some(CostMetric::Time));
+    assert_eq!(SceneDescription::parse("").unwrap().render.heatmap, None);
+    assert!(SceneDescription::parse("render { heatmap rays }").is_err());
+}
+
+#[test]
+fn scene_file_integrator() {
+    for name in ["whitted", "path", "normal", "uv", "depth"].iter() {
+        let description = SceneDescription::parse(&format!("render {{ integrator {} }}", name)).unwrap();
+        assert!(description.to_source().unwrap().contains(&format!("integrator {}", name)));
+        assert!(integrator_from_name(name).is_some());
+    }
+
+    let description = SceneDescription::parse("render { integrator depth 500 }").unwrap();
+    assert!(description.to_source().unwrap().contains("integrator depth 500"));
+    assert!(SceneDescription::parse(&description.to_source().unwrap()).is_ok());
+
+    assert!(SceneDescription::parse("render { integrator photon_map }").is_err());
+    assert!(SceneDescription::parse("render { integrator depth -1 }").is_err());
+    assert!(SceneDescription::parse("render { integrator path 5 }").is_err());
+    assert!(integrator_from_name("photon_map").is_none());
+}
+
+#[test]
+fn scene_file_path_tracing() {
+    let description = SceneDescription::parse("render { integrator path; samples 32 }").unwrap();
+    assert!(description.render.integrator.is_random());
+    assert_eq!(description.render.samples, 32);
+    assert!(!SceneDescription::parse("").unwrap().render.integrator.is_random());
+
+    let source = description.to_source().unwrap();
+    assert!(source.contains("integrator path"));
+    assert!(source.contains("samples 32"));
+    assert!(SceneDescription::parse("render { samples 0 }").is_err());
+}
+
+#[test]
+fn scene_file_progressive() {
+    let description = SceneDescription::parse("render { progressive { samples 256; samples_per_pass 4; time_limit 1.5; noise_threshold 0.01 } }").unwrap();
+    let progressive = description.render.progressive.unwrap();
+    assert_eq!(progressive.samples, 256);
+    assert_eq!(progressive.samples_per_pass, 4);
+    assert_eq!(progressive.time_limit, Some(Duration::from_millis(1500)));
+    assert_eq!(progressive.noise_threshold, Some(0.01));
+
+    let written = description.to_source().unwrap();
+    assert!(written.contains("time_limit 1.5"));
+    assert_eq!(SceneDescription::parse(&written).unwrap().render.progressive, Some(progressive));
+
+    let defaults = SceneDescription::parse("render { progressive {} }").unwrap();
+    assert_eq!(defaults.render.progressive, Some(ProgressiveConfig::default()));
+    assert!(SceneDescription::parse("").unwrap().render.progressive.is_none());
+    assert!(!SceneDescription::parse("").unwrap().to_source().unwrap().contains("progressive"));
+
+    assert!(SceneDescription::parse("render { progressive { samples 0 } }").is_err());
+    assert!(SceneDescription::parse("render { progressive { time_limit 0 } }").is_err());
+    assert!(SceneDescription::parse("render { progressive { noise_threshold -1 } }").is_err());
+    assert!(SceneDescription::parse("render { progressive { passes 3 } }").is_err());
+}
+
+#[test]
+fn scene_file_sampler() {
+    let description = SceneDescription::parse("render { sampler halton }").unwrap();
+    assert_eq!(description.render.sampler, SamplerKind::Halton);
+    let reread = SceneDescription::parse(&description.to_source().unwrap()).unwrap();
+    assert_eq!(reread.render.sampler, SamplerKind::Halton);
+    assert_eq!(SceneDescription::parse("").unwrap().render.sampler, SamplerKind::Independent);
+    assert!(SceneDescription::parse("render { sampler random }").is_err());
+}
+
+#[test]
+fn scene_file_seed() {
+    let description = SceneDescription::parse("render { seed 1234 }").unwrap();
+    assert_eq!(description.render.seed, 1234);
+    let reread = SceneDescription::parse(&description.to_source().unwrap()).unwrap();
+    assert_eq!(reread.render.seed, 1234);
+    assert_eq!(SceneDescription::parse("").unwrap().render.seed, 0);
+    assert!(SceneDescription::parse("render { seed -1 }").is_err());
+}
+
+#[test]
+fn scene_file_tiles() {
+    let description = SceneDescription::parse("render { tile_size 48; tile_order hilbert; checkpoint \"renders/room.checkpoint\" }").unwrap();
+    assert_eq!(description.render.tile_size, 48);
+    assert_eq!(description.render.tile_order, TileOrder::Hilbert);
+    assert_eq!(description.render.checkpoint, Some(String::from("renders/room.checkpoint")));
+
+    let written = description.to_source().unwrap();
+    assert!(written.contains("tile_order hilbert"));
+    assert!(written.contains("checkpoint \"renders/room.checkpoint\""));
+    let reread = SceneDescription::parse(&written).unwrap();
+    assert_eq!(reread.render.tile_size, 48);
+    assert_eq!(reread.render.checkpoint, description.render.checkpoint);
+
+    let defaults = SceneDescription::parse("").unwrap();
+    assert_eq!(defaults.render.tile_order, TileOrder::Spiral);
+    assert!(defaults.render.checkpoint.is_none());
+    assert!(SceneDescription::parse("render { tile_size 0 }").is_err());
+    assert!(SceneDescription::parse("render { tile_order diagonal }").is_err());
+    assert!(SceneDescription::parse("render { checkpoint 5 }").is_err());
+}
+
+#[test]
+fn scene_file_tone_map() {
+    let description = SceneDescription::parse("render { tone_map aces; exposure -1.5 }").unwrap();
+    assert_eq!(description.render.tone_mapping, ToneMapping::new(ToneMapOperator::Aces, -1.5));
+    assert_eq!(SceneDescription::parse("").unwrap().render.tone_mapping, ToneMapping::default());
+    assert!(description.to_source().unwrap().contains("tone_map aces"));
+    assert!(SceneDescription::parse("render { tone_map gamma }").is_err());
+}
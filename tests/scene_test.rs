@@ -18,7 +18,7 @@ fn scene_test_1() {
     test_scene.root.add_child(Box::new(sphere));
 
     let image = render(test_scene, image(1280, 720), camera([0.0, 0.0, room_size/2.0], [0.0, -(room_size/2.0)*0.6, 0.0]));
-    write_to_png( image, "output/scene_1");
+    write_to_png( image, "output/scene_1").unwrap();
 }
 
 #[test]
@@ -33,7 +33,7 @@ fn scene_test_2() {
     test_scene.root.add_child(Box::new(sphere));
 
     let image = render(test_scene, image(1280, 720), camera([0.0, 0.0, room_size/2.0], [0.0, -(room_size/2.0)*0.6, 0.0]));
-    write_to_png( image, "output/scene_2");
+    write_to_png( image, "output/scene_2").unwrap();
 }
 
 #[test]
@@ -48,7 +48,7 @@ fn scene_test_3() {
     test_scene.root.add_child(Box::new(sphere));
 
     let image = render(test_scene, image(1280, 720), camera([0.0, -50.0, 0.0], [0.0, 0.0, -(room_size/2.0)]));
-    write_to_png( image, "output/scene_3");
+    write_to_png( image, "output/scene_3").unwrap();
 }
 
 #[test]
@@ -63,7 +63,7 @@ fn scene_test_4() {
     test_scene.root.add_child(Box::new(sphere));
 
     let image = render(test_scene, image(1280, 720), camera([0.0, 0.0, room_size/2.0], [0.0, -(room_size/2.0)*0.6, 0.0]));
-    write_to_png( image, "output/scene_4");
+    write_to_png( image, "output/scene_4").unwrap();
 }
 
 #[test]
@@ -78,7 +78,7 @@ fn scene_test_5() {
     test_scene.root.add_child(Box::new(sphere));
 
     let image = render(test_scene, image(512, 512), camera([0.0, 0.0, room_size/2.0], [0.0, -(room_size/2.0)*0.6, 0.0]));
-    write_to_png( image, "output/scene_5");
+    write_to_png( image, "output/scene_5").unwrap();
 }
 
 #[test]
@@ -94,7 +94,7 @@ fn refraction_test() {
         test_scene.root.add_child(Box::new(sphere));
 
         let image = render(test_scene, image(512, 512), camera([0.0, -50.0, room_size/2.0], [0.0, -50.0, 0.0]));
-        write_to_png( image, &format!("output/refraction/refraction_{:02}", i));
+        write_to_png( image, &format!("output/refraction/refraction_{:02}", i)).unwrap();
     }
 }
 
@@ -110,7 +110,7 @@ fn scene_light_1() {
         test_scene.add_light(Box::new(PointLight::new(dvec3!(-(room_size/3.0), (room_size/2.0)*0.6, -(room_size/3.0)), Color::new(1.0, 1.0, 1.0), 2000.0 * i as f64, (0.0, 0.0, 4.0*PI))));
 
         let image = render(test_scene, image(512, 512), camera([0.0, 0.0, 200.0], [0.0; 3]));
-        write_to_png( image, &format!("output/lighting/light_scene_{:02}", i));
+        write_to_png( image, &format!("output/lighting/light_scene_{:02}", i)).unwrap();
     }
 }
 
@@ -123,11 +123,11 @@ fn background_1() {
     scene.root.add_child(Box::new(sphere2));
     scene.add_light(Box::new(PointLight::new(dvec3!(0.0, 60, 60), Color::new(1.0, 1.0, 1.0), 100000.0, (0.0, 0.0, 4.0*PI))));
     scene.ambient_light = AmbientLight::new(Color::WHITE, 1.0);
-    scene.set_background_from_path("assets/images/backgrounds/room.jpg");
+    scene.set_background_from_path("assets/images/backgrounds/room.jpg").unwrap();
 
     println!("after setting background");
     let image = render(scene, image(512, 512), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/background_1");
+    write_to_png( image, "output/background_1").unwrap();
 }
 
 #[test]
@@ -146,10 +146,10 @@ fn many_balls() {
 
     scene.add_light(Box::new(PointLight::new(dvec3!(-100.0, 300.0, 300.0), Color::new(1.0, 1.0, 1.0), 150000.0, (0.0, 0.0, 1.0*PI))));
     scene.ambient_light = AmbientLight::new(Color::WHITE, 0.0);
-    scene.set_background_from_path("assets/images/backgrounds/forest2.jpg");
+    scene.set_background_from_path("assets/images/backgrounds/forest2.jpg").unwrap();
 
     let image = render(scene, image(1920, 1080), camera([0.0, 200.0, 400.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/many_balls");
+    write_to_png( image, "output/many_balls").unwrap();
 }
 
 #[test]
@@ -162,9 +162,9 @@ fn translucent_shadow() {
         scene.root.add_child(floor);
 
         scene.add_light(Box::new(PointLight::new(dvec3!(-100.0, 300.0, -300.0), Color::new(1.0, 1.0, 1.0), 150000.0, (0.0, 0.0, 1.0*PI))));
-        scene.set_background_from_path("assets/images/backgrounds/forest2.jpg");
+        scene.set_background_from_path("assets/images/backgrounds/forest2.jpg").unwrap();
 
         let image = render(scene, image(1920, 1080), camera([0.0, 200.0, 400.0], [0.0, 0.0, 0.0]));
-        write_to_png( image, &format!("output/translucent_shadow_{:02}", i));
+        write_to_png( image, &format!("output/translucent_shadow_{:02}", i)).unwrap();
     }
 }
\ No newline at end of file
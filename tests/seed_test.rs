@@ -0,0 +1,61 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+
+// A sphere on a floor under a square light, so every pixel depends on random numbers
+// for the soft shadow, the anti-aliasing and the bounces off the floor
+fn soft_shadow_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    let mut sphere = SceneNode::new();
+    sphere.set_primitive(Sphere::from_radius(30.0));
+    sphere.set_material(PhongShader::new(Color::new(0.8, 0.2, 0.2), Color::WHITE*0.2, Color::BLACK, 8.0));
+    root.add_child(Box::new(sphere));
+    root.add_child(Box::new(create_wall(400.0, Color::WHITE*0.8, rotation(Axis::X, -90.0) * translation(0.0, 0.0, -30.0))));
+    scene.root = Box::new(root);
+    scene.add_light(SquareLight::new(dvec3!(0.0, 100.0, 0.0), 60.0, Color::WHITE, 200000.0, (1.0, 0.0, 0.0)));
+    scene
+}
+
+fn render_seeded(seed: u32, num_threads: usize, tile_size: u32, config: RenderConfig) -> FrameBuffer {
+    let mut config = config;
+    config.seed = seed;
+    config.num_threads = num_threads;
+    config.tile_size = tile_size;
+    render_framebuffer_with_config(soft_shadow_scene(), image(24, 24), camera([0.0, 40.0, 120.0], [0.0, 0.0, 0.0]), config)
+}
+
+#[test]
+fn seed_makes_renders_repeatable() {
+    let mut config = RenderConfig::default();
+    config.aa_max_samples = 8;
+    let first = render_seeded(3, 8, 8, config.clone());
+    let again = render_seeded(3, 1, 8, config.clone());
+    assert_eq!(first.colors, again.colors);
+    assert_eq!(first.distances, again.distances);
+
+    // Each pixel's samples don't depend on the tile it's in
+    assert_eq!(render_seeded(3, 3, 5, config.clone()).colors, first.colors);
+
+    let other = render_seeded(4, 8, 8, config.clone());
+    assert!(other.colors != first.colors);
+
+    // Samples that spill over into other tiles still add up the same
+    config.filter = PixelFilter::from_kind(FilterKind::Gaussian);
+    config.sampler = SamplerKind::Sobol;
+    assert_eq!(render_seeded(9, 8, 8, config.clone()).colors, render_seeded(9, 2, 5, config).colors);
+}
+
+#[test]
+fn seed_path_tracing_and_progressive() {
+    let mut config = RenderConfig::default();
+    config.integrator = PathIntegrator::new();
+    config.samples = 4;
+    assert_eq!(render_seeded(1, 8, 8, config.clone()).colors, render_seeded(1, 1, 16, config.clone()).colors);
+
+    config.progressive = Some(ProgressiveConfig { samples: 4, samples_per_pass: 2, ..ProgressiveConfig::default() });
+    config.filter = PixelFilter::from_kind(FilterKind::Tent);
+    assert_eq!(render_seeded(1, 8, 8, config.clone()).colors, render_seeded(1, 3, 8, config).colors);
+}
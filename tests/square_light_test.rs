@@ -57,7 +57,7 @@ fn square_light_basic() {
     let mut config = RenderConfig::default();
     config.anti_alias = true;
     let image = render_with_config(scene, image(256, 256), camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]), config);
-    write_to_png( image, "output/square_light_sphere");
+    write_to_png( image, "output/square_light_sphere").unwrap();
 }
 
 #[test]
@@ -85,7 +85,7 @@ fn light_room() {
                 ),
                 geometry_node(
                     translation(150.0, -270.0, 80.0),
-                    texture_phong_material("assets/images/textures/cube_rgb_gradient.png", 0.5, 0.5, 0.0, 4.0),
+                    texture_phong_material("assets/images/textures/cube_rgb_gradient.png", 0.5, 0.5, 0.0, 4.0).unwrap(),
                     Cube::new(160.0),
                     vec!(),
                 ),
@@ -96,7 +96,7 @@ fn light_room() {
     let mut config = RenderConfig::default();
     config.anti_alias = true;
     let image = render_with_config(scene, image(512, 512), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]), config);
-    write_to_png( image, "output/light_room");
+    write_to_png( image, "output/light_room").unwrap();
 }
 
 #[test]
@@ -125,7 +125,7 @@ fn subdivision_light() {
                 ),
                 geometry_node(
                     translation(150.0, -270.0, 80.0),
-                    texture_phong_material("assets/images/textures/cube_rgb_gradient.png", 0.5, 0.5, 0.0, 4.0),
+                    texture_phong_material("assets/images/textures/cube_rgb_gradient.png", 0.5, 0.5, 0.0, 4.0).unwrap(),
                     Cube::new(160.0),
                     vec!(),
                 ),
@@ -137,5 +137,5 @@ fn subdivision_light() {
     let mut config = RenderConfig::default();
     config.anti_alias = false;
     let image = render_with_config(scene, image(256, 256), camera([-300.0, -300.0, 300.0], [300.0, 300.0, -300.0]), config);
-    write_to_png( image, "output/subdivision_light");
+    write_to_png( image, "output/subdivision_light").unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use euler::*;
+use std::path::Path;
+
+// A sphere with nothing else in the scene and no lights, so every ray is easy to count
+fn lone_sphere() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    root.set_primitive(Sphere::from_radius(30.0));
+    root.set_material(slightly_shiney(Color::RED));
+    scene.root = Box::new(root);
+    scene
+}
+
+// A mirror ball, a hollowed out cube and a mesh on a floor under a light
+fn busy_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+
+    let mut ball = SceneNode::new();
+    ball.set_primitive(Sphere::from_radius(20.0));
+    ball.set_material(ReflectionShader::new(Color::WHITE * 0.9));
+    ball.set_transform(translation(-40.0, 0.0, 0.0));
+    root.add_child(Box::new(ball));
+
+    let mut hollow = SceneNode::new();
+    let cube = Box::new(BaseShape::new(DMat4::identity(), Cube::new(30.0)));
+    let hole = Box::new(BaseShape::new(DMat4::identity(), Sphere::from_radius(19.0)));
+    hollow.set_primitive(Box::new(SubtractShape::new(cube, hole)));
+    hollow.set_material(slightly_shiney(Color::new(0.2, 0.6, 0.2)));
+    root.add_child(Box::new(hollow));
+
+    let mut model = SceneNode::new();
+    model.set_primitive(Mesh::from_path(Path::new("assets/models/cube.obj")).unwrap());
+    model.set_material(slightly_shiney(Color::new(0.2, 0.2, 0.6)));
+    model.set_transform(translation(40.0, 0.0, 0.0) * scaling(10.0, 10.0, 10.0));
+    root.add_child(Box::new(model));
+
+    root.add_child(Box::new(create_wall(400.0, Color::WHITE*0.8, rotation(Axis::X, -90.0) * translation(0.0, 0.0, -20.0))));
+    scene.root = Box::new(root);
+    scene.add_light(Box::new(PointLight::new(dvec3!(0.0, 100.0, 50.0), Color::WHITE, 100000.0, (0.0, 0.0, 1.0))));
+    scene
+}
+
+fn render_stats(scene: Scene, config: RenderConfig) -> RenderStats {
+    render_framebuffer_with_stats(scene, image(32, 24), camera([0.0, 30.0, 120.0], [0.0, 0.0, 0.0]), config).1
+}
+
+// Counts leave out the times, which are different every run
+fn counts(stats: RenderStats) -> RenderStats {
+    RenderStats { elapsed: Default::default(), trace_time: Default::default(), anti_alias_time: Default::default(), aov_time: Default::default(), denoise_time: Default::default(), ..stats }
+}
+
+#[test]
+fn stats_count_rays() {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    let stats = render_stats(lone_sphere(), config.clone());
+    assert_eq!(stats.primary_rays, 32 * 24);
+    assert_eq!((stats.secondary_rays, stats.shadow_rays), (0, 0));
+    assert_eq!(stats.intersection_tests(PrimitiveKind::Sphere), 32 * 24);
+    assert_eq!(stats.total_intersection_tests(), 32 * 24);
+    assert_eq!(stats.bvh_node_visits, 0);
+    assert!(stats.elapsed > Default::default() && stats.rays_per_second() > 0.0);
+
+    // Anti-aliasing adds rays, and the time they took goes under their own phase
+    config.anti_alias = true;
+    let stats = render_stats(lone_sphere(), config.clone());
+    assert!(stats.primary_rays >= 32 * 24 * config.aa_min_samples as u64);
+    assert!(stats.anti_alias_time > Default::default());
+
+    // Path tracing sends every sample from the camera
+    config.integrator = PathIntegrator::new();
+    config.samples = 3;
+    assert_eq!(render_stats(lone_sphere(), config).primary_rays, 32 * 24 * 3);
+}
+
+#[test]
+fn stats_count_primitives() {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    let stats = render_stats(busy_scene(), config.clone());
+    assert!(stats.secondary_rays > 0 && stats.shadow_rays > 0);
+    assert_eq!(stats.total_rays(), stats.primary_rays + stats.secondary_rays + stats.shadow_rays);
+
+    // Composite shapes count themselves and the shapes they're made of
+    assert!(stats.intersection_tests(PrimitiveKind::Subtract) > 0);
+    assert!(stats.intersection_tests(PrimitiveKind::Cube) >= stats.intersection_tests(PrimitiveKind::Subtract));
+    assert!(stats.intersection_tests(PrimitiveKind::Sphere) > stats.intersection_tests(PrimitiveKind::Subtract));
+    assert!(stats.intersection_tests(PrimitiveKind::Mesh) > 0 && stats.intersection_tests(PrimitiveKind::Rectangle) > 0);
+    assert_eq!(stats.intersection_tests(PrimitiveKind::Other), 0);
+    assert!(stats.bvh_node_visits >= stats.intersection_tests(PrimitiveKind::Mesh));
+    assert!(stats.mesh_triangle_tests > 0);
+
+    let report = stats.report();
+    assert!(report.contains("rays: ") && report.contains("subtract ") && report.contains("node visits"), "{}", report);
+
+    // The work done doesn't depend on how it's split between threads
+    config.num_threads = 1;
+    config.tile_size = 5;
+    assert_eq!(counts(render_stats(busy_scene(), config.clone())), counts(stats));
+
+    // AOV lookups aren't part of the render's work
+    config.aovs = AovConfig::all();
+    assert_eq!(counts(render_stats(busy_scene(), config)), counts(stats));
+}
+
+#[test]
+fn stats_progressive() {
+    let mut config = RenderConfig::default();
+    config.integrator = PathIntegrator::new();
+    config.denoise = Some(DenoiseConfig::default());
+    let progressive = ProgressiveConfig { samples: 4, samples_per_pass: 2, ..ProgressiveConfig::default() };
+    let mut passes = Vec::new();
+    render_progressive(lone_sphere(), image(32, 24), camera([0.0, 30.0, 120.0], [0.0, 0.0, 0.0]), config.clone(), progressive, |_, status| {
+        passes.push(status.stats);
+    });
+    assert_eq!(passes.len(), 2);
+    assert_eq!(passes[0].primary_rays, 32 * 24 * 2);
+    assert_eq!(passes[1].primary_rays, 32 * 24 * 4);
+    assert!(passes[0].denoise_time == Default::default() && passes[1].denoise_time > Default::default());
+
+    // Stats come back the same way through render_framebuffer_with_stats
+    config.progressive = Some(progressive);
+    assert_eq!(render_stats(lone_sphere(), config).primary_rays, 32 * 24 * 4);
+}
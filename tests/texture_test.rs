@@ -40,22 +40,22 @@ fn texture_room() {
             DMat4::identity(),
             vec!(
                 create_room_from_material(700.0, RoomMaterialScheme {
-                    ceiling: texture_phong_material("assets/images/textures/granite.jpg", 1.0, 0.0, 0.0, 2.0),
-                    floor: texture_phong_material("assets/images/textures/wood_boards.jpg", 1.0, 0.0, 0.0, 1.0),
-                    front: texture_phong_material("assets/images/textures/brick_wall.jpg", 1.0, 0.0, 0.0, 1.0),
+                    ceiling: texture_phong_material("assets/images/textures/granite.jpg", 1.0, 0.0, 0.0, 2.0).unwrap(),
+                    floor: texture_phong_material("assets/images/textures/wood_boards.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
+                    front: texture_phong_material("assets/images/textures/brick_wall.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
                     back: default_material(Color::CYAN),
-                    left: texture_phong_material("assets/images/textures/orange_leather.jpg", 1.0, 0.0, 0.0, 1.0),
+                    left: texture_phong_material("assets/images/textures/orange_leather.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
                     right: ReflectionShader::new(Color::WHITE),
                 }),
                 geometry_node(
                     translation(-150.0, -270.0, 0.0),
-                    texture_phong_material("assets/images/textures/orange_leather.jpg", 1.0, 0.0, 0.0, 1.0),
+                    texture_phong_material("assets/images/textures/orange_leather.jpg", 1.0, 0.0, 0.0, 1.0).unwrap(),
                     Sphere::from_radius(80.0),
                     vec!(),
                 ),
                 geometry_node(
                     translation(150.0, -270.0, 80.0),
-                    texture_phong_material("assets/images/textures/cube_rgb_gradient.png", 0.5, 0.5, 0.0, 4.0),
+                    texture_phong_material("assets/images/textures/cube_rgb_gradient.png", 0.5, 0.5, 0.0, 4.0).unwrap(),
                     Cube::new(160.0),
                     vec!(),
                 ),
@@ -64,7 +64,7 @@ fn texture_room() {
     );
 
     let image = render(scene, image(5000, 5000), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/texture_room");
+    write_to_png( image, "output/texture_room").unwrap();
 }
 
 #[test]
@@ -86,7 +86,7 @@ fn texture_cube() {
                 }),
                 geometry_node(
                     translation(0.0, 0.0, 0.0),
-                    texture_phong_material("assets/images/textures/cube_rgb_numbers.png", 0.5, 0.5, 0.0, 4.0),
+                    texture_phong_material("assets/images/textures/cube_rgb_numbers.png", 0.5, 0.5, 0.0, 4.0).unwrap(),
                     Cube::new(160.0),
                     vec!(),
                 ),
@@ -95,7 +95,7 @@ fn texture_cube() {
     );
 
     let image = render(scene, image(1920, 1080), camera([-310.0, 300.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/texture_cube");
+    write_to_png( image, "output/texture_cube").unwrap();
 }
 
 #[test]
@@ -103,7 +103,7 @@ fn skybox_test() {
     let scene = build_scene(
         vec!(),
         no_ambient(),
-        Some(SkyBox::from_path("assets/images/living_room.jpg", DMat4::identity())),
+        Some(SkyBox::from_path("assets/images/living_room.jpg", DMat4::identity()).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(),
@@ -111,7 +111,7 @@ fn skybox_test() {
     );
 
     let image = render(scene, image(512, 512), camera([-310.0, 300.0, 300.0], [0.0, 0.0, 0.0]));
-    write_to_png( image, "output/skybox_test");
+    write_to_png( image, "output/skybox_test").unwrap();
 }
 
 #[test]
@@ -124,19 +124,19 @@ fn correlated_texture() {
             DMat4::identity(),
             vec!(
                 create_room_from_material(700.0, RoomMaterialScheme {
-                    ceiling: texture_phong_material("assets/images/tiny.png", 4.0, 6.0, 0.0, 2.0),
-                    floor: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0),
-                    front: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0),
+                    ceiling: texture_phong_material("assets/images/tiny.png", 4.0, 6.0, 0.0, 2.0).unwrap(),
+                    floor: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0).unwrap(),
+                    front: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0).unwrap(),
                     back: default_material(Color::CYAN),
-                    left: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0),
-                    right: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0),
+                    left: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0).unwrap(),
+                    right: texture_phong_material("assets/images/tiny.png", 1.0, 0.0, 0.0, 1.0).unwrap(),
                 }),
             ),
         ),
     );
 
     let image = render(scene, image(512, 512), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/correlated_texture");
+    write_to_png( image, "output/correlated_texture").unwrap();
 }
 
 #[test]
@@ -150,7 +150,7 @@ fn plane_texture() {
             vec!(
                 geometry_node(
                     translation(0.0, 0.0, 0.0),
-                    texture_phong_material("assets/images/textures/keanu.jpg", 1.0, 0.0, 0.0, 4.0),
+                    texture_phong_material("assets/images/textures/keanu.jpg", 1.0, 0.0, 0.0, 4.0).unwrap(),
                     Plane::with_tangent(dvec3!(0.0, 0.0, 0.0), dvec3!(0.0, 0.0, 1.0), dvec3!(0.0, 1.0, 0.0)),
                     vec!(),
                 ),
@@ -159,5 +159,5 @@ fn plane_texture() {
     );
 
     let image = render(scene, image(512, 512), camera([0.0, 0.0, 1.0], [1.0, 0.0, 1.0]));
-    write_to_png( image, "output/plane_texture");
+    write_to_png( image, "output/plane_texture").unwrap();
 }
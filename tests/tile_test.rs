@@ -0,0 +1,198 @@
+extern crate raytracer;
+extern crate euler;
+
+use raytracer::*;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use common::sphere_scene;
+
+mod common;
+
+fn render_sphere(config: RenderConfig) -> FrameBuffer {
+    render_framebuffer_with_config(sphere_scene(), image(40, 30), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config)
+}
+
+fn sphere_fingerprint(config: &RenderConfig) -> u64 {
+    render_fingerprint(&sphere_scene(), image(40, 30), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), config).unwrap()
+}
+
+fn tiled(tile_size: u32, tile_order: TileOrder, checkpoint: Option<&str>) -> RenderConfig {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.tile_size = tile_size;
+    config.tile_order = tile_order;
+    config.checkpoint = checkpoint.map(String::from);
+    config
+}
+
+#[test]
+fn tile_orders_cover_image() {
+    let dimension = ImageDimension::new(100, 70);
+    for order in TileOrder::ALL.iter() {
+        let tiles = image_tiles(dimension, 32, *order);
+        assert_eq!(tiles.len(), 4 * 3);
+        let mut covered = vec![0; dimension.area() as usize];
+        for tile in tiles.iter() {
+            assert!(tile.width <= 32 && tile.height <= 32);
+            for (x, y) in tile.pixels() {
+                covered[(y * 100 + x) as usize] += 1;
+            }
+        }
+        assert!(covered.iter().all(|&count| count == 1), "{} tiles overlap or leave gaps", order.name());
+    }
+
+    assert_eq!(image_tiles(dimension, 32, TileOrder::Scanline)[1], Tile::new(32, 0, 32, 32));
+    assert_eq!(image_tiles(dimension, 32, TileOrder::Scanline)[11], Tile::new(96, 64, 4, 6));
+    // Starts from the middle and works its way out
+    assert_eq!(image_tiles(dimension, 32, TileOrder::Spiral)[0], Tile::new(32, 32, 32, 32));
+    assert_eq!(image_tiles(ImageDimension::new(160, 160), 32, TileOrder::Spiral)[0], Tile::new(64, 64, 32, 32));
+
+    // Every tile along a Hilbert curve is next to the one before it
+    let hilbert = image_tiles(ImageDimension::new(256, 256), 32, TileOrder::Hilbert);
+    assert_eq!(hilbert.len(), 64);
+    for pair in hilbert.windows(2) {
+        let distance = (pair[0].x as i64 - pair[1].x as i64).abs() + (pair[0].y as i64 - pair[1].y as i64).abs();
+        assert_eq!(distance, 32);
+    }
+
+    assert_eq!(TileOrder::from_name("hilbert"), Some(TileOrder::Hilbert));
+    assert_eq!(TileOrder::default(), TileOrder::Spiral);
+}
+
+#[test]
+fn tile_order_does_not_change_image() {
+    let scanline = render_sphere(tiled(7, TileOrder::Scanline, None));
+    let spiral = render_sphere(tiled(16, TileOrder::Spiral, None));
+    let hilbert = render_sphere(tiled(64, TileOrder::Hilbert, None));
+    assert_eq!(scanline.colors, spiral.colors);
+    assert_eq!(scanline.colors, hilbert.colors);
+    assert_eq!(scanline.distances, hilbert.distances);
+    assert!(scanline.colors[scanline.index(19, 14)].red > 0.0);
+}
+
+#[test]
+fn tile_checkpoint_resume() {
+    let path = "output/tile_checkpoint_resume.checkpoint";
+    let _ = fs::remove_file(path);
+    let dimension = ImageDimension::new(40, 30);
+    let expected = render_sphere(tiled(16, TileOrder::Spiral, None));
+    let fingerprint = sphere_fingerprint(&tiled(16, TileOrder::Spiral, None));
+
+    // Pretend an earlier run got as far as one tile, then crashed halfway through writing the next
+    let done = Tile::new(16, 16, 16, 14);
+    {
+        let mut checkpoint = Checkpoint::open(path, dimension, PixelFilter::default(), fingerprint).unwrap();
+        let mut film = FilmTile::from_region(done);
+        film.pixels = vec![FilmPixel::sample(Color::new(0.0, 1.0, 0.0), 1.0); done.area() as usize];
+        checkpoint.record(&RenderedTile { tile: done, distances: vec![10.0; done.area() as usize], film }).unwrap();
+    }
+    OpenOptions::new().append(true).open(path).unwrap().write_all(&[16, 0, 0, 0, 0, 0]).unwrap();
+    assert_eq!(Checkpoint::open(path, dimension, PixelFilter::default(), fingerprint).unwrap().tiles().len(), 1);
+
+    let resumed = render_sphere(tiled(16, TileOrder::Spiral, Some(path)));
+    for y in 0..30 {
+        for x in 0..40 {
+            let i = resumed.index(x, y);
+            if (16..32).contains(&x) && y >= 16 {
+                assert_eq!(resumed.colors[i], Color::new(0.0, 1.0, 0.0));
+                assert_eq!(resumed.distances[i], 10.0);
+            }
+            else {
+                assert_eq!(resumed.colors[i], expected.colors[i]);
+            }
+        }
+    }
+
+    // Finished renders clean up after themselves
+    assert!(!Path::new(path).exists());
+}
+
+// Has no scene file description, so renders with it can't be fingerprinted
+#[derive(Clone)]
+struct UnnamedIntegrator;
+
+impl Integrator for UnnamedIntegrator {
+    fn trace(&self, _scene: &Scene, _ray: Ray) -> (f64, Color) {
+        (f64::INFINITY, Color::BLACK)
+    }
+}
+
+// Opens the checkpoint at path after an earlier render left a tile in it, and whether that tile was kept
+fn reopen_checkpoint(path: &str, dimension: ImageDimension, filter: PixelFilter, fingerprint: u64) -> bool {
+    let old = format!("{}.old", path);
+    let _ = fs::remove_file(&old);
+    let checkpoint = Checkpoint::open(path, dimension, filter, fingerprint).unwrap();
+    let kept = !checkpoint.tiles().is_empty();
+    assert_eq!(checkpoint.replaced(), if kept { None } else { Some(old.as_str()) });
+    assert_eq!(Path::new(&old).exists(), !kept);
+    kept
+}
+
+#[test]
+fn tile_checkpoint_replaced() {
+    let path = "output/tile_checkpoint_replaced.checkpoint";
+    let _ = fs::remove_file(path);
+    let config = tiled(16, TileOrder::Spiral, Some(path));
+    let fingerprint = sphere_fingerprint(&config);
+    let dimension = ImageDimension::new(40, 30);
+    let record = |dimension: ImageDimension, filter: PixelFilter, fingerprint: u64| {
+        let _ = fs::remove_file(path);
+        let tile = Tile::new(0, 0, 16, 16);
+        let rendered = RenderedTile { tile, distances: vec![1.0; 256], film: FilmTile::new(tile, filter, dimension) };
+        Checkpoint::open(path, dimension, filter, fingerprint).unwrap().record(&rendered).unwrap();
+    };
+
+    // Checkpoints of other renders are moved out of the way, not added to
+    record(dimension, PixelFilter::default(), fingerprint);
+    assert!(reopen_checkpoint(path, dimension, PixelFilter::default(), fingerprint));
+    assert!(!reopen_checkpoint(path, ImageDimension::new(30, 40), PixelFilter::default(), fingerprint));
+    record(dimension, PixelFilter::default(), fingerprint);
+    assert!(!reopen_checkpoint(path, dimension, PixelFilter::from_kind(FilterKind::Gaussian), fingerprint));
+
+    // Only settings that change the image count, and so does the scene
+    let mut other_depth = config.clone();
+    other_depth.recursion_depth += 1;
+    record(dimension, PixelFilter::default(), fingerprint);
+    assert!(!reopen_checkpoint(path, dimension, PixelFilter::default(), sphere_fingerprint(&other_depth)));
+    let mut other_threads = tiled(16, TileOrder::Hilbert, Some("elsewhere.checkpoint"));
+    other_threads.num_threads += 3;
+    other_threads.aovs = AovConfig::all();
+    other_threads.denoise = Some(DenoiseConfig::default());
+    assert_eq!(sphere_fingerprint(&other_threads), fingerprint);
+    let mut other_scene = sphere_scene();
+    other_scene.ambient_light = AmbientLight::new(Color::WHITE, 0.5);
+    assert!(render_fingerprint(&other_scene, image(40, 30), camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), &config).unwrap() != fingerprint);
+
+    fs::write(path, b"not a checkpoint").unwrap();
+    assert!(!reopen_checkpoint(path, dimension, PixelFilter::default(), fingerprint));
+
+    // The render after a replaced checkpoint is checkpointed like any other
+    record(dimension, PixelFilter::default(), sphere_fingerprint(&other_depth));
+    let framebuffer = render_sphere(config);
+    assert!(framebuffer.colors[framebuffer.index(19, 14)].red > 0.0);
+    assert!(!Path::new(path).exists());
+    assert!(Path::new(&format!("{}.old", path)).exists());
+}
+
+#[test]
+fn tile_checkpoint_errors() {
+    let dimension = ImageDimension::new(40, 30);
+    let config = tiled(16, TileOrder::Spiral, Some("output/tile_checkpoint_errors.checkpoint"));
+
+    // Somewhere that can't be read or written fails, rather than rendering without a checkpoint
+    assert!(Checkpoint::open("output", dimension, PixelFilter::default(), sphere_fingerprint(&config)).is_err());
+
+    // So does a render that can't be fingerprinted
+    let mut unnamed = config.clone();
+    unnamed.integrator = Box::new(UnnamedIntegrator);
+    let err = open_checkpoint(&sphere_scene(), dimension, camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), &unnamed).err().unwrap();
+    assert!(err.to_string().contains("can't be checkpointed"), "{}", err);
+
+    // Renders that aren't checkpointed don't need to be
+    unnamed.crop = Some(Tile::new(0, 0, 8, 8));
+    assert!(open_checkpoint(&sphere_scene(), dimension, camera([0.0, 0.0, 200.0], [0.0, 0.0, 0.0]), &unnamed).unwrap().is_none());
+    let mut progressive = config;
+    progressive.progressive = Some(ProgressiveConfig::default());
+    assert!(progressive.check().is_err());
+}
@@ -0,0 +1,80 @@
+extern crate raytracer;
+extern crate euler;
+extern crate image;
+
+use raytracer::*;
+use euler::*;
+use image::RgbImage;
+
+// A floor lit up by a square light that's much too bright for a hard clamp
+fn blown_out_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mut root = SceneNode::new();
+    let mut floor = SceneNode::new();
+    floor.set_primitive(Rectangle::new(400.0, 400.0));
+    floor.set_material(PhongShader::new(Color::new(0.9, 0.6, 0.3), Color::BLACK, Color::BLACK, 1.0));
+    floor.set_transform(rotation(Axis::X, -90.0));
+    root.add_child(Box::new(floor));
+    scene.root = Box::new(root);
+    scene.add_light(SquareLight::new(dvec3!(0.0, 100.0, 0.0), 20.0, Color::WHITE, 300000.0, (0.0, 0.0, 4.0*std::f64::consts::PI)));
+    scene
+}
+
+fn render_blown_out(tone_mapping: ToneMapping) -> RgbImage {
+    let mut config = RenderConfig::default();
+    config.anti_alias = false;
+    config.tone_mapping = tone_mapping;
+    render_with_config(blown_out_scene(), image(64, 64), camera([0.0, 150.0, 150.0], [0.0, 0.0, 0.0]), config)
+}
+
+fn clipped_pixels(image: &RgbImage) -> usize {
+    image.pixels().filter(|pixel| pixel.data.contains(&255)).count()
+}
+
+#[test]
+fn tone_map_operators() {
+    let bright = Color::new(2.0, 1.5, 1.0);
+    let dim = Color::new(0.2, 0.1, 0.05);
+
+    let clamp = ToneMapping::default();
+    assert_eq!(clamp.apply(dim), dim);
+    assert_eq!(clamp.apply(bright), Color::WHITE);
+    assert_eq!(ToneMapping::new(ToneMapOperator::Clamp, 1.0).apply(dim), dim * 2.0);
+    assert_eq!(ToneMapping::new(ToneMapOperator::Clamp, -1.0).apply(dim), dim * 0.5);
+
+    // Reinhard keeps the hue of highlights instead of turning them white
+    let reinhard = ToneMapping::new(ToneMapOperator::Reinhard, 0.0).apply(bright);
+    assert!(reinhard.red < 1.0);
+    assert!((reinhard.red / reinhard.blue - 2.0).abs() < 1e-9);
+    assert!((reinhard.green / reinhard.blue - 1.5).abs() < 1e-9);
+    let reinhard_brighter = ToneMapping::new(ToneMapOperator::Reinhard, 0.0).apply(bright * 1.5);
+    assert!(reinhard_brighter.green > reinhard.green && reinhard_brighter.green < 1.0);
+    assert_eq!(ToneMapping::new(ToneMapOperator::Reinhard, 0.0).apply(Color::BLACK), Color::BLACK);
+
+    let aces = ToneMapping::new(ToneMapOperator::Aces, 0.0);
+    assert_eq!(aces.apply(Color::BLACK), Color::BLACK);
+    assert!(aces.apply(Color::from_f64(0.5)).red < aces.apply(Color::from_f64(2.0)).red);
+    assert!(aces.apply(Color::from_f64(1000.0)).red <= 1.0);
+
+    for operator in ToneMapOperator::ALL.iter() {
+        assert_eq!(ToneMapOperator::from_name(operator.name()), Some(*operator));
+    }
+}
+
+#[test]
+fn tone_map_highlights() {
+    let clamped = render_blown_out(ToneMapping::default());
+    let reinhard = render_blown_out(ToneMapping::new(ToneMapOperator::Reinhard, 0.0));
+    let aces = render_blown_out(ToneMapping::new(ToneMapOperator::Aces, 0.0));
+    let darker = render_blown_out(ToneMapping::new(ToneMapOperator::Clamp, -3.0));
+
+    assert!(clipped_pixels(&clamped) > 0, "scene should be too bright to clamp");
+    assert!(clipped_pixels(&reinhard) < clipped_pixels(&clamped));
+    assert!(clipped_pixels(&aces) < clipped_pixels(&clamped));
+    assert!(clipped_pixels(&darker) < clipped_pixels(&clamped));
+
+    write_to_png(clamped, "output/tone_map_clamp").unwrap();
+    write_to_png(reinhard, "output/tone_map_reinhard").unwrap();
+    write_to_png(aces, "output/tone_map_aces").unwrap();
+    write_to_png(darker, "output/tone_map_exposure").unwrap();
+}
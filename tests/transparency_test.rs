@@ -56,7 +56,7 @@ fn transparent_shapes() {
     );
 
     let image = render(scene, image(512, 512), camera([-300.0, 0.0, 300.0], [350.0, -350.0, -350.0]));
-    write_to_png( image, "output/transparent_shapes");
+    write_to_png( image, "output/transparent_shapes").unwrap();
 }
 
 #[test]
@@ -64,7 +64,7 @@ fn transparent_orb() {
     let scene = build_scene(
         vec!(light1(), light2(), light3()),
         no_ambient(),
-        Some(SkyBox::from_path("assets/images/backgrounds/sky_ocean.jpg", rotation(Axis::Y, 20.0))),
+        Some(SkyBox::from_path("assets/images/backgrounds/sky_ocean.jpg", rotation(Axis::Y, 20.0)).unwrap()),
         scene_node(
             DMat4::identity(),
             vec!(
@@ -79,5 +79,5 @@ fn transparent_orb() {
     );
 
     let image = render(scene, image(512, 512), camera([0.0, 0.0, 300.0], [0.0, 0.0, -0.0]));
-    write_to_png( image, "output/transparent_shapes");
+    write_to_png( image, "output/transparent_shapes").unwrap();
 }
\ No newline at end of file